@@ -1,33 +1,12 @@
 use anyhow::{Context, Result};
 use boreal_lib::media_processor::{self, SystemTranscoder};
+use boreal_lib::pricing::*;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use std::time::Instant;
 
-/// === STORAGE COSTS (USD per GB per month) ===
-const COST_S3_STANDARD: f64 = 0.023;
-const COST_GLACIER_DEEP: f64 = 0.00099;
-const COST_S3_INSTANT: f64 = 0.004;  // Glacier Instant Retrieval
-
-/// === TRANSITION COSTS (one-time, USD per GB) ===
-/// Lifecycle transition from Standard to Glacier tiers
-const COST_TRANSITION_TO_GLACIER: f64 = 0.02;
-
-/// === PUT REQUEST COSTS (USD per 1,000 requests) ===
-const COST_PUT_STANDARD: f64 = 0.005;
-const COST_PUT_GLACIER_IR: f64 = 0.02;
-const COST_PUT_DEEP_ARCHIVE: f64 = 0.05;
-
-/// === RETRIEVAL COSTS (USD per GB) ===
-/// Glacier Instant Retrieval - immediate access
-const COST_RETRIEVE_GLACIER_IR: f64 = 0.03;
-/// Deep Archive Standard - 12 hour retrieval
-const COST_RETRIEVE_DA_STANDARD: f64 = 0.01;
-/// Deep Archive Bulk - 48 hour retrieval (cheapest)
-const COST_RETRIEVE_DA_BULK: f64 = 0.0025;
-
 #[tokio::main]
 async fn main() -> Result<()> {
     log::info!("Starting Compression Benchmark & Cost Analysis...");
@@ -184,18 +163,18 @@ async fn run_benchmarks(test_dir: &Path) -> Result<Vec<BenchmarkResult>> {
                 // Simulate Frontend Frame Extraction
                 let frames = extract_simulation_frames(&path).ok();
 
-                let p = media_processor::process_video(&transcoder, &path, &output, frames).await;
+                let p = media_processor::process_video(&transcoder, &path, &output, frames, Default::default()).await;
                 fs::remove_file(output).ok();
                 (p, "Video")
             }
             "ogg" | "mp3" | "wav" | "flac" | "m4a" | "aac" => {
                 let output = test_dir.join(format!("output_{}.opus", file_name));
-                let p = media_processor::process_audio(&transcoder, &path, &output).await;
+                let p = media_processor::process_audio(&transcoder, &path, &output, Default::default()).await;
                 fs::remove_file(output).ok();
                 (p, "Audio")
             }
             "jpg" | "jpeg" | "png" | "webp" | "bmp" | "tiff" | "tif" | "gif" => {
-                let p = media_processor::process_image(&transcoder, &path).await;
+                let p = media_processor::process_image(&transcoder, &path, Default::default()).await;
                 (p, "Image")
             }
             _ => {
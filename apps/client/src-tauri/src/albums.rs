@@ -0,0 +1,518 @@
+use crate::AppState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+/// Order of photos inside an album
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AlbumSortMode {
+    /// User-defined order (drag and drop)
+    #[default]
+    Manual,
+    DateAsc,
+    DateDesc,
+    AddedAsc,
+    AddedDesc,
+    Filename,
+}
+
+impl AlbumSortMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            AlbumSortMode::Manual => "manual",
+            AlbumSortMode::DateAsc => "date_asc",
+            AlbumSortMode::DateDesc => "date_desc",
+            AlbumSortMode::AddedAsc => "added_asc",
+            AlbumSortMode::AddedDesc => "added_desc",
+            AlbumSortMode::Filename => "filename",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "date_asc" => AlbumSortMode::DateAsc,
+            "date_desc" => AlbumSortMode::DateDesc,
+            "added_asc" => AlbumSortMode::AddedAsc,
+            "added_desc" => AlbumSortMode::AddedDesc,
+            "filename" => AlbumSortMode::Filename,
+            _ => AlbumSortMode::Manual,
+        }
+    }
+
+    fn order_by(self) -> &'static str {
+        match self {
+            AlbumSortMode::Manual => "ai.position ASC",
+            AlbumSortMode::DateAsc => "COALESCE(p.captured_at, p.created_at) ASC",
+            AlbumSortMode::DateDesc => "COALESCE(p.captured_at, p.created_at) DESC",
+            AlbumSortMode::AddedAsc => "ai.added_at ASC",
+            AlbumSortMode::AddedDesc => "ai.added_at DESC",
+            AlbumSortMode::Filename => "p.filename COLLATE NOCASE ASC",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Album {
+    pub id: String,
+    pub name: String,
+    /// Explicit cover, or the first photo of the album when unset
+    pub cover_photo_id: Option<String>,
+    pub sort_mode: AlbumSortMode,
+    pub created_at: String,
+    pub updated_at: String,
+    pub item_count: u32,
+    /// Size of the originals in the album
+    pub total_size_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct UpdateAlbumPayload {
+    pub name: Option<String>,
+    /// Set to an empty string to fall back to the first photo
+    pub cover_photo_id: Option<String>,
+    pub sort_mode: Option<AlbumSortMode>,
+}
+
+/// Bump the album's updated_at; manifest merges compare it to pick the winning side
+fn touch_album(conn: &Connection, id: &str) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE albums SET updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            rusqlite::params![chrono::Utc::now().to_rfc3339(), id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Album not found".to_string());
+    }
+    Ok(())
+}
+
+/// Photo IDs of an album in its display order, excluding trashed photos
+fn album_photo_ids(conn: &Connection, id: &str, sort_mode: AlbumSortMode) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT ai.photo_id FROM album_items ai
+             JOIN photos p ON p.id = ai.photo_id
+             WHERE ai.album_id = ?1 AND p.deleted_at IS NULL
+             ORDER BY {}, ai.photo_id",
+            sort_mode.order_by()
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map([id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(ids)
+}
+
+fn load_album(conn: &Connection, id: &str) -> Result<Album, String> {
+    let albums = query_albums(conn, Some(id))?;
+    albums.into_iter().next().ok_or_else(|| "Album not found".to_string())
+}
+
+fn query_albums(conn: &Connection, id: Option<&str>) -> Result<Vec<Album>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.id, a.name, a.cover_photo_id, a.sort_mode, a.created_at, a.updated_at,
+                    COUNT(p.id), CAST(COALESCE(SUM(p.size_bytes), 0) AS INTEGER)
+             FROM albums a
+             LEFT JOIN album_items ai ON ai.album_id = a.id
+             LEFT JOIN photos p ON p.id = ai.photo_id AND p.deleted_at IS NULL
+             WHERE a.deleted_at IS NULL AND (?1 IS NULL OR a.id = ?1)
+             GROUP BY a.id
+             ORDER BY a.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([id], |row| {
+            Ok(Album {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                cover_photo_id: row.get(2)?,
+                sort_mode: AlbumSortMode::parse(&row.get::<_, String>(3)?),
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+                item_count: row.get(6)?,
+                total_size_bytes: row.get::<_, i64>(7)? as u64,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Album>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Fall back to the first photo for albums without an explicit cover
+    let mut albums = rows;
+    for album in albums.iter_mut().filter(|a| a.cover_photo_id.is_none()) {
+        album.cover_photo_id = album_photo_ids(conn, &album.id, album.sort_mode)?
+            .into_iter()
+            .next();
+    }
+    Ok(albums)
+}
+
+/// Insert an empty album and return its ID
+pub(crate) fn insert_album(conn: &Connection, name: &str) -> Result<String, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    conn.execute(
+        "INSERT INTO albums (id, name, sort_mode, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+        rusqlite::params![id, name, AlbumSortMode::Manual.as_str(), now],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Append photos to the end of an album, leaving photos already in it in place
+fn append_photos(conn: &Connection, id: &str, photo_ids: &[String]) -> Result<(), String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut position: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(position), -1) FROM album_items WHERE album_id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "INSERT OR IGNORE INTO album_items (album_id, photo_id, position, added_at)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .map_err(|e| e.to_string())?;
+
+    for photo_id in photo_ids {
+        position += 1;
+        stmt.execute(rusqlite::params![id, photo_id, position, now])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Add a photo at a given position, e.g. once an imported photo finished uploading
+pub(crate) fn insert_photo_at(conn: &Connection, id: &str, photo_id: &str, position: i64) -> Result<(), String> {
+    touch_album(conn, id)?;
+    conn.execute(
+        "INSERT OR IGNORE INTO album_items (album_id, photo_id, position, added_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![id, photo_id, position, chrono::Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn create_album(app: AppHandle, state: State<'_, AppState>, name: String) -> Result<Album, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        let id = insert_album(conn, &name)?;
+        load_album(conn, &id)?
+    };
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(album)
+}
+
+#[tauri::command]
+pub async fn get_albums(state: State<'_, AppState>) -> Result<Vec<Album>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    query_albums(conn, None)
+}
+
+/// Photo IDs of an album, in the album's sort order
+#[tauri::command]
+pub async fn get_album_items(state: State<'_, AppState>, id: String) -> Result<Vec<String>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    let album = load_album(conn, &id)?;
+    album_photo_ids(conn, &id, album.sort_mode)
+}
+
+fn apply_update(conn: &Connection, id: &str, payload: &UpdateAlbumPayload) -> Result<(), String> {
+    // Fails for deleted albums before anything is written
+    touch_album(conn, id)?;
+
+    if let Some(name) = &payload.name {
+        conn.execute("UPDATE albums SET name = ?1 WHERE id = ?2", [name, id])
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(cover) = &payload.cover_photo_id {
+        let cover = Some(cover).filter(|c| !c.is_empty());
+        conn.execute(
+            "UPDATE albums SET cover_photo_id = ?1 WHERE id = ?2",
+            rusqlite::params![cover, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(sort_mode) = payload.sort_mode {
+        conn.execute(
+            "UPDATE albums SET sort_mode = ?1 WHERE id = ?2",
+            [sort_mode.as_str(), id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_album(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    payload: UpdateAlbumPayload,
+) -> Result<Album, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        apply_update(conn, &id, &payload)?;
+        load_album(conn, &id)?
+    };
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(album)
+}
+
+/// Delete an album (photos stay in the vault). The row is kept as a tombstone for sync.
+#[tauri::command]
+pub async fn delete_album(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<(), String> {
+    {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        // Deleting again would bump the tombstone's timestamps and sync for nothing
+        let now = chrono::Utc::now().to_rfc3339();
+        let deleted = conn
+            .execute(
+                "UPDATE albums SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                rusqlite::params![now, id],
+            )
+            .map_err(|e| e.to_string())?;
+        if deleted == 0 {
+            return Err("Album not found".to_string());
+        }
+        conn.execute("DELETE FROM album_items WHERE album_id = ?1", [&id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(())
+}
+
+/// Append photos to the end of an album. Photos already in it are left in place.
+#[tauri::command]
+pub async fn add_to_album(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    photo_ids: Vec<String>,
+) -> Result<Album, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        touch_album(conn, &id)?;
+        append_photos(conn, &id, &photo_ids)?;
+        load_album(conn, &id)?
+    };
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(album)
+}
+
+#[tauri::command]
+pub async fn remove_from_album(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    photo_ids: Vec<String>,
+) -> Result<Album, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        touch_album(conn, &id)?;
+
+        let mut stmt = conn
+            .prepare("DELETE FROM album_items WHERE album_id = ?1 AND photo_id = ?2")
+            .map_err(|e| e.to_string())?;
+        for photo_id in &photo_ids {
+            stmt.execute([&id, photo_id]).map_err(|e| e.to_string())?;
+        }
+
+        // Clear a cover that is no longer part of the album
+        conn.execute(
+            "UPDATE albums SET cover_photo_id = NULL
+             WHERE id = ?1 AND cover_photo_id NOT IN (SELECT photo_id FROM album_items WHERE album_id = ?1)",
+            [&id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        load_album(conn, &id)?
+    };
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(album)
+}
+
+/// Renumber every item of an album (trashed photos included) with `photo_ids` first
+fn apply_order(conn: &Connection, id: &str, photo_ids: Vec<String>) -> Result<(), String> {
+    touch_album(conn, id)?;
+
+    // All items in their current manual order, so trashed photos keep their place
+    // relative to the others once restored
+    let existing: Vec<String> = {
+        let mut stmt = conn
+            .prepare("SELECT photo_id FROM album_items WHERE album_id = ?1 ORDER BY position, photo_id")
+            .map_err(|e| e.to_string())?;
+        let ids = stmt
+            .query_map([id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| e.to_string())?;
+        ids
+    };
+
+    let mut ordered = photo_ids;
+    let listed: std::collections::HashSet<String> = ordered.iter().cloned().collect();
+    ordered.extend(existing.into_iter().filter(|p| !listed.contains(p)));
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for (position, photo_id) in ordered.iter().enumerate() {
+        tx.execute(
+            "UPDATE album_items SET position = ?1 WHERE album_id = ?2 AND photo_id = ?3",
+            rusqlite::params![position as i64, id, photo_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.execute(
+        "UPDATE albums SET sort_mode = ?1 WHERE id = ?2",
+        [AlbumSortMode::Manual.as_str(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Set the manual order of an album and switch it to manual sorting.
+/// Photos missing from `photo_ids` keep their relative order after the listed ones.
+#[tauri::command]
+pub async fn reorder_album(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    photo_ids: Vec<String>,
+) -> Result<Album, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        apply_order(conn, &id, photo_ids)?;
+        load_album(conn, &id)?
+    };
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(album)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest;
+
+    fn setup() -> (Connection, String) {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        for (id, captured, filename) in [
+            ("p1", "2024-03-01T10:00:00+00:00", "c.jpg"),
+            ("p2", "2024-01-01T10:00:00+00:00", "a.jpg"),
+            ("p3", "2024-02-01T10:00:00+00:00", "b.jpg"),
+        ] {
+            conn.execute(
+                "INSERT INTO photos (id, filename, s3_key, tier, captured_at) VALUES (?1, ?2, 'k', 'Standard', ?3)",
+                [id, filename, captured],
+            )
+            .unwrap();
+        }
+        let id = insert_album(&conn, "Trip").unwrap();
+        append_photos(&conn, &id, &["p1".to_string(), "p2".to_string(), "p3".to_string()]).unwrap();
+        (conn, id)
+    }
+
+    #[test]
+    fn test_sort_modes() {
+        let (conn, id) = setup();
+        assert_eq!(album_photo_ids(&conn, &id, AlbumSortMode::Manual).unwrap(), ["p1", "p2", "p3"]);
+        assert_eq!(album_photo_ids(&conn, &id, AlbumSortMode::DateAsc).unwrap(), ["p2", "p3", "p1"]);
+        assert_eq!(album_photo_ids(&conn, &id, AlbumSortMode::Filename).unwrap(), ["p2", "p3", "p1"]);
+
+        // Trashed photos are hidden but the album still holds them
+        conn.execute("UPDATE photos SET deleted_at = '2024-04-01T00:00:00+00:00' WHERE id = 'p2'", [])
+            .unwrap();
+        assert_eq!(album_photo_ids(&conn, &id, AlbumSortMode::Manual).unwrap(), ["p1", "p3"]);
+        assert_eq!(load_album(&conn, &id).unwrap().item_count, 2);
+    }
+
+    #[test]
+    fn test_reorder_keeps_trashed_photos_in_sequence() {
+        let (conn, id) = setup();
+        conn.execute("UPDATE photos SET deleted_at = '2024-04-01T00:00:00+00:00' WHERE id = 'p2'", [])
+            .unwrap();
+
+        apply_order(&conn, &id, vec!["p3".to_string()]).unwrap();
+        let positions: Vec<(String, i64)> = conn
+            .prepare("SELECT photo_id, position FROM album_items WHERE album_id = ?1 ORDER BY position")
+            .unwrap()
+            .query_map([&id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            positions,
+            [("p3".to_string(), 0), ("p1".to_string(), 1), ("p2".to_string(), 2)]
+        );
+
+        // Editing a deleted album fails without writing anything
+        conn.execute("UPDATE albums SET deleted_at = updated_at WHERE id = ?1", [&id]).unwrap();
+        let payload = UpdateAlbumPayload { name: Some("Renamed".to_string()), ..Default::default() };
+        assert!(apply_update(&conn, &id, &payload).is_err());
+        let name: String = conn.query_row("SELECT name FROM albums WHERE id = ?1", [&id], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Trip");
+    }
+
+    #[test]
+    fn test_deleted_album_merges_as_tombstone() {
+        let (local, id) = setup();
+        let remote = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&remote).unwrap();
+        let stale = manifest::export_manifest(&local).unwrap();
+        manifest::import_manifest(&remote, stale.clone()).unwrap();
+
+        // Deleted on the other device after the last local change
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let now = chrono::Utc::now().to_rfc3339();
+        remote
+            .execute("UPDATE albums SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2", [&now, &id])
+            .unwrap();
+        remote.execute("DELETE FROM album_items WHERE album_id = ?1", [&id]).unwrap();
+        let deleted = manifest::export_manifest(&remote).unwrap();
+
+        manifest::import_manifest(&local, deleted).unwrap();
+        assert!(query_albums(&local, None).unwrap().is_empty());
+        let items: u32 = local
+            .query_row("SELECT COUNT(*) FROM album_items WHERE album_id = ?1", [&id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(items, 0);
+
+        // An older copy of the album doesn't bring it back
+        manifest::import_manifest(&remote, stale).unwrap();
+        assert!(load_album(&remote, &id).is_err());
+    }
+}
@@ -145,6 +145,17 @@ impl ThumbnailCache {
         *total = total.saturating_sub(freed);
         Ok(())
     }
+    /// Remove a thumbnail from the cache
+    pub fn remove(&self, id: &str) -> Result<()> {
+        let key = format!("{}.webp", id);
+        let removed = self.entries.write().unwrap().remove(&key);
+        if let Some(entry) = removed {
+            fs::remove_file(self.cache_dir.join(&key)).ok();
+            let mut total = self.total_size.write().unwrap();
+            *total = total.saturating_sub(entry.size);
+        }
+        Ok(())
+    }
     /// Clear all cached thumbnails
     #[allow(dead_code)]
     pub fn clear(&self) -> Result<()> {
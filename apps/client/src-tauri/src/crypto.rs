@@ -6,6 +6,9 @@ use rand::RngCore;
 
 pub const NONCE_LEN: usize = 12;
 
+/// Read size for streaming file hashes
+const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
 /// Derives a 32-byte key from a PIN using Argon2id.
 ///
 /// Parameters are tuned to make brute-forcing expensive (0.5s - 1s per attempt).
@@ -64,3 +67,42 @@ pub fn decrypt(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
         .decrypt(nonce, ciphertext)
         .map_err(|e| anyhow::anyhow!("Decryption failed: {}", e))
 }
+
+/// Derive the key used for content hashes from the vault key. The hashes are
+/// stored in the manifest, so they must not reveal anything about file contents
+/// to someone without the vault key.
+pub fn derive_content_hash_key(vault_key: &[u8; 32]) -> [u8; 32] {
+    use hkdf::Hkdf;
+    use sha2::Sha256;
+
+    let hk = Hkdf::<Sha256>::new(None, vault_key);
+    let mut okm = [0u8; 32];
+    hk.expand(b"boreal-content-hash-v1", &mut okm)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    okm
+}
+
+/// Keyed hash (HMAC-SHA256, hex) of a file's contents, used to detect duplicates
+pub fn content_hash(path: &std::path::Path, key: &[u8; 32]) -> Result<String> {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use std::io::Read;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|_| anyhow::anyhow!("HMAC init failed"))?;
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        mac.update(&buf[..n]);
+    }
+
+    Ok(mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
@@ -0,0 +1,346 @@
+//! User curation: favorites, star ratings, color labels and keyword tags
+//!
+//! Curation syncs through the manifest as part of each photo record. Any edit that
+//! changes a value bumps `curation_updated_at`, and merges keep the most recently
+//! curated side.
+
+use crate::AppState;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// Color labels, matching the ones Lightroom offers
+pub const COLOR_LABELS: &[&str] = &["red", "yellow", "green", "blue", "purple"];
+
+pub const MAX_RATING: u8 = 5;
+
+/// Bulk edit applied to every selected photo. Unset fields are left unchanged.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CurationEdit {
+    pub favorite: Option<bool>,
+    pub rating: Option<u8>,
+    /// An empty string clears the label
+    pub color_label: Option<String>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagSummary {
+    pub name: String,
+    pub photo_count: u32,
+}
+
+/// Trim a tag and collapse inner whitespace; empty tags are dropped
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ");
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag)
+    }
+}
+
+/// Returns whether the photo did not have the tag yet
+fn add_photo_tag(conn: &Connection, photo_id: &str, tag: &str) -> rusqlite::Result<bool> {
+    conn.execute(
+        "INSERT OR IGNORE INTO tags (name, created_at) VALUES (?1, ?2)",
+        rusqlite::params![tag, chrono::Utc::now().to_rfc3339()],
+    )?;
+    let added = conn.execute(
+        "INSERT OR IGNORE INTO photo_tags (photo_id, tag) VALUES (?1, ?2)",
+        [photo_id, tag],
+    )?;
+    Ok(added > 0)
+}
+
+/// Replace the tags of a photo
+pub fn set_photo_tags(conn: &Connection, photo_id: &str, tags: &[String]) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM photo_tags WHERE photo_id = ?1", [photo_id])?;
+    for tag in tags.iter().filter_map(|t| normalize_tag(t)) {
+        add_photo_tag(conn, photo_id, &tag)?;
+    }
+    Ok(())
+}
+
+/// Tags of every photo, keyed by photo ID
+pub fn load_all_photo_tags(conn: &Connection) -> rusqlite::Result<HashMap<String, Vec<String>>> {
+    let mut stmt = conn.prepare("SELECT photo_id, tag FROM photo_tags ORDER BY tag COLLATE NOCASE")?;
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+    for row in rows {
+        let (photo_id, tag) = row?;
+        tags.entry(photo_id).or_default().push(tag);
+    }
+    Ok(tags)
+}
+
+/// Apply a bulk edit. Returns the number of photos whose curation actually changed;
+/// only those get a new `curation_updated_at`, so a no-op edit can't win a merge
+/// over a real edit from another device.
+pub fn apply_edit(conn: &Connection, ids: &[String], edit: &CurationEdit) -> Result<u32, String> {
+    if let Some(rating) = edit.rating {
+        if rating > MAX_RATING {
+            return Err(format!("Rating must be between 0 and {}", MAX_RATING));
+        }
+    }
+    if let Some(label) = &edit.color_label {
+        if !label.is_empty() && !COLOR_LABELS.contains(&label.as_str()) {
+            return Err(format!("Unknown color label: {}", label));
+        }
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let mut updated = 0;
+
+    for id in ids {
+        let mut changed = 0;
+
+        if let Some(favorite) = edit.favorite {
+            changed += tx
+                .execute(
+                    "UPDATE photos SET favorite = ?1 WHERE id = ?2 AND favorite IS NOT ?1",
+                    rusqlite::params![favorite, id],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(rating) = edit.rating {
+            changed += tx
+                .execute(
+                    "UPDATE photos SET rating = ?1 WHERE id = ?2 AND rating IS NOT ?1",
+                    rusqlite::params![rating, id],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(label) = &edit.color_label {
+            let label = Some(label).filter(|l| !l.is_empty());
+            changed += tx
+                .execute(
+                    "UPDATE photos SET color_label = ?1 WHERE id = ?2 AND color_label IS NOT ?1",
+                    rusqlite::params![label, id],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+        if !edit.add_tags.is_empty() {
+            let exists = tx
+                .query_row("SELECT 1 FROM photos WHERE id = ?1", [id], |_| Ok(()))
+                .optional()
+                .map_err(|e| e.to_string())?
+                .is_some();
+            if exists {
+                for tag in edit.add_tags.iter().filter_map(|t| normalize_tag(t)) {
+                    if add_photo_tag(&tx, id, &tag).map_err(|e| e.to_string())? {
+                        changed += 1;
+                    }
+                }
+            }
+        }
+        for tag in edit.remove_tags.iter().filter_map(|t| normalize_tag(t)) {
+            changed += tx
+                .execute(
+                    "DELETE FROM photo_tags WHERE photo_id = ?1 AND tag = ?2",
+                    [id, &tag],
+                )
+                .map_err(|e| e.to_string())?;
+        }
+
+        if changed > 0 {
+            tx.execute(
+                "UPDATE photos SET curation_updated_at = ?1 WHERE id = ?2",
+                [&now, id],
+            )
+            .map_err(|e| e.to_string())?;
+            updated += 1;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(updated)
+}
+
+/// Set favorite/rating/color label and add or remove tags on many photos at once
+#[tauri::command]
+pub async fn edit_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    ids: Vec<String>,
+    edit: CurationEdit,
+) -> Result<u32, String> {
+    let updated = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        apply_edit(conn, &ids, &edit)?
+    };
+
+    if updated > 0 {
+        crate::recovery::refresh_sidecars(&app, ids);
+        crate::sync_daemon::notify_local_change(&app).await;
+    }
+    Ok(updated)
+}
+
+/// All tags with the number of (non-trashed) photos using them
+#[tauri::command]
+pub async fn list_tags(state: State<'_, AppState>) -> Result<Vec<TagSummary>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name, COUNT(p.id) FROM tags t
+             LEFT JOIN photo_tags pt ON pt.tag = t.name
+             LEFT JOIN photos p ON p.id = pt.photo_id AND p.deleted_at IS NULL
+             GROUP BY t.name
+             ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let tags = stmt
+        .query_map([], |row| {
+            Ok(TagSummary {
+                name: row.get(0)?,
+                photo_count: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+#[tauri::command]
+pub async fn get_photo_tags(state: State<'_, AppState>, id: String) -> Result<Vec<String>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+    let mut stmt = conn
+        .prepare("SELECT tag FROM photo_tags WHERE photo_id = ?1 ORDER BY tag COLLATE NOCASE")
+        .map_err(|e| e.to_string())?;
+    let tags = stmt
+        .query_map([&id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulk_edit() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO photos (id, filename, s3_key, tier) VALUES ('p1', 'a.jpg', 'k1', 'Standard');
+             INSERT INTO photos (id, filename, s3_key, tier) VALUES ('p2', 'b.jpg', 'k2', 'Standard');",
+        )
+        .unwrap();
+        let ids = vec!["p1".to_string(), "p2".to_string(), "missing".to_string()];
+
+        let edit = CurationEdit {
+            favorite: Some(true),
+            rating: Some(4),
+            color_label: Some("red".to_string()),
+            add_tags: vec!["  Iceland  trip ".to_string(), "aurora".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(apply_edit(&conn, &ids, &edit).unwrap(), 2);
+
+        let edit = CurationEdit {
+            color_label: Some(String::new()),
+            remove_tags: vec!["AURORA".to_string()],
+            ..Default::default()
+        };
+        apply_edit(&conn, &ids[..1], &edit).unwrap();
+
+        let tags = load_all_photo_tags(&conn).unwrap();
+        assert_eq!(tags["p1"], vec!["Iceland trip"]);
+        assert_eq!(tags["p2"], vec!["aurora", "Iceland trip"]);
+
+        let (favorite, rating, label): (bool, u8, Option<String>) = conn
+            .query_row(
+                "SELECT favorite, rating, color_label FROM photos WHERE id = 'p1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert!(favorite);
+        assert_eq!(rating, 4);
+        assert_eq!(label, None);
+    }
+
+    #[test]
+    fn test_noop_edit_keeps_curation_timestamp() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO photos (id, filename, s3_key, tier) VALUES ('p1', 'a.jpg', 'k1', 'Standard')",
+            [],
+        )
+        .unwrap();
+        let ids = vec!["p1".to_string()];
+
+        let edit = CurationEdit {
+            favorite: Some(true),
+            rating: Some(3),
+            color_label: Some("blue".to_string()),
+            add_tags: vec!["aurora".to_string()],
+            remove_tags: vec!["sunset".to_string()],
+        };
+        assert_eq!(apply_edit(&conn, &ids, &edit).unwrap(), 1);
+        conn.execute(
+            "UPDATE photos SET curation_updated_at = '2024-01-01T00:00:00+00:00'",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(apply_edit(&conn, &ids, &edit).unwrap(), 0);
+        let stamp: String = conn
+            .query_row(
+                "SELECT curation_updated_at FROM photos WHERE id = 'p1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stamp, "2024-01-01T00:00:00+00:00");
+
+        let edit = CurationEdit {
+            rating: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(apply_edit(&conn, &ids, &edit).unwrap(), 1);
+        let stamp: String = conn
+            .query_row(
+                "SELECT curation_updated_at FROM photos WHERE id = 'p1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_ne!(stamp, "2024-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_invalid_edits_are_rejected() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+
+        let edit = CurationEdit {
+            rating: Some(6),
+            ..Default::default()
+        };
+        assert!(apply_edit(&conn, &[], &edit).is_err());
+
+        let edit = CurationEdit {
+            color_label: Some("magenta".to_string()),
+            ..Default::default()
+        };
+        assert!(apply_edit(&conn, &[], &edit).is_err());
+    }
+}
@@ -2,9 +2,170 @@ use rusqlite::{Connection, Result};
 use std::path::Path;
 use chrono;
 
-pub fn init_db(path: &Path) -> Result<Connection> {
+/// A single schema migration. Migrations run in order, each inside its own
+/// transaction, and `PRAGMA user_version` records how many have been applied.
+struct Migration {
+    name: &'static str,
+    up: fn(&Connection) -> Result<()>,
+}
+
+/// Ordered list of schema migrations. Never reorder or edit an existing entry;
+/// append a new one instead.
+///
+/// Databases created before versioning (`user_version = 0`) may already contain
+/// any subset of the early columns and tables, so migrations up to `devices`
+/// are written to be idempotent.
+const MIGRATIONS: &[Migration] = &[
+    Migration { name: "photos", up: migrate_photos },
+    Migration { name: "photo_capture_and_gps", up: migrate_photo_capture_and_gps },
+    Migration { name: "photo_thumbnail_size", up: migrate_photo_thumbnail_size },
+    Migration { name: "photo_camera_metadata", up: migrate_photo_camera_metadata },
+    Migration { name: "metadata", up: migrate_metadata },
+    Migration { name: "memories", up: migrate_memories },
+    Migration { name: "embeddings", up: migrate_embeddings },
+    Migration { name: "original_restores", up: migrate_original_restores },
+    Migration { name: "devices", up: migrate_devices },
+    Migration { name: "photo_query_indexes", up: migrate_photo_query_indexes },
+    Migration { name: "search_index", up: migrate_search_index },
+    Migration { name: "photo_trash", up: migrate_photo_trash },
+    Migration { name: "albums", up: migrate_albums },
+    Migration { name: "photo_curation", up: migrate_photo_curation },
+    Migration { name: "smart_albums", up: migrate_smart_albums },
+    Migration { name: "utc_photo_timestamps", up: migrate_utc_photo_timestamps },
+    Migration { name: "photo_content_hash", up: migrate_photo_content_hash },
+    Migration { name: "watch_folders", up: migrate_watch_folders },
+    Migration { name: "photo_original_policy", up: migrate_photo_original_policy },
+    Migration { name: "photo_motion", up: migrate_photo_motion },
+    Migration { name: "photo_description", up: migrate_photo_description },
+    Migration { name: "photo_verification", up: migrate_photo_verification },
+    Migration { name: "pending_memberships", up: migrate_pending_memberships },
+];
+
+/// First 16 bytes of every unencrypted SQLite database file
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Open (or create) a vault DB encrypted at rest with SQLCipher.
+/// Plaintext DBs from older versions are encrypted in place first.
+pub fn init_db(path: &Path, key: &[u8; 32]) -> anyhow::Result<Connection> {
+    use anyhow::Context;
+
+    if is_plaintext_db(path) {
+        encrypt_plaintext_db(path, key).context("Failed to encrypt existing vault DB")?;
+        log::info!("[DB] Encrypted plaintext vault DB at {:?}", path);
+    }
+
     let conn = Connection::open(path)?;
+    conn.execute_batch(&format!("PRAGMA key = \"x'{}'\";", hex_key(key)))?;
+    // SQLCipher only validates the key on first access
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+        .context("Failed to unlock vault DB (wrong key?)")?;
+
+    migrate(&conn)?;
+    Ok(conn)
+}
+
+/// Derive the at-rest key for a vault's manifest.db from the vault key,
+/// so the local DB never shares a key with cloud data
+pub fn derive_db_key(vault_key: &[u8; 32]) -> [u8; 32] {
+    use hkdf::Hkdf;
+    use sha2::Sha256;
+
+    let hk = Hkdf::<Sha256>::new(None, vault_key);
+    let mut okm = [0u8; 32];
+    hk.expand(b"boreal-manifest-db-v1", &mut okm)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    okm
+}
+
+fn hex_key(key: &[u8; 32]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn is_plaintext_db(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut header = [0u8; 16];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map(|_| &header == SQLITE_HEADER)
+        .unwrap_or(false)
+}
+
+/// Export a plaintext DB into an encrypted copy, then swap it into place
+fn encrypt_plaintext_db(path: &Path, key: &[u8; 32]) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("db.encrypting");
+    if tmp_path.exists() {
+        std::fs::remove_file(&tmp_path)?;
+    }
+
+    {
+        let conn = Connection::open(path)?;
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        let tmp_str = tmp_path.to_string_lossy().replace('\'', "''");
+
+        conn.execute_batch(&format!(
+            "ATTACH DATABASE '{}' AS encrypted KEY \"x'{}'\";",
+            tmp_str,
+            hex_key(key)
+        ))?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+        conn.execute_batch(&format!(
+            "PRAGMA encrypted.user_version = {}; DETACH DATABASE encrypted;",
+            version
+        ))?;
+    }
+
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Bring the schema up to date, applying every migration newer than `user_version`
+pub fn migrate(conn: &Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    if version > MIGRATIONS.len() {
+        log::warn!(
+            "[DB] Schema version {} is newer than this app supports ({})",
+            version,
+            MIGRATIONS.len()
+        );
+        return Ok(());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.unchecked_transaction()?;
+        (migration.up)(&tx)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+        log::info!("[DB] Applied migration {} ({})", index + 1, migration.name);
+    }
+
+    Ok(())
+}
 
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in columns {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// `ALTER TABLE ... ADD COLUMN` that is a no-op if the column already exists
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_photos(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS photos (
             id TEXT PRIMARY KEY,
@@ -21,36 +182,34 @@ pub fn init_db(path: &Path) -> Result<Connection> {
         [],
     )?;
 
-    // Migration: Add media_type column if it doesn't exist (for existing databases)
-    conn.execute(
-        "ALTER TABLE photos ADD COLUMN media_type TEXT NOT NULL DEFAULT 'image'",
-        [],
-    )
-    .ok(); // Ignore error if column already exists
-
-    // Migration: Add captured_at for actual photo capture date (from EXIF)
-    conn.execute("ALTER TABLE photos ADD COLUMN captured_at TEXT", [])
-        .ok();
-
-    // Migration: Add GPS coordinates
-    conn.execute("ALTER TABLE photos ADD COLUMN latitude REAL", [])
-        .ok();
-    conn.execute("ALTER TABLE photos ADD COLUMN longitude REAL", [])
-        .ok();
-
-    // Migration: Add thumbnail size for more accurate vault size tracking
-    conn.execute("ALTER TABLE photos ADD COLUMN thumbnail_size_bytes INTEGER", [])
-        .ok();
-
-    // Migration: Extended Metadata (Make, Model, Lens, Shooting Settings)
-    conn.execute("ALTER TABLE photos ADD COLUMN make TEXT", []).ok();
-    conn.execute("ALTER TABLE photos ADD COLUMN model TEXT", []).ok();
-    conn.execute("ALTER TABLE photos ADD COLUMN lens_model TEXT", []).ok();
-    conn.execute("ALTER TABLE photos ADD COLUMN iso INTEGER", []).ok();
-    conn.execute("ALTER TABLE photos ADD COLUMN f_number REAL", []).ok();
-    conn.execute("ALTER TABLE photos ADD COLUMN exposure_time TEXT", []).ok();
-
-    // Migration: Create metadata table for syncing vault properties (visits, name, etc.)
+    // Databases from before media types existed
+    add_column_if_missing(conn, "photos", "media_type", "TEXT NOT NULL DEFAULT 'image'")
+}
+
+fn migrate_photo_capture_and_gps(conn: &Connection) -> Result<()> {
+    // Actual photo capture date (from EXIF)
+    add_column_if_missing(conn, "photos", "captured_at", "TEXT")?;
+    add_column_if_missing(conn, "photos", "latitude", "REAL")?;
+    add_column_if_missing(conn, "photos", "longitude", "REAL")
+}
+
+fn migrate_photo_thumbnail_size(conn: &Connection) -> Result<()> {
+    // Thumbnail size for more accurate vault size tracking
+    add_column_if_missing(conn, "photos", "thumbnail_size_bytes", "INTEGER")
+}
+
+fn migrate_photo_camera_metadata(conn: &Connection) -> Result<()> {
+    // Extended Metadata (Make, Model, Lens, Shooting Settings)
+    add_column_if_missing(conn, "photos", "make", "TEXT")?;
+    add_column_if_missing(conn, "photos", "model", "TEXT")?;
+    add_column_if_missing(conn, "photos", "lens_model", "TEXT")?;
+    add_column_if_missing(conn, "photos", "iso", "INTEGER")?;
+    add_column_if_missing(conn, "photos", "f_number", "REAL")?;
+    add_column_if_missing(conn, "photos", "exposure_time", "TEXT")
+}
+
+fn migrate_metadata(conn: &Connection) -> Result<()> {
+    // Metadata table for syncing vault properties (visits, name, etc.)
     conn.execute(
         "CREATE TABLE IF NOT EXISTS metadata (
             key TEXT PRIMARY KEY,
@@ -58,8 +217,10 @@ pub fn init_db(path: &Path) -> Result<Connection> {
         )",
         [],
     )?;
+    Ok(())
+}
 
-    // Migration: Memories Feature
+fn migrate_memories(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS memories (
             id TEXT PRIMARY KEY,
@@ -84,8 +245,11 @@ pub fn init_db(path: &Path) -> Result<Connection> {
         )",
         [],
     )?;
+    Ok(())
+}
 
-    // Migration: Embeddings table for semantic search
+fn migrate_embeddings(conn: &Connection) -> Result<()> {
+    // Embeddings table for semantic search
     conn.execute(
         "CREATE TABLE IF NOT EXISTS embeddings (
             photo_id TEXT PRIMARY KEY,
@@ -96,8 +260,11 @@ pub fn init_db(path: &Path) -> Result<Connection> {
         )",
         [],
     )?;
+    Ok(())
+}
 
-    // Migration: Original Restores table for tracking Deep Glacier restore requests
+fn migrate_original_restores(conn: &Connection) -> Result<()> {
+    // Tracks Deep Glacier restore requests
     conn.execute(
         "CREATE TABLE IF NOT EXISTS original_restores (
             photo_id TEXT PRIMARY KEY,
@@ -109,10 +276,352 @@ pub fn init_db(path: &Path) -> Result<Connection> {
         )",
         [],
     )?;
+    Ok(())
+}
 
-    Ok(conn)
+fn migrate_devices(conn: &Connection) -> Result<()> {
+    // Devices that have synced this vault (synced via manifest)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS devices (
+            id TEXT PRIMARY KEY,
+            hostname TEXT NOT NULL,
+            platform TEXT NOT NULL,
+            app_version TEXT NOT NULL,
+            first_sync_at TEXT NOT NULL,
+            last_sync_at TEXT NOT NULL,
+            revoked_at TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_photo_query_indexes(conn: &Connection) -> Result<()> {
+    // Indexes backing the sort orders and filters of photo_query
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_photos_display_date ON photos (COALESCE(captured_at, created_at, ''), id);
+         CREATE INDEX IF NOT EXISTS idx_photos_created_at ON photos (COALESCE(created_at, ''), id);
+         CREATE INDEX IF NOT EXISTS idx_photos_filename ON photos (filename, id);
+         CREATE INDEX IF NOT EXISTS idx_photos_size ON photos (COALESCE(size_bytes, 0), id);
+         CREATE INDEX IF NOT EXISTS idx_photos_media_type ON photos (media_type);
+         CREATE INDEX IF NOT EXISTS idx_photos_camera ON photos (make COLLATE NOCASE, model COLLATE NOCASE);
+         CREATE INDEX IF NOT EXISTS idx_photos_lens ON photos (lens_model COLLATE NOCASE);
+         CREATE INDEX IF NOT EXISTS idx_photos_location ON photos (latitude, longitude);",
+    )?;
+    Ok(())
+}
+
+fn migrate_search_index(conn: &Connection) -> Result<()> {
+    // Full-text index over photos and memories, kept in sync by triggers.
+    // `body` holds memory text and is where photo captions will go.
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+            kind UNINDEXED,
+            id UNINDEXED,
+            title,
+            camera,
+            body,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS photos_search_insert AFTER INSERT ON photos BEGIN
+            INSERT INTO search_index (kind, id, title, camera)
+            VALUES ('photo', new.id, new.filename,
+                    TRIM(COALESCE(new.make, '') || ' ' || COALESCE(new.model, '') || ' ' || COALESCE(new.lens_model, '')));
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS photos_search_update AFTER UPDATE OF filename, make, model, lens_model ON photos BEGIN
+            UPDATE search_index
+            SET title = new.filename,
+                camera = TRIM(COALESCE(new.make, '') || ' ' || COALESCE(new.model, '') || ' ' || COALESCE(new.lens_model, ''))
+            WHERE kind = 'photo' AND id = old.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS photos_search_delete AFTER DELETE ON photos BEGIN
+            DELETE FROM search_index WHERE kind = 'photo' AND id = old.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS memories_search_insert AFTER INSERT ON memories BEGIN
+            INSERT INTO search_index (kind, id, title, body)
+            VALUES ('memory', new.id, new.title, new.text_content);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS memories_search_update AFTER UPDATE OF title, text_content ON memories BEGIN
+            UPDATE search_index
+            SET title = new.title, body = new.text_content
+            WHERE kind = 'memory' AND id = old.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS memories_search_delete AFTER DELETE ON memories BEGIN
+            DELETE FROM search_index WHERE kind = 'memory' AND id = old.id;
+        END;
+
+        DELETE FROM search_index;
+
+        INSERT INTO search_index (kind, id, title, camera)
+        SELECT 'photo', id, filename,
+               TRIM(COALESCE(make, '') || ' ' || COALESCE(model, '') || ' ' || COALESCE(lens_model, ''))
+        FROM photos;
+
+        INSERT INTO search_index (kind, id, title, body)
+        SELECT 'memory', id, title, text_content FROM memories;",
+    )?;
+    Ok(())
+}
+
+fn migrate_photo_trash(conn: &Connection) -> Result<()> {
+    // Soft delete: trashed photos keep their row until purged.
+    // trash_updated_at orders trash/restore actions from different devices.
+    conn.execute("ALTER TABLE photos ADD COLUMN deleted_at TEXT", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN trash_updated_at TEXT", [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_photos_deleted_at ON photos (deleted_at)",
+        [],
+    )?;
+
+    // IDs of purged photos (synced via manifest) so merges don't bring them back
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS photo_tombstones (
+            id TEXT PRIMARY KEY,
+            purged_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_albums(conn: &Connection) -> Result<()> {
+    // Undated collections (synced via manifest). Deleted albums keep their row
+    // with deleted_at set so the deletion reaches other devices.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS albums (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            cover_photo_id TEXT,
+            sort_mode TEXT NOT NULL DEFAULT 'manual',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            deleted_at TEXT
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS album_items (
+            album_id TEXT NOT NULL,
+            photo_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            added_at TEXT NOT NULL,
+            PRIMARY KEY (album_id, photo_id),
+            FOREIGN KEY(album_id) REFERENCES albums(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_album_items_photo ON album_items (photo_id)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_photo_curation(conn: &Connection) -> Result<()> {
+    // User curation (synced via manifest). curation_updated_at orders edits of
+    // favorite/rating/color label/tags made on different devices.
+    conn.execute("ALTER TABLE photos ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN rating INTEGER NOT NULL DEFAULT 0", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN color_label TEXT", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN curation_updated_at TEXT", [])?;
+
+    // Tags are identified by name so devices creating the same tag agree on it
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            name TEXT PRIMARY KEY COLLATE NOCASE,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS photo_tags (
+            photo_id TEXT NOT NULL,
+            tag TEXT NOT NULL COLLATE NOCASE,
+            PRIMARY KEY (photo_id, tag),
+            FOREIGN KEY(tag) REFERENCES tags(name) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_photo_tags_tag ON photo_tags (tag);
+         CREATE INDEX IF NOT EXISTS idx_photos_favorite ON photos (favorite) WHERE favorite = 1;
+         CREATE INDEX IF NOT EXISTS idx_photos_rating ON photos (rating);",
+    )?;
+    Ok(())
+}
+
+fn migrate_smart_albums(conn: &Connection) -> Result<()> {
+    // Saved queries (synced via manifest). `rule` is the JSON-encoded
+    // `smart_albums::SmartAlbumRule`, evaluated whenever the album is opened.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS smart_albums (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            rule TEXT NOT NULL,
+            sort TEXT NOT NULL DEFAULT 'date_desc',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            deleted_at TEXT
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_utc_photo_timestamps(conn: &Connection) -> Result<()> {
+    // Timestamps written with other offsets ("...+02:00", "...Z") sort and bucket
+    // incorrectly next to UTC ones; rewrite them in the canonical form. Older
+    // `captured_at` values labelled "+00:00" may be camera wall-clock time, but
+    // no offset was recorded, so they are kept as-is rather than guessed from this
+    // device's zone (every device must migrate a row to the same value).
+    let rows: Vec<(String, Option<String>, Option<String>)> = {
+        let mut stmt = conn.prepare("SELECT id, created_at, captured_at FROM photos")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<Vec<_>>>()?
+    };
+
+    for (id, created_at, captured_at) in rows {
+        let created = created_at.as_deref().map(normalize_timestamp);
+        let captured = captured_at.as_deref().map(normalize_timestamp);
+        if created != created_at || captured != captured_at {
+            conn.execute(
+                "UPDATE photos SET created_at = ?1, captured_at = ?2 WHERE id = ?3",
+                rusqlite::params![created, captured, id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn migrate_photo_content_hash(conn: &Connection) -> Result<()> {
+    // Keyed hash of the source file (see `crypto::content_hash`) used to skip
+    // re-uploading the same file. NULL for photos uploaded before hashing.
+    conn.execute("ALTER TABLE photos ADD COLUMN content_hash TEXT", [])?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_photos_content_hash ON photos (content_hash)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_watch_folders(conn: &Connection) -> Result<()> {
+    // Folders scanned for new media to upload. Device-local (paths differ per
+    // device), so neither table is part of the manifest.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS watch_folders (
+            id TEXT PRIMARY KEY,
+            path TEXT NOT NULL UNIQUE,
+            include TEXT NOT NULL DEFAULT '[]',
+            exclude TEXT NOT NULL DEFAULT '[]',
+            after_upload TEXT NOT NULL DEFAULT 'keep',
+            move_to TEXT,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    // Files already picked up, so they aren't queued again. A file is picked up
+    // again if its size or modification time changes.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS watch_ingested (
+            folder_id TEXT NOT NULL,
+            path TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            modified_at INTEGER,
+            upload_id TEXT,
+            state TEXT NOT NULL,
+            ingested_at TEXT NOT NULL,
+            PRIMARY KEY (folder_id, path)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_watch_ingested_state ON watch_ingested (state)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_photo_original_policy(conn: &Connection) -> Result<()> {
+    // How the original was stored (see `vault::OriginalPolicy`), plus the optional
+    // display copy kept next to a lossless original. NULL policy predates the column
+    // and means the optimized encoding (or a passthrough).
+    conn.execute("ALTER TABLE photos ADD COLUMN original_policy TEXT", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN display_key TEXT", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN display_size_bytes INTEGER", [])?;
+    Ok(())
 }
 
+fn migrate_photo_motion(conn: &Connection) -> Result<()> {
+    // Video component of a Live Photo / Motion Photo, stored next to the still
+    conn.execute("ALTER TABLE photos ADD COLUMN motion_key TEXT", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN motion_size_bytes INTEGER", [])?;
+    Ok(())
+}
+
+fn migrate_photo_description(conn: &Connection) -> Result<()> {
+    // Caption from XMP or an imported library export, indexed as the photo's search body
+    conn.execute_batch(
+        "ALTER TABLE photos ADD COLUMN description TEXT;
+
+        DROP TRIGGER IF EXISTS photos_search_insert;
+        CREATE TRIGGER photos_search_insert AFTER INSERT ON photos BEGIN
+            INSERT INTO search_index (kind, id, title, camera, body)
+            VALUES ('photo', new.id, new.filename,
+                    TRIM(COALESCE(new.make, '') || ' ' || COALESCE(new.model, '') || ' ' || COALESCE(new.lens_model, '')),
+                    new.description);
+        END;
+
+        DROP TRIGGER IF EXISTS photos_search_update;
+        CREATE TRIGGER photos_search_update AFTER UPDATE OF filename, make, model, lens_model, description ON photos BEGIN
+            UPDATE search_index
+            SET title = new.filename,
+                camera = TRIM(COALESCE(new.make, '') || ' ' || COALESCE(new.model, '') || ' ' || COALESCE(new.lens_model, '')),
+                body = new.description
+            WHERE kind = 'photo' AND id = old.id;
+        END;",
+    )?;
+    Ok(())
+}
+
+fn migrate_photo_verification(conn: &Connection) -> Result<()> {
+    // How the stored objects were checked after upload (see `verification`); NULL for older photos
+    conn.execute("ALTER TABLE photos ADD COLUMN verification TEXT", [])?;
+    conn.execute("ALTER TABLE photos ADD COLUMN verified_at TEXT", [])?;
+    Ok(())
+}
+
+fn migrate_pending_memberships(conn: &Connection) -> Result<()> {
+    // Albums and memories that queued uploads of a library import join once their
+    // photo exists. Device-local, like the upload queue.
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pending_memberships (
+            upload_id TEXT NOT NULL,
+            target_kind TEXT NOT NULL,
+            target_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (upload_id, target_kind, target_id)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Canonical form of stored photo timestamps: RFC3339 in UTC, as written by
+/// `chrono::Utc::now().to_rfc3339()`. Unparseable values are returned unchanged.
+pub fn normalize_timestamp(value: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(value.trim())
+        .map(|t| t.with_timezone(&chrono::Utc).to_rfc3339())
+        .unwrap_or_else(|_| value.to_string())
+}
 
 pub fn set_metadata(conn: &Connection, key: &str, value: &str) -> Result<()> {
     conn.execute(
@@ -136,17 +645,21 @@ pub fn get_metadata(conn: &Connection, key: &str) -> Result<Option<String>> {
 }
 
 pub fn save_embedding(conn: &Connection, photo_id: &str, embedding: &[f32]) -> Result<()> {
-    // Convert f32 vector to bytes (u8)
-    let bytes: Vec<u8> = embedding
-        .iter()
-        .flat_map(|f| f.to_le_bytes())
-        .collect();
+    save_embedding_for_model(conn, photo_id, embedding, crate::embedding::VISION_MODEL_VERSION)
+}
 
+pub fn save_embedding_for_model(
+    conn: &Connection,
+    photo_id: &str,
+    embedding: &[f32],
+    model_version: &str,
+) -> Result<()> {
+    let bytes = embedding_to_bytes(embedding);
     let created_at = chrono::Utc::now().to_rfc3339();
 
     conn.execute(
-        "INSERT OR REPLACE INTO embeddings (photo_id, embedding, created_at) VALUES (?1, ?2, ?3)",
-        (photo_id, bytes, created_at),
+        "INSERT OR REPLACE INTO embeddings (photo_id, embedding, model_version, created_at) VALUES (?1, ?2, ?3, ?4)",
+        (photo_id, bytes, model_version, created_at),
     )?;
 
     Ok(())
@@ -158,17 +671,7 @@ pub fn load_embeddings(conn: &Connection) -> Result<Vec<(String, Vec<f32>)>> {
     let rows = stmt.query_map([], |row| {
         let photo_id: String = row.get(0)?;
         let bytes: Vec<u8> = row.get(1)?;
-        
-        // Convert bytes back to f32
-        let embedding: Vec<f32> = bytes
-            .chunks_exact(4)
-            .map(|chunk| {
-                let bytes: [u8; 4] = chunk.try_into().unwrap();
-                f32::from_le_bytes(bytes)
-            })
-            .collect();
-            
-        Ok((photo_id, embedding))
+        Ok((photo_id, embedding_from_bytes(&bytes)))
     })?;
 
     let mut results = Vec::new();
@@ -179,6 +682,51 @@ pub fn load_embeddings(conn: &Connection) -> Result<Vec<(String, Vec<f32>)>> {
     Ok(results)
 }
 
+/// Load embeddings produced by a specific model version
+pub fn load_embeddings_for_model(
+    conn: &Connection,
+    model_version: &str,
+) -> Result<Vec<(String, Vec<f32>)>> {
+    let mut stmt =
+        conn.prepare("SELECT photo_id, embedding FROM embeddings WHERE model_version = ?1")?;
+
+    let rows = stmt.query_map([model_version], |row| {
+        let photo_id: String = row.get(0)?;
+        let bytes: Vec<u8> = row.get(1)?;
+        Ok((photo_id, embedding_from_bytes(&bytes)))
+    })?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+
+    Ok(results)
+}
+
+/// Get the IDs of photos that already have an embedding for the given model version
+pub fn get_embedded_photo_ids(
+    conn: &Connection,
+    model_version: &str,
+) -> Result<std::collections::HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT photo_id FROM embeddings WHERE model_version = ?1")?;
+    let rows = stmt.query_map([model_version], |row| row.get::<_, String>(0))?;
+    rows.collect()
+}
+
+/// Convert an f32 vector to little-endian bytes (the on-disk BLOB format)
+pub fn embedding_to_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+/// Convert little-endian bytes back to an f32 vector
+pub fn embedding_from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
 // ============ Original Restores Functions ============
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -269,3 +817,307 @@ pub fn delete_restore_request(conn: &Connection, photo_id: &str) -> Result<()> {
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Schemas shipped before versioned migrations existed (all `user_version = 0`)
+    const HISTORICAL_SCHEMAS: &[(&str, &str)] = &[
+        (
+            "initial",
+            "CREATE TABLE photos (
+                id TEXT PRIMARY KEY,
+                filename TEXT NOT NULL,
+                width INTEGER,
+                height INTEGER,
+                created_at TEXT,
+                size_bytes INTEGER,
+                s3_key TEXT NOT NULL,
+                thumbnail_key TEXT,
+                tier TEXT NOT NULL
+            );",
+        ),
+        (
+            "media_type_and_gps",
+            "CREATE TABLE photos (
+                id TEXT PRIMARY KEY,
+                filename TEXT NOT NULL,
+                width INTEGER,
+                height INTEGER,
+                created_at TEXT,
+                size_bytes INTEGER,
+                s3_key TEXT NOT NULL,
+                thumbnail_key TEXT,
+                tier TEXT NOT NULL,
+                media_type TEXT NOT NULL DEFAULT 'image',
+                captured_at TEXT,
+                latitude REAL,
+                longitude REAL
+            );
+            CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO metadata (key, value) VALUES ('name', 'Holidays');",
+        ),
+        (
+            "memories",
+            "CREATE TABLE photos (
+                id TEXT PRIMARY KEY,
+                filename TEXT NOT NULL,
+                width INTEGER,
+                height INTEGER,
+                created_at TEXT,
+                size_bytes INTEGER,
+                s3_key TEXT NOT NULL,
+                thumbnail_key TEXT,
+                tier TEXT NOT NULL,
+                media_type TEXT NOT NULL DEFAULT 'image',
+                captured_at TEXT,
+                latitude REAL,
+                longitude REAL,
+                thumbnail_size_bytes INTEGER,
+                make TEXT,
+                model TEXT,
+                lens_model TEXT,
+                iso INTEGER,
+                f_number REAL,
+                exposure_time TEXT
+            );
+            CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE memories (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                text_content TEXT,
+                date TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE memory_media (
+                memory_id TEXT NOT NULL,
+                media_id TEXT NOT NULL,
+                display_order INTEGER NOT NULL,
+                PRIMARY KEY (memory_id, media_id)
+            );",
+        ),
+        (
+            "embeddings_and_restores",
+            "CREATE TABLE photos (
+                id TEXT PRIMARY KEY,
+                filename TEXT NOT NULL,
+                width INTEGER,
+                height INTEGER,
+                created_at TEXT,
+                size_bytes INTEGER,
+                s3_key TEXT NOT NULL,
+                thumbnail_key TEXT,
+                tier TEXT NOT NULL,
+                media_type TEXT NOT NULL DEFAULT 'image',
+                captured_at TEXT,
+                latitude REAL,
+                longitude REAL,
+                thumbnail_size_bytes INTEGER,
+                make TEXT,
+                model TEXT,
+                lens_model TEXT,
+                iso INTEGER,
+                f_number REAL,
+                exposure_time TEXT
+            );
+            CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            CREATE TABLE memories (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                text_content TEXT,
+                date TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE memory_media (
+                memory_id TEXT NOT NULL,
+                media_id TEXT NOT NULL,
+                display_order INTEGER NOT NULL,
+                PRIMARY KEY (memory_id, media_id)
+            );
+            CREATE TABLE embeddings (
+                photo_id TEXT PRIMARY KEY,
+                embedding BLOB NOT NULL,
+                model_version TEXT NOT NULL DEFAULT 'nomic-embed-vision-v1.5',
+                created_at TEXT NOT NULL
+            );
+            CREATE TABLE original_restores (
+                photo_id TEXT PRIMARY KEY,
+                requested_at TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'restoring',
+                expires_at TEXT,
+                size_bytes INTEGER NOT NULL DEFAULT 0
+            );",
+        ),
+    ];
+
+    fn user_version(conn: &Connection) -> usize {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    fn assert_latest_schema(conn: &Connection) {
+        assert_eq!(user_version(conn), MIGRATIONS.len());
+        for column in [
+            "media_type",
+            "captured_at",
+            "latitude",
+            "longitude",
+            "thumbnail_size_bytes",
+            "make",
+            "model",
+            "lens_model",
+            "iso",
+            "f_number",
+            "exposure_time",
+            "deleted_at",
+            "trash_updated_at",
+            "favorite",
+            "rating",
+            "color_label",
+            "curation_updated_at",
+            "content_hash",
+            "original_policy",
+            "display_key",
+            "display_size_bytes",
+            "motion_key",
+            "motion_size_bytes",
+            "description",
+            "verification",
+            "verified_at",
+        ] {
+            assert!(has_column(conn, "photos", column).unwrap(), "missing photos.{}", column);
+        }
+        for table in [
+            "metadata",
+            "memories",
+            "memory_media",
+            "embeddings",
+            "original_restores",
+            "devices",
+            "search_index",
+            "photo_tombstones",
+            "albums",
+            "album_items",
+            "tags",
+            "photo_tags",
+            "smart_albums",
+            "watch_folders",
+            "watch_ingested",
+        ] {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                    [table],
+                    |_| Ok(()),
+                )
+                .is_ok();
+            assert!(exists, "missing table {}", table);
+        }
+    }
+
+    #[test]
+    fn test_fresh_db_migrates_to_latest() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        assert_latest_schema(&conn);
+    }
+
+    #[test]
+    fn test_historical_schemas_upgrade() {
+        for (name, schema) in HISTORICAL_SCHEMAS {
+            let conn = Connection::open_in_memory().unwrap();
+            conn.execute_batch(schema).unwrap();
+            conn.execute(
+                "INSERT INTO photos (id, filename, s3_key, tier) VALUES ('p1', 'a.jpg', 'originals/images/p1.avif', 'DeepArchive')",
+                [],
+            )
+            .unwrap();
+
+            migrate(&conn).unwrap_or_else(|e| panic!("schema '{}' failed to upgrade: {}", name, e));
+            assert_latest_schema(&conn);
+
+            // Existing rows survive and pick up column defaults
+            let media_type: String = conn
+                .query_row("SELECT media_type FROM photos WHERE id = 'p1'", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(media_type, "image");
+        }
+    }
+
+    #[test]
+    fn test_historical_metadata_preserved() {
+        let (_, schema) = HISTORICAL_SCHEMAS[1];
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(schema).unwrap();
+        migrate(&conn).unwrap();
+        assert_eq!(get_metadata(&conn, "name").unwrap().as_deref(), Some("Holidays"));
+    }
+
+    #[test]
+    fn test_plaintext_db_is_encrypted_in_place() {
+        let path = std::env::temp_dir().join(format!("boreal-test-{}.db", uuid::Uuid::new_v4()));
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(HISTORICAL_SCHEMAS[1].1).unwrap();
+        }
+        assert!(is_plaintext_db(&path));
+
+        let key = derive_db_key(&[7u8; 32]);
+        {
+            let conn = init_db(&path, &key).unwrap();
+            assert_latest_schema(&conn);
+            assert_eq!(get_metadata(&conn, "name").unwrap().as_deref(), Some("Holidays"));
+        }
+        assert!(!is_plaintext_db(&path));
+
+        // Reopening works with the right key and fails with any other
+        assert!(init_db(&path, &key).is_ok());
+        assert!(init_db(&path, &derive_db_key(&[8u8; 32])).is_err());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_normalize_timestamp() {
+        assert_eq!(normalize_timestamp("2024-01-01T01:30:00+02:00"), "2023-12-31T23:30:00+00:00");
+        assert_eq!(normalize_timestamp("2024-01-01T10:00:00Z"), "2024-01-01T10:00:00+00:00");
+        assert_eq!(normalize_timestamp("2024-01-01T10:00:00+00:00"), "2024-01-01T10:00:00+00:00");
+        assert_eq!(normalize_timestamp("not a date"), "not a date");
+    }
+
+    #[test]
+    fn test_utc_migration_keeps_unlabelled_capture_dates() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO photos (id, filename, s3_key, tier, created_at, captured_at) VALUES
+                ('a', 'a.jpg', 'a', 'Standard', '2024-07-01T12:00:00Z', '2024-07-01T09:00:00+00:00'),
+                ('b', 'b.jpg', 'b', 'Standard', NULL, '2024-07-01T09:00:00+02:00');",
+        )
+        .unwrap();
+        migrate_utc_photo_timestamps(&conn).unwrap();
+
+        let row = |id: &str| -> (Option<String>, Option<String>) {
+            conn.query_row("SELECT created_at, captured_at FROM photos WHERE id = ?1", [id], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap()
+        };
+        assert_eq!(
+            row("a"),
+            (Some("2024-07-01T12:00:00+00:00".into()), Some("2024-07-01T09:00:00+00:00".into()))
+        );
+        assert_eq!(row("b"), (None, Some("2024-07-01T07:00:00+00:00".into())));
+    }
+
+    #[test]
+    fn test_migrate_is_noop_when_up_to_date() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        migrate(&conn).unwrap();
+        assert_latest_schema(&conn);
+    }
+}
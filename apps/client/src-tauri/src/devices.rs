@@ -0,0 +1,157 @@
+use crate::db;
+use crate::AppState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+/// Metadata key holding this device's ID inside each vault DB
+const DEVICE_ID_KEY: &str = "device_id";
+
+/// A device that has synced this vault
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Device {
+    pub id: String,
+    pub hostname: String,
+    pub platform: String,
+    pub app_version: String,
+    pub first_sync_at: String,
+    pub last_sync_at: String,
+    pub revoked_at: Option<String>,
+    pub is_current: bool,
+}
+
+/// Stable per-install device ID, persisted in the app data directory
+fn get_or_create_device_id(app: &AppHandle) -> Result<String, String> {
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let path = app_dir.join("device_id");
+
+    if let Ok(existing) = std::fs::read_to_string(&path) {
+        let existing = existing.trim();
+        if !existing.is_empty() {
+            return Ok(existing.to_string());
+        }
+    }
+
+    std::fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
+    let id = uuid::Uuid::new_v4().to_string();
+    std::fs::write(&path, &id).map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Add or refresh this device's entry in the vault DB (called on vault load)
+pub fn register_current_device(app: &AppHandle, conn: &Connection) -> Result<(), String> {
+    let id = get_or_create_device_id(app)?;
+    let hostname = hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "Boreal Device".to_string());
+    let platform = std::env::consts::OS.to_string();
+    let app_version = app.package_info().version.to_string();
+    let now = chrono::Utc::now().to_rfc3339();
+
+    conn.execute(
+        "INSERT INTO devices (id, hostname, platform, app_version, first_sync_at, last_sync_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+         ON CONFLICT(id) DO UPDATE SET
+            hostname = excluded.hostname,
+            platform = excluded.platform,
+            app_version = excluded.app_version",
+        rusqlite::params![id, hostname, platform, app_version, now],
+    )
+    .map_err(|e| e.to_string())?;
+
+    db::set_metadata(conn, DEVICE_ID_KEY, &id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Bump this device's last sync time (called before each manifest upload)
+pub fn touch_current_device(conn: &Connection) -> rusqlite::Result<()> {
+    if let Some(id) = db::get_metadata(conn, DEVICE_ID_KEY)? {
+        conn.execute(
+            "UPDATE devices SET last_sync_at = ?1 WHERE id = ?2",
+            rusqlite::params![chrono::Utc::now().to_rfc3339(), id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Whether another device has revoked this one
+pub fn is_current_device_revoked(conn: &Connection) -> rusqlite::Result<bool> {
+    let Some(id) = db::get_metadata(conn, DEVICE_ID_KEY)? else {
+        return Ok(false);
+    };
+    let revoked: Option<String> = conn
+        .query_row(
+            "SELECT revoked_at FROM devices WHERE id = ?1",
+            [&id],
+            |row| row.get(0),
+        )
+        .unwrap_or(None);
+    Ok(revoked.is_some())
+}
+
+#[tauri::command]
+pub async fn list_devices(state: State<'_, AppState>) -> Result<Vec<Device>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+    let current_id = db::get_metadata(conn, DEVICE_ID_KEY).map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, hostname, platform, app_version, first_sync_at, last_sync_at, revoked_at
+             FROM devices ORDER BY last_sync_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let devices = stmt
+        .query_map([], |row| {
+            let id: String = row.get(0)?;
+            Ok(Device {
+                is_current: current_id.as_deref() == Some(id.as_str()),
+                id,
+                hostname: row.get(1)?,
+                platform: row.get(2)?,
+                app_version: row.get(3)?,
+                first_sync_at: row.get(4)?,
+                last_sync_at: row.get(5)?,
+                revoked_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Device>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(devices)
+}
+
+/// Mark a device as revoked. Other clients warn about it once the manifest syncs.
+#[tauri::command]
+pub async fn revoke_device(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        let current_id = db::get_metadata(conn, DEVICE_ID_KEY).map_err(|e| e.to_string())?;
+        if current_id.as_deref() == Some(id.as_str()) {
+            return Err("Cannot revoke the current device".to_string());
+        }
+
+        let updated = conn
+            .execute(
+                "UPDATE devices SET revoked_at = ?1 WHERE id = ?2 AND revoked_at IS NULL",
+                rusqlite::params![chrono::Utc::now().to_rfc3339(), id],
+            )
+            .map_err(|e| e.to_string())?;
+
+        if updated == 0 {
+            return Err("Device not found or already revoked".to_string());
+        }
+    }
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(())
+}
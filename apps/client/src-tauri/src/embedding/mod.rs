@@ -5,6 +5,7 @@
 pub mod download;
 pub mod preprocess;
 pub mod search;
+pub mod sync;
 
 
 use std::path::PathBuf;
@@ -14,6 +15,12 @@ use tokio::sync::Mutex;
 pub use download::{get_model_paths, models_exist};
 pub use search::EmbeddingIndex;
 
+/// Model version recorded alongside every stored vision embedding
+pub const VISION_MODEL_VERSION: &str = "nomic-embed-vision-v1.5";
+
+/// Text-to-image similarity below which results are treated as irrelevant
+pub const MIN_SEMANTIC_SCORE: f32 = 0.0525;
+
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 pub mod text_desktop;
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
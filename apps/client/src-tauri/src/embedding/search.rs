@@ -31,8 +31,10 @@ impl EmbeddingIndex {
         self.embeddings.contains_key(id)
     }
 
-
-
+    /// Remove an embedding. Returns whether it was present.
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.embeddings.remove(id).is_some()
+    }
 
     /// Search for top-k similar items
     /// Uses dot product (equivalent to cosine similarity for L2-normalized vectors)
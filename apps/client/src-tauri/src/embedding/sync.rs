@@ -0,0 +1,246 @@
+// Cross-device sync of vision embeddings
+// Embeddings are stored in S3 as encrypted chunks, keyed by model version:
+// - embeddings/{model_version}.enc          index listing every chunk and its photo IDs
+// - embeddings/{model_version}/{chunk}.enc  up to CHUNK_SIZE vectors per chunk
+//
+// Chunks are immutable. Each device only uploads vectors missing from the index
+// as new chunks, so sync is incremental in both directions.
+
+use crate::crypto;
+use crate::db;
+use crate::storage::Storage;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Current index format version
+const INDEX_VERSION: u32 = 1;
+
+/// Maximum number of vectors per chunk (~3MB for 768-dim vectors)
+const CHUNK_SIZE: usize = 1000;
+
+/// Index of all embedding chunks uploaded for one model version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingSyncIndex {
+    pub version: u32,
+    pub model_version: String,
+    pub chunks: Vec<ChunkEntry>,
+    pub updated_at: String,
+}
+
+/// A single chunk listed in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkEntry {
+    pub key: String,
+    pub photo_ids: Vec<String>,
+    pub created_at: String,
+}
+
+/// Encrypted payload of a chunk object
+#[derive(Debug, Serialize, Deserialize)]
+struct EmbeddingChunk {
+    model_version: String,
+    entries: Vec<ChunkRecord>,
+}
+
+/// Vector stored as base64 of little-endian f32 bytes (same layout as the DB BLOB)
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkRecord {
+    photo_id: String,
+    vector: String,
+}
+
+/// Statistics from a sync operation
+#[derive(Debug, Default)]
+pub struct EmbeddingSyncStats {
+    pub chunks_downloaded: usize,
+    pub imported: usize,
+    pub chunks_uploaded: usize,
+    pub uploaded: usize,
+}
+
+/// S3 key of the index for a model version
+pub fn index_key(model_version: &str) -> String {
+    format!("embeddings/{}.enc", model_version)
+}
+
+fn chunk_key(model_version: &str, chunk_id: &str) -> String {
+    format!("embeddings/{}/{}.enc", model_version, chunk_id)
+}
+
+impl EmbeddingSyncIndex {
+    fn empty(model_version: &str) -> Self {
+        Self {
+            version: INDEX_VERSION,
+            model_version: model_version.to_string(),
+            chunks: Vec::new(),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// All photo IDs covered by any chunk
+    pub fn photo_ids(&self) -> HashSet<&str> {
+        self.chunks
+            .iter()
+            .flat_map(|c| c.photo_ids.iter().map(|id| id.as_str()))
+            .collect()
+    }
+}
+
+/// Download and decrypt the index. Returns None if no embeddings were synced yet.
+pub async fn download_index(
+    storage: &Storage,
+    key: &[u8; 32],
+    model_version: &str,
+) -> Result<Option<EmbeddingSyncIndex>> {
+    let enc_bytes = match storage.download_file_if_exists(&index_key(model_version)).await? {
+        Some(bytes) => bytes,
+        None => return Ok(None),
+    };
+
+    let json = crypto::decrypt(&enc_bytes, key).context("Failed to decrypt embedding index")?;
+    let index = serde_json::from_slice(&json).context("Failed to deserialize embedding index")?;
+    Ok(Some(index))
+}
+
+/// Download chunks containing vectors missing locally and store them in the vault DB.
+/// Returns the imported vectors so the caller can add them to the in-memory index.
+pub async fn pull_embeddings(
+    storage: &Storage,
+    db_path: &Path,
+    key: &[u8; 32],
+    index: &EmbeddingSyncIndex,
+    stats: &mut EmbeddingSyncStats,
+) -> Result<Vec<(String, Vec<f32>)>> {
+    let model_version = index.model_version.as_str();
+
+    // Only import vectors for photos this vault knows about and hasn't embedded yet
+    let (wanted, embedded) = {
+        let conn = db::init_db(db_path, &db::derive_db_key(key)).context("Failed to open vault DB")?;
+        let mut stmt = conn.prepare("SELECT id FROM photos")?;
+        let photo_ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<HashSet<String>>>()?;
+        let embedded = db::get_embedded_photo_ids(&conn, model_version)?;
+        (photo_ids, embedded)
+    };
+
+    let mut imported = Vec::new();
+
+    for chunk in &index.chunks {
+        let needed = chunk
+            .photo_ids
+            .iter()
+            .any(|id| wanted.contains(id) && !embedded.contains(id));
+        if !needed {
+            continue;
+        }
+
+        let enc_bytes = match storage.download_file(&chunk.key).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("[Embedding Sync] Failed to download chunk {}: {}", chunk.key, e);
+                continue;
+            }
+        };
+        let json = crypto::decrypt(&enc_bytes, key)
+            .with_context(|| format!("Failed to decrypt chunk {}", chunk.key))?;
+        let payload: EmbeddingChunk = serde_json::from_slice(&json)
+            .with_context(|| format!("Failed to deserialize chunk {}", chunk.key))?;
+        stats.chunks_downloaded += 1;
+
+        for record in payload.entries {
+            if !wanted.contains(&record.photo_id) || embedded.contains(&record.photo_id) {
+                continue;
+            }
+            let bytes = BASE64
+                .decode(&record.vector)
+                .context("Invalid vector encoding")?;
+            imported.push((record.photo_id, db::embedding_from_bytes(&bytes)));
+        }
+    }
+
+    if !imported.is_empty() {
+        let mut conn = db::init_db(db_path, &db::derive_db_key(key)).context("Failed to open vault DB")?;
+        let tx = conn.transaction()?;
+        for (photo_id, vector) in &imported {
+            db::save_embedding_for_model(&tx, photo_id, vector, model_version)?;
+        }
+        tx.commit()?;
+    }
+
+    stats.imported = imported.len();
+    Ok(imported)
+}
+
+/// Upload local vectors that are not yet in the remote index as new chunks,
+/// then upload the updated index.
+///
+/// Two devices pushing at the same time may overwrite each other's index entry;
+/// the losing device simply re-uploads its vectors on the next sync.
+pub async fn push_embeddings(
+    storage: &Storage,
+    db_path: &Path,
+    key: &[u8; 32],
+    model_version: &str,
+    remote: Option<EmbeddingSyncIndex>,
+    stats: &mut EmbeddingSyncStats,
+) -> Result<()> {
+    let mut index = remote.unwrap_or_else(|| EmbeddingSyncIndex::empty(model_version));
+
+    let missing: Vec<(String, Vec<f32>)> = {
+        let remote_ids = index.photo_ids();
+        let conn = db::init_db(db_path, &db::derive_db_key(key)).context("Failed to open vault DB")?;
+        db::load_embeddings_for_model(&conn, model_version)?
+            .into_iter()
+            .filter(|(id, _)| !remote_ids.contains(id.as_str()))
+            .collect()
+    };
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    for batch in missing.chunks(CHUNK_SIZE) {
+        let chunk_id = uuid::Uuid::new_v4().to_string();
+        let key_name = chunk_key(model_version, &chunk_id);
+
+        let payload = EmbeddingChunk {
+            model_version: model_version.to_string(),
+            entries: batch
+                .iter()
+                .map(|(id, vector)| ChunkRecord {
+                    photo_id: id.clone(),
+                    vector: BASE64.encode(db::embedding_to_bytes(vector)),
+                })
+                .collect(),
+        };
+
+        let json = serde_json::to_vec(&payload).context("Failed to serialize chunk")?;
+        let enc_bytes = crypto::encrypt(&json, key).context("Failed to encrypt chunk")?;
+        storage
+            .upload_file(&key_name, enc_bytes)
+            .await
+            .with_context(|| format!("Failed to upload chunk {}", key_name))?;
+
+        index.chunks.push(ChunkEntry {
+            key: key_name,
+            photo_ids: batch.iter().map(|(id, _)| id.clone()).collect(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        });
+        stats.chunks_uploaded += 1;
+        stats.uploaded += batch.len();
+    }
+
+    index.updated_at = chrono::Utc::now().to_rfc3339();
+    let json = serde_json::to_vec(&index).context("Failed to serialize embedding index")?;
+    let enc_bytes = crypto::encrypt(&json, key).context("Failed to encrypt embedding index")?;
+    storage
+        .upload_file(&index_key(model_version), enc_bytes)
+        .await
+        .context("Failed to upload embedding index")?;
+
+    Ok(())
+}
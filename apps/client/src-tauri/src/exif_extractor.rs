@@ -1,13 +1,18 @@
 //! Metadata extraction module using nom-exif
 //!
 //! Supports extraction of EXIF and other metadata from Images (JPEG, HEIF, PNG, WebP)
-//! and Videos (MOV, MP4, QuickTime).
+//! and Videos (MOV, MP4, QuickTime). XMP ratings, keywords and captions are read from
+//! `.xmp` sidecars or the packet embedded in the file.
 
 use anyhow::Result;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
 use nom_exif::{parse_exif, ExifIter}; 
 use std::fs::File;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// How far into a file to look for an embedded XMP packet
+const XMP_SCAN_LIMIT: u64 = 4 * 1024 * 1024;
 
 /// Extracted Metadata
 #[derive(Debug, Clone, Default)]
@@ -30,6 +35,14 @@ pub struct ExifMetadata {
     // Intrinsic dimensions (from metadata, might differ from actual file stream)
     pub width: Option<u32>,
     pub height: Option<u32>,
+
+    // -- XMP curation (e.g. from Lightroom) --
+    /// `xmp:Rating`, 0-5 (rejected photos are ignored)
+    pub rating: Option<u8>,
+    /// `dc:subject` keywords
+    pub keywords: Vec<String>,
+    /// `dc:description` caption
+    pub description: Option<String>,
 }
 
 impl ExifMetadata {
@@ -55,6 +68,8 @@ pub fn extract_metadata(path: &Path) -> ExifMetadata {
 
 fn try_extract(path: &Path) -> Result<ExifMetadata> {
     let mut meta = ExifMetadata::default();
+    let mut wall_clock: Option<NaiveDateTime> = None;
+    let mut offset: Option<FixedOffset> = None;
     
     // Pass 1: Standard Tags
     // We open file for reading standard tags
@@ -73,10 +88,17 @@ fn try_extract(path: &Path) -> Result<ExifMetadata> {
             match tag_id {
                  // -- DATE & TIME --
                 0x9003 | 0x9004 | 0x9291 => {
-                    if meta.captured_at.is_none() {
-                        meta.captured_at = parse_date_str(&value_str);
+                    if meta.captured_at.is_none() && wall_clock.is_none() {
+                        match parse_date_str(&value_str) {
+                            Some(ExifDate::Exact(t)) => meta.captured_at = Some(t),
+                            Some(ExifDate::WallClock(t)) => wall_clock = Some(t),
+                            None => {}
+                        }
                     }
                 }
+                // OffsetTimeOriginal, falling back to OffsetTime
+                0x9011 => offset = parse_offset(&value_str).or(offset),
+                0x9010 => offset = offset.or_else(|| parse_offset(&value_str)),
                 
                 // -- CAMERA INFO --
                 0x010f => meta.make = Some(value_str.to_string()),
@@ -97,6 +119,10 @@ fn try_extract(path: &Path) -> Result<ExifMetadata> {
         }
     }
     
+    if meta.captured_at.is_none() {
+        meta.captured_at = wall_clock.map(|t| resolve_wall_clock(t, offset));
+    }
+    
     // Pass 2: Precise GPS via nom-exif built-in parsing (requires separate File handle)
     // This calls iter.parse_gps_info() which properly handles Rational comparisons and alignment
     if let Ok(file_gps) = File::open(path) {
@@ -124,10 +150,167 @@ fn try_extract(path: &Path) -> Result<ExifMetadata> {
              Err(e) => log::warn!("[Exif] Second pass parse_exif error: {}", e),
         }
     }
+
+    // Pass 3: XMP curation
+    if let Some(xmp) = read_xmp(path) {
+        meta.rating = parse_xmp_rating(&xmp);
+        meta.keywords = parse_xmp_subjects(&xmp);
+        meta.description = parse_xmp_description(&xmp);
+    }
     
     Ok(meta)
 }
 
+/// Read the XMP packet for a file, preferring a sidecar (`IMG.xmp` or `IMG.CR2.xmp`)
+/// over the packet embedded in the file itself
+fn read_xmp(path: &Path) -> Option<String> {
+    let sidecars = [
+        path.with_extension("xmp"),
+        PathBuf::from(format!("{}.xmp", path.display())),
+    ];
+    for sidecar in sidecars {
+        if let Ok(xmp) = std::fs::read_to_string(&sidecar) {
+            return Some(xmp);
+        }
+    }
+
+    let mut buf = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(XMP_SCAN_LIMIT)
+        .read_to_end(&mut buf)
+        .ok()?;
+
+    let start = find_bytes(&buf, b"<x:xmpmeta")?;
+    let end = find_bytes(&buf[start..], b"</x:xmpmeta>")? + start + b"</x:xmpmeta>".len();
+    Some(String::from_utf8_lossy(&buf[start..end]).into_owned())
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Value of a simple XMP property, written either as an attribute (`xmp:Rating="4"`)
+/// or as an element (`<xmp:Rating>4</xmp:Rating>`)
+fn xmp_property(xmp: &str, name: &str) -> Option<String> {
+    let mut offset = 0;
+    while let Some(pos) = xmp[offset..].find(name) {
+        let rest = xmp[offset + pos + name.len()..].trim_start();
+        offset += pos + name.len();
+
+        if let Some(value) = rest.strip_prefix('=') {
+            let value = value.trim_start();
+            let quote = value.chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &value[1..];
+                return value.find(quote).map(|end| xml_unescape(&value[..end]));
+            }
+        } else if let Some(value) = rest.strip_prefix('>') {
+            return value.find('<').map(|end| xml_unescape(value[..end].trim()));
+        }
+    }
+    None
+}
+
+fn parse_xmp_rating(xmp: &str) -> Option<u8> {
+    let rating: f64 = xmp_property(xmp, "xmp:Rating")?.parse().ok()?;
+    // -1 marks a rejected photo in Lightroom
+    if (0.0..=5.0).contains(&rating) {
+        Some(rating.round() as u8)
+    } else {
+        None
+    }
+}
+
+/// Items of an XMP array property (`rdf:Bag`, `rdf:Seq` or `rdf:Alt`)
+fn xmp_list(xmp: &str, name: &str) -> Vec<String> {
+    let Some(start) = xmp.find(&format!("<{}", name)) else {
+        return Vec::new();
+    };
+    let block = &xmp[start..];
+    let block = &block[..block.find(&format!("</{}>", name)).unwrap_or(block.len())];
+
+    let mut items = Vec::new();
+    let mut rest = block;
+    while let Some(pos) = rest.find("<rdf:li") {
+        rest = &rest[pos..];
+        let Some(open_end) = rest.find('>') else { break };
+        let Some(close) = rest.find("</rdf:li>") else { break };
+        if close > open_end {
+            let item = xml_unescape(rest[open_end + 1..close].trim());
+            if !item.is_empty() {
+                items.push(item);
+            }
+        }
+        rest = &rest[close + "</rdf:li>".len()..];
+    }
+    items
+}
+
+/// Keywords from the `dc:subject` bag
+fn parse_xmp_subjects(xmp: &str) -> Vec<String> {
+    xmp_list(xmp, "dc:subject")
+}
+
+/// Caption from `dc:description` (the first language alternative)
+fn parse_xmp_description(xmp: &str) -> Option<String> {
+    xmp_list(xmp, "dc:description").into_iter().next()
+}
+
+/// Parse an XMP GPS coordinate, `DDD,MM.mmmK` or `DDD,MM,SSK` (K is N, S, E or W)
+fn parse_xmp_coordinate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let sign = match value.chars().last()?.to_ascii_uppercase() {
+        'N' | 'E' => 1.0,
+        'S' | 'W' => -1.0,
+        _ => return None,
+    };
+    let parts = value[..value.len() - 1]
+        .split(',')
+        .map(|p| p.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let degrees = match parts.as_slice() {
+        [d, m] => d + m / 60.0,
+        [d, m, s] => d + m / 60.0 + s / 3600.0,
+        _ => return None,
+    };
+    Some(sign * degrees)
+}
+
+/// Metadata from an XMP sidecar alone, as exported by Apple Photos ("Export IPTC
+/// as XMP") and most photo managers: capture time, location, rating, keywords and
+/// caption
+pub fn parse_xmp_sidecar(xmp: &str) -> ExifMetadata {
+    let captured_at = ["exif:DateTimeOriginal", "photoshop:DateCreated", "xmp:CreateDate"]
+        .iter()
+        .filter_map(|name| xmp_property(xmp, name))
+        .find_map(|value| match parse_date_str(&value)? {
+            ExifDate::Exact(t) => Some(t),
+            ExifDate::WallClock(t) => Some(resolve_wall_clock(t, None)),
+        });
+    let latitude = xmp_property(xmp, "exif:GPSLatitude").and_then(|v| parse_xmp_coordinate(&v));
+    let longitude = xmp_property(xmp, "exif:GPSLongitude").and_then(|v| parse_xmp_coordinate(&v));
+    let has_location = latitude.is_some() && longitude.is_some();
+
+    ExifMetadata {
+        captured_at,
+        latitude: latitude.filter(|_| has_location),
+        longitude: longitude.filter(|_| has_location),
+        rating: parse_xmp_rating(xmp),
+        keywords: parse_xmp_subjects(xmp),
+        description: parse_xmp_description(xmp),
+        ..Default::default()
+    }
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
 /// Parse ISO 6709 string (e.g. "+48.8577+002.295/" or "+51.51326-000.11307+14.313/") to lat/lon
 /// Format: ±DD.DDDD±DDD.DDDD[±AAA.AAA]/  (altitude is optional)
 fn parse_iso6709_str(s: &str) -> Option<(f64, f64)> {
@@ -155,14 +338,98 @@ fn parse_iso6709_str(s: &str) -> Option<(f64, f64)> {
     Some((lat, lon))
 }
 
-fn parse_date_str(s: &str) -> Option<DateTime<Utc>> {
+/// An EXIF date, with or without a UTC offset
+enum ExifDate {
+    Exact(DateTime<Utc>),
+    /// Camera wall-clock time (`DateTimeOriginal` has no time zone)
+    WallClock(NaiveDateTime),
+}
+
+fn parse_date_str(s: &str) -> Option<ExifDate> {
     let s = s.trim().trim_matches('"');
     
-    if let Ok(naive) = NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S") {
-        return Some(DateTime::from_naive_utc_and_offset(naive, Utc));
+    for format in ["%Y:%m:%d %H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(ExifDate::WallClock(naive));
+        }
     }
     if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
-        return Some(dt.with_timezone(&Utc));
+        return Some(ExifDate::Exact(dt.with_timezone(&Utc)));
+    }
+    if let Ok(dt) = DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S %:z") {
+        return Some(ExifDate::Exact(dt.with_timezone(&Utc)));
     }
     None
 }
+
+/// Parse an EXIF offset tag such as "+02:00"
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let s = s.trim().trim_matches('"');
+    DateTime::parse_from_str(&format!("2000-01-01 00:00:00 {}", s), "%Y-%m-%d %H:%M:%S %:z")
+        .ok()
+        .map(|t| *t.offset())
+}
+
+/// Convert camera wall-clock time to UTC using the EXIF offset. Without one, assume
+/// the device's time zone, so the photo lands on the day shown on the camera.
+fn resolve_wall_clock(naive: NaiveDateTime, offset: Option<FixedOffset>) -> DateTime<Utc> {
+    let local = match offset {
+        Some(offset) => offset.from_local_datetime(&naive).single().map(|t| t.with_timezone(&Utc)),
+        None => Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+    };
+    local.unwrap_or_else(|| Utc.from_utc_datetime(&naive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmp:Rating="4" xmp:RatingPercent="80">
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>Iceland</rdf:li>
+     <rdf:li>Rock &amp; Ice</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    #[test]
+    fn test_parse_xmp() {
+        assert_eq!(parse_xmp_rating(XMP), Some(4));
+        assert_eq!(parse_xmp_subjects(XMP), vec!["Iceland", "Rock & Ice"]);
+    }
+
+    #[test]
+    fn test_parse_xmp_element_rating() {
+        assert_eq!(parse_xmp_rating("<xmp:Rating>5</xmp:Rating>"), Some(5));
+        assert_eq!(parse_xmp_rating("<xmp:Rating>-1</xmp:Rating>"), None);
+        assert!(parse_xmp_subjects("<xmp:Rating>5</xmp:Rating>").is_empty());
+    }
+
+    #[test]
+    fn test_parse_xmp_sidecar() {
+        let xmp = r#"<rdf:Description exif:GPSLatitude="64,8.5N" exif:GPSLongitude="21,56,15W">
+   <photoshop:DateCreated>2023-06-21T23:10:00Z</photoshop:DateCreated>
+   <dc:description><rdf:Alt><rdf:li xml:lang="x-default">Midnight sun</rdf:li></rdf:Alt></dc:description>
+  </rdf:Description>"#;
+        let meta = parse_xmp_sidecar(xmp);
+
+        assert_eq!(meta.captured_at.unwrap().to_rfc3339(), "2023-06-21T23:10:00+00:00");
+        assert!((meta.latitude.unwrap() - 64.141_666).abs() < 1e-5);
+        assert!((meta.longitude.unwrap() + 21.9375).abs() < 1e-9);
+        assert_eq!(meta.description.as_deref(), Some("Midnight sun"));
+    }
+
+    #[test]
+    fn test_wall_clock_with_offset() {
+        let Some(ExifDate::WallClock(naive)) = parse_date_str("2024:01:01 01:30:00") else {
+            panic!("expected a wall-clock date");
+        };
+        let utc = resolve_wall_clock(naive, parse_offset("+02:00"));
+        assert_eq!(utc.to_rfc3339(), "2023-12-31T23:30:00+00:00");
+    }
+}
@@ -1,7 +1,7 @@
 use std::path::Path;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum MediaType {
     Image,
     Video,
@@ -128,6 +128,29 @@ pub fn get_file_size(path: &Path) -> Result<u64, FileFilterError> {
         .map_err(|e| FileFilterError::ReadError(e.to_string()))
 }
 
+const GLOB_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// Compile user glob patterns, skipping invalid ones
+pub fn compile_globs(patterns: &[String]) -> Vec<glob::Pattern> {
+    patterns.iter().filter_map(|p| glob::Pattern::new(p).ok()).collect()
+}
+
+/// Whether a path relative to an imported folder matches one of `include` (or
+/// `include` is empty) and none of `exclude`. Matching is case-insensitive.
+pub fn matches_globs(relative: &Path, include: &[glob::Pattern], exclude: &[glob::Pattern]) -> bool {
+    let included = include.is_empty() || include.iter().any(|p| p.matches_path_with(relative, GLOB_OPTIONS));
+    included && !exclude.iter().any(|p| p.matches_path_with(relative, GLOB_OPTIONS))
+}
+
+/// Whether a file or directory name is hidden (dot files, including macOS `._*` resource forks)
+pub fn is_hidden_name(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +203,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_matches_globs() {
+        let include = compile_globs(&["DCIM/**/*.jpg".to_string(), "*.mp4".to_string()]);
+        let exclude = compile_globs(&["**/.thumbnails/**".to_string()]);
+
+        assert!(matches_globs(Path::new("DCIM/100CANON/IMG_1.JPG"), &include, &exclude));
+        assert!(matches_globs(Path::new("clip.mp4"), &include, &exclude));
+        assert!(!matches_globs(Path::new("DCIM/.thumbnails/IMG_1.jpg"), &include, &exclude));
+        assert!(!matches_globs(Path::new("notes.png"), &include, &exclude));
+        assert!(matches_globs(Path::new("anything.png"), &[], &exclude));
+    }
+
     #[test]
     fn test_unsupported() {
         assert!(detect_media_type(Path::new("document.pdf")).is_err());
@@ -0,0 +1,433 @@
+//! Folder import with filtering and preview
+//!
+//! Walks the folders picked by the user and keeps supported media that pass the
+//! user's glob patterns, size and modification date limits and hidden-file policy.
+//! `preview_folder_import` summarizes what an import would upload (counts, size,
+//! duplicates, estimated cost) before `import_folder` queues it.
+
+use crate::file_filter::{self, MediaType};
+use crate::pricing;
+use crate::upload_manager::{self, UploadItem};
+use crate::vault::StorageTier;
+use crate::{AppState, UploadManagerState};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+/// What to do with dot files and files inside dot folders
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HiddenFiles {
+    #[default]
+    Skip,
+    Include,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FolderImportRequest {
+    /// Folders to walk recursively; files are taken as they are
+    pub paths: Vec<String>,
+    /// Glob patterns matched against the path relative to its folder (all files if empty)
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub min_size_bytes: Option<u64>,
+    pub max_size_bytes: Option<u64>,
+    /// Only files last modified at or after this time (RFC3339 or `YYYY-MM-DD`)
+    pub modified_after: Option<String>,
+    /// Only files last modified before this time (RFC3339 or `YYYY-MM-DD`)
+    pub modified_before: Option<String>,
+    pub hidden: HiddenFiles,
+    pub fresh_upload: bool,
+    /// Name of the upload batch (defaults to the import time)
+    pub batch_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportBucket {
+    pub media_type: MediaType,
+    pub count: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportPreview {
+    pub total_count: usize,
+    pub total_bytes: u64,
+    pub by_media_type: Vec<ImportBucket>,
+    /// Files already in the vault or repeated within the import
+    pub duplicate_count: usize,
+    pub duplicate_bytes: u64,
+    /// Files found but left out by the filters (unsupported, patterns, limits, hidden)
+    pub filtered_count: usize,
+    /// Storage cost of the new files per month. An upper bound: images are
+    /// usually compressed before upload.
+    pub estimated_monthly_cost_usd: f64,
+    /// One-time request cost of uploading the new files
+    pub estimated_upload_cost_usd: f64,
+    pub fresh_upload_auto_disabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FolderImportResult {
+    pub items: Vec<UploadItem>,
+    pub skipped_duplicates: usize,
+    pub fresh_upload_auto_disabled: bool,
+}
+
+/// A file selected for import
+#[derive(Debug, Clone)]
+struct ImportFile {
+    path: PathBuf,
+    size: u64,
+    media_type: MediaType,
+}
+
+/// Selected files plus the number left out by the filters
+#[derive(Debug, Default)]
+struct WalkResult {
+    files: Vec<ImportFile>,
+    filtered: usize,
+}
+
+/// Parse a date limit. Plain dates mean midnight UTC.
+fn parse_limit(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+        })
+        .map_err(|_| format!("Invalid date: {}", value))
+}
+
+struct Filters {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+    min_size: u64,
+    max_size: u64,
+    /// Modification time limits in ms since epoch
+    after: Option<i64>,
+    before: Option<i64>,
+    hidden: HiddenFiles,
+}
+
+impl Filters {
+    fn new(request: &FolderImportRequest) -> Result<Self, String> {
+        for pattern in request.include.iter().chain(&request.exclude) {
+            glob::Pattern::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+        }
+        let limit = |value: &Option<String>| -> Result<Option<i64>, String> {
+            value
+                .as_deref()
+                .map(|v| parse_limit(v).map(|t| t.timestamp_millis()))
+                .transpose()
+        };
+
+        Ok(Self {
+            include: file_filter::compile_globs(&request.include),
+            exclude: file_filter::compile_globs(&request.exclude),
+            min_size: request.min_size_bytes.unwrap_or(0),
+            max_size: request.max_size_bytes.unwrap_or(u64::MAX),
+            after: limit(&request.modified_after)?,
+            before: limit(&request.modified_before)?,
+            hidden: request.hidden,
+        })
+    }
+
+    /// The file to import, if it passes every filter
+    fn check(&self, path: &Path, relative: &Path) -> Option<ImportFile> {
+        if self.hidden == HiddenFiles::Skip && relative.iter().any(file_filter::is_hidden_name) {
+            return None;
+        }
+        if !file_filter::matches_globs(relative, &self.include, &self.exclude) {
+            return None;
+        }
+
+        let meta = std::fs::metadata(path).ok()?;
+        let size = meta.len();
+        if size < self.min_size || size > self.max_size {
+            return None;
+        }
+        if self.after.is_some() || self.before.is_some() {
+            let modified = upload_manager::modified_millis(&meta)?;
+            if self.after.is_some_and(|after| modified < after) || self.before.is_some_and(|before| modified >= before) {
+                return None;
+            }
+        }
+
+        let media_type = file_filter::detect_media_type(path).ok()?;
+        Some(ImportFile {
+            path: path.to_path_buf(),
+            size,
+            media_type,
+        })
+    }
+}
+
+/// Walk the requested paths and apply the filters. Each file is listed once.
+fn walk(paths: &[String], filters: &Filters) -> WalkResult {
+    let mut result = WalkResult::default();
+    let mut seen = HashSet::new();
+    let mut consider = |path: &Path, relative: &Path, result: &mut WalkResult| {
+        if !seen.insert(path.to_path_buf()) {
+            return;
+        }
+        match filters.check(path, relative) {
+            Some(file) => result.files.push(file),
+            None => result.filtered += 1,
+        }
+    };
+
+    for root in paths.iter().map(Path::new) {
+        if root.is_file() {
+            let name = root.file_name().map(Path::new).unwrap_or(root);
+            consider(root, name, &mut result);
+            continue;
+        }
+
+        let entries = walkdir::WalkDir::new(root)
+            .into_iter()
+            // Don't descend into hidden folders unless they're included
+            .filter_entry(|e| {
+                e.depth() == 0 || filters.hidden == HiddenFiles::Include || !file_filter::is_hidden_name(e.file_name())
+            })
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file());
+        for entry in entries {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            consider(entry.path(), relative, &mut result);
+        }
+    }
+    result
+}
+
+/// Summarize an import. `duplicate_of` says whether each file (same order) is a duplicate.
+fn summarize(files: &[ImportFile], duplicates: &[bool], filtered: usize, fresh_upload: bool, storage_tier: StorageTier) -> ImportPreview {
+    let mut preview = ImportPreview {
+        filtered_count: filtered,
+        ..Default::default()
+    };
+    let mut by_media_type: HashMap<MediaType, ImportBucket> = HashMap::new();
+
+    preview.fresh_upload_auto_disabled = fresh_upload && exceeds_fresh_upload_limits(files, duplicates);
+    let fresh_upload = fresh_upload && !preview.fresh_upload_auto_disabled;
+
+    for (file, duplicate) in files.iter().zip(duplicates) {
+        preview.total_count += 1;
+        preview.total_bytes += file.size;
+        let bucket = by_media_type.entry(file.media_type).or_insert(ImportBucket {
+            media_type: file.media_type,
+            count: 0,
+            bytes: 0,
+        });
+        bucket.count += 1;
+        bucket.bytes += file.size;
+
+        if *duplicate {
+            preview.duplicate_count += 1;
+            preview.duplicate_bytes += file.size;
+            continue;
+        }
+
+        let class = upload_manager::original_storage_class(file.media_type, fresh_upload, storage_tier);
+        let tier = upload_manager::tier_label(class.as_ref());
+        preview.estimated_monthly_cost_usd += pricing::monthly_storage_cost(tier, file.size);
+        // Original, metadata sidecar (Standard) and, except for audio, a thumbnail (Glacier IR)
+        let mut puts = pricing::put_price(tier) + pricing::put_price("Standard");
+        if file.media_type != MediaType::Audio {
+            puts += pricing::put_price("GlacierIR");
+        }
+        preview.estimated_upload_cost_usd += puts / 1000.0;
+    }
+
+    preview.by_media_type = by_media_type.into_values().collect();
+    preview.by_media_type.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    preview
+}
+
+/// Whether the files that will be uploaded (not the duplicates) are too many or too
+/// large for Fresh Upload. Preview and import both decide with this.
+fn exceeds_fresh_upload_limits(files: &[ImportFile], duplicates: &[bool]) -> bool {
+    let new_count = duplicates.iter().filter(|d| !**d).count();
+    let new_bytes: u64 = files.iter().zip(duplicates).filter(|(_, d)| !**d).map(|(f, _)| f.size).sum();
+    upload_manager::exceeds_fresh_upload_limits(new_count, new_bytes)
+}
+
+/// Whether each file (same order) repeats a photo in the vault or an earlier file.
+/// Hashes come from the upload manager's cache, so `add_files` doesn't read the
+/// files again; with `refresh` the cache is replaced by these files' hashes.
+async fn find_duplicates(
+    state: &AppState,
+    upload_state: &UploadManagerState,
+    files: &[ImportFile],
+    refresh: bool,
+) -> Result<Vec<bool>, String> {
+    let config = state.config.lock().await.clone().ok_or("Vault not loaded")?;
+    let hash_key = upload_manager::content_hash_key(&config).ok_or("Invalid vault key")?;
+    let hash_cache = upload_state
+        .manager
+        .lock()
+        .await
+        .as_ref()
+        .ok_or("Upload manager not initialized")?
+        .hash_cache();
+
+    let to_hash: Vec<PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+    let hashes: Vec<Option<String>> = tokio::task::spawn_blocking(move || {
+        if refresh {
+            hash_cache.hash_all(&to_hash, &hash_key)
+        } else {
+            to_hash.iter().map(|path| hash_cache.get_or_hash(path, &hash_key).ok()).collect()
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::with_capacity(hashes.len());
+    for hash in &hashes {
+        let duplicate = match hash {
+            Some(hash) => {
+                !seen.insert(hash.clone())
+                    || upload_manager::find_vault_duplicate(conn, hash)
+                        .map_err(|e| e.to_string())?
+                        .is_some()
+            }
+            None => false,
+        };
+        duplicates.push(duplicate);
+    }
+    Ok(duplicates)
+}
+
+/// Walk and filter on the blocking pool
+async fn select_files(request: &FolderImportRequest) -> Result<WalkResult, String> {
+    let filters = Filters::new(request)?;
+    let paths = request.paths.clone();
+    tokio::task::spawn_blocking(move || walk(&paths, &filters))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Summarize what importing the selected folders would upload, without queueing anything
+#[tauri::command]
+pub async fn preview_folder_import(
+    state: State<'_, AppState>,
+    upload_state: State<'_, UploadManagerState>,
+    request: FolderImportRequest,
+) -> Result<ImportPreview, String> {
+    let storage_tier = state.config.lock().await.as_ref().ok_or("Vault not loaded")?.storage_tier;
+    let walked = select_files(&request).await?;
+    let duplicates = find_duplicates(&state, &upload_state, &walked.files, true).await?;
+
+    Ok(summarize(
+        &walked.files,
+        &duplicates,
+        walked.filtered,
+        request.fresh_upload,
+        storage_tier,
+    ))
+}
+
+/// Queue the files selected by `request`
+#[tauri::command]
+pub async fn import_folder(
+    state: State<'_, AppState>,
+    upload_state: State<'_, UploadManagerState>,
+    request: FolderImportRequest,
+    skip_duplicates: bool,
+) -> Result<FolderImportResult, String> {
+    let walked = select_files(&request).await?;
+    // Duplicates are never uploaded (skipped or held as `Duplicate`), so they
+    // don't count towards the limits, like in the preview
+    let fresh_upload_auto_disabled = request.fresh_upload && {
+        let duplicates = find_duplicates(&state, &upload_state, &walked.files, false).await?;
+        exceeds_fresh_upload_limits(&walked.files, &duplicates)
+    };
+    let fresh_upload = request.fresh_upload && !fresh_upload_auto_disabled;
+
+    let manager_guard = upload_state.manager.lock().await;
+    let manager = manager_guard
+        .as_ref()
+        .ok_or("Upload manager not initialized")?;
+
+    let paths = walked.files.into_iter().map(|f| f.path).collect();
+    let (items, skipped_duplicates) = manager
+        .add_files(paths, fresh_upload, None, skip_duplicates, request.batch_name)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(FolderImportResult {
+        items,
+        skipped_duplicates,
+        fresh_upload_auto_disabled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_filters() {
+        let root = std::env::temp_dir().join(format!("boreal-import-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("trip/.cache")).unwrap();
+        for (name, size) in [
+            ("a.jpg", 10),
+            ("big.jpg", 1000),
+            (".hidden.jpg", 10),
+            ("notes.txt", 10),
+            ("trip/b.mp4", 10),
+            ("trip/.cache/c.jpg", 10),
+        ] {
+            std::fs::write(root.join(name), vec![0u8; size]).unwrap();
+        }
+
+        let request = FolderImportRequest {
+            paths: vec![root.to_string_lossy().to_string()],
+            max_size_bytes: Some(100),
+            ..Default::default()
+        };
+        let walked = walk(&request.paths, &Filters::new(&request).unwrap());
+        let mut names: Vec<String> = walked
+            .files
+            .iter()
+            .map(|f| f.path.strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["a.jpg", "trip/b.mp4"]);
+        // big.jpg, .hidden.jpg and notes.txt; the hidden folder isn't walked
+        assert_eq!(walked.filtered, 3);
+
+        let request = FolderImportRequest {
+            hidden: HiddenFiles::Include,
+            exclude: vec!["trip/**".to_string()],
+            ..request
+        };
+        assert_eq!(walk(&request.paths, &Filters::new(&request).unwrap()).files.len(), 2);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_summarize_costs() {
+        let gb = 1024 * 1024 * 1024;
+        let files = vec![
+            ImportFile { path: "a.jpg".into(), size: gb, media_type: MediaType::Image },
+            ImportFile { path: "b.jpg".into(), size: gb, media_type: MediaType::Image },
+            ImportFile { path: "c.mp3".into(), size: gb, media_type: MediaType::Audio },
+        ];
+        let preview = summarize(&files, &[false, true, false], 0, false, StorageTier::DeepArchive);
+
+        assert_eq!(preview.total_count, 3);
+        assert_eq!(preview.duplicate_count, 1);
+        let expected = pricing::COST_GLACIER_DEEP + pricing::COST_S3_INSTANT;
+        assert!((preview.estimated_monthly_cost_usd - expected).abs() < 1e-9);
+        assert_eq!(preview.by_media_type.len(), 2);
+    }
+}
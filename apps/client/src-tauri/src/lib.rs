@@ -1,24 +1,42 @@
+mod albums;
 mod cache;
 mod crypto;
+mod curation;
 mod db;
+mod devices;
 mod embedding;
 mod exif_extractor;
 mod file_filter;
+mod folder_import;
 
+mod library_import;
 mod manifest;
 pub mod media_processor;
 mod memories;
+mod motion_photo;
 mod originals_cache;
 mod pairing;
+mod photo_query;
+pub mod pricing;
 mod qr_transfer;
+mod recovery;
+mod smart_albums;
+mod stats;
 mod storage;
+mod sync_daemon;
+mod text_search;
+mod timeline;
+mod trash;
 mod upload_manager;
+mod upload_queue;
 mod tray_manager;
 mod vault;
+mod verification;
+mod watch_folders;
 
 use crate::cache::ThumbnailCache;
 use crate::storage::Storage;
-use crate::upload_manager::{QueueState, UploadItem, UploadManager};
+use crate::upload_manager::{QueueOrder, QueueState, UploadConcurrency, UploadItem, UploadManager};
 use crate::vault::VaultConfig;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use rusqlite::Connection;
@@ -57,12 +75,30 @@ struct OriginalsCacheState {
     cache: Arc<Mutex<Option<originals_cache::OriginalsCache>>>,
 }
 
+struct SyncDaemonState {
+    daemon: Mutex<Option<sync_daemon::SyncDaemon>>,
+}
+
+struct FolderWatcherState {
+    watcher: Mutex<Option<watch_folders::FolderWatcher>>,
+}
+
 // Commands
 
 use crate::vault::store;
 use crate::vault::VaultPublic;
 
 
+/// Open a vault's manifest.db by ID, keyed from its stored config
+fn open_vault_db(
+    app: &AppHandle,
+    vault_id: &str,
+    db_path: &std::path::Path,
+) -> Result<Connection, String> {
+    let config = store::load_vault(app, vault_id)?;
+    db::init_db(db_path, &config.db_key()?).map_err(|e| e.to_string())
+}
+
 /// Get all vaults with name/visits from SQLite
 #[tauri::command]
 async fn get_vaults(app: AppHandle) -> Result<Vec<VaultPublic>, String> {
@@ -77,7 +113,7 @@ async fn get_vaults(app: AppHandle) -> Result<Vec<VaultPublic>, String> {
 
         let (name, visits, size) = if db_path.exists() {
             // Read from SQLite
-            let conn = db::init_db(&db_path).map_err(|e| e.to_string())?;
+            let conn = open_vault_db(&app, &id, &db_path)?;
             let name = db::get_metadata(&conn, "name")
                 .map_err(|e| e.to_string())?
                 .unwrap_or_else(|| "Untitled Vault".to_string());
@@ -122,6 +158,8 @@ async fn load_vault(
     cache_state: State<'_, CacheState>,
     originals_cache_state: State<'_, OriginalsCacheState>,
     embedding_state: State<'_, embedding::EmbeddingState>,
+    sync_state: State<'_, SyncDaemonState>,
+    watcher_state: State<'_, FolderWatcherState>,
     id: String,
 ) -> Result<(), String> {
     // 1. Get config from JSON (credentials only)
@@ -143,7 +181,7 @@ async fn load_vault(
     }
 
     let db_path = vault_dir.join("manifest.db");
-    let conn = db::init_db(&db_path).map_err(|e| e.to_string())?;
+    let conn = db::init_db(&db_path, &config.db_key()?).map_err(|e| e.to_string())?;
 
     // 5. Migration: Move name/visits from JSON to SQLite (one-time)
     let needs_migration = db::get_metadata(&conn, "name")
@@ -178,6 +216,11 @@ async fn load_vault(
     db::set_metadata(&conn, "visits", &(current_visits + 1).to_string())
         .map_err(|e| format!("Failed to update visits: {}", e))?;
 
+    // Register this device in the vault's device registry (synced via manifest)
+    if let Err(e) = devices::register_current_device(&app, &conn) {
+        log::warn!("[Devices] Failed to register device: {}", e);
+    }
+
     // 7. Initialize ThumbnailCache for this vault
     let thumbnail_cache = ThumbnailCache::new(&vault_dir).map_err(|e| e.to_string())?;
     *cache_state.thumbnail_cache.lock().await = Some(thumbnail_cache);
@@ -189,9 +232,29 @@ async fn load_vault(
     log::info!("[OriginalsCache] Initialized for vault {}", config.id);
 
     // 8. Update State
+    // Stop the previous vault's background tasks first, so they can't act on the
+    // new vault's DB with the old vault's storage (or the other way round).
+    // The daemon lock is held until the new vault is in place, so a pending
+    // `start_sync_daemon` of the previous vault sees that it was replaced.
+    let mut daemon_guard = sync_state.daemon.lock().await;
+    if let Some(daemon) = daemon_guard.take() {
+        daemon.stop().await;
+    }
+    let old_watcher = watcher_state.watcher.lock().await.take();
+    if let Some(watcher) = old_watcher {
+        watcher.stop().await;
+    }
+
     *state.storage.lock().await = Some(storage);
     *state.db.lock().await = Some(conn);
-    *state.config.lock().await = Some(config);
+    *state.config.lock().await = Some(config.clone());
+    drop(daemon_guard);
+
+    *watcher_state.watcher.lock().await = Some(watch_folders::FolderWatcher::start(
+        app.clone(),
+        state.db.clone(),
+        config,
+    ));
 
     // 9. Background: Download and merge manifest from S3, then push updates, then embed
     // (This is done async after returning to not block UI)
@@ -199,6 +262,7 @@ async fn load_vault(
     let db_clone = state.db.clone();
     let config_clone = state.config.lock().await.clone();
     let app_dir_clone = app_dir.clone();
+    let app_clone = app.clone();
     
     let embedding_state_clone = embedding_state.inner().clone();
 
@@ -208,17 +272,26 @@ async fn load_vault(
             match sync_manifest_download_internal(&storage, &db_clone, &config).await {
                 Ok(_) => {
                     // 2. Push our updated state (new visits count + merged changes) to cloud
-                    if let Err(e) =
-                        sync_manifest_upload_internal(&storage, &db_clone, &config).await
-                    {
-                        log::info!("[Manifest Sync] Background upload failed: {}", e);
+                    let stamp = match sync_manifest_upload_internal(&storage, &db_clone, &config).await {
+                        Ok(etag) => etag,
+                        Err(e) => {
+                            log::info!("[Manifest Sync] Background upload failed: {}", e);
+                            None
+                        }
+                    };
+                    start_sync_daemon(&app_clone, &storage, &db_clone, &config, stamp).await;
+
+                    // Purge photos past the trash retention period
+                    if let Err(e) = trash::purge_expired(&app_clone, &storage, &db_clone).await {
+                        log::warn!("[Trash] Failed to purge expired photos: {}", e);
                     }
                     
                     // 3. Embed cached photos
                     {
                         let result = embed_all_photos_internal(
                             &app_dir_clone,
-                            &config.id,
+                            &config,
+                            &storage,
                             &embedding_state_clone
                         ).await;
                         match result {
@@ -238,6 +311,7 @@ async fn load_vault(
                 }
                 Err(e) => {
                     log::info!("[Manifest Sync] Background download failed: {}", e);
+                    start_sync_daemon(&app_clone, &storage, &db_clone, &config, None).await;
                 }
             }
         }
@@ -246,6 +320,37 @@ async fn load_vault(
     Ok(())
 }
 
+/// Start the background sync of a vault once its initial sync is done, unless
+/// another vault was loaded in the meantime. `last_stamp` is the manifest ETag
+/// the initial sync left behind, so the daemon doesn't merge it again.
+async fn start_sync_daemon(
+    app: &AppHandle,
+    storage: &Storage,
+    db: &Arc<Mutex<Option<Connection>>>,
+    config: &VaultConfig,
+    last_stamp: Option<String>,
+) {
+    let sync_state = app.state::<SyncDaemonState>();
+    let mut daemon_guard = sync_state.daemon.lock().await;
+
+    let state = app.state::<AppState>();
+    let loaded = state.config.lock().await.as_ref().map(|c| c.id.clone());
+    if loaded.as_deref() != Some(config.id.as_str()) {
+        return;
+    }
+
+    if let Some(daemon) = daemon_guard.take() {
+        daemon.stop().await;
+    }
+    *daemon_guard = Some(sync_daemon::SyncDaemon::start(
+        app.clone(),
+        storage.clone(),
+        Arc::clone(db),
+        config.clone(),
+        last_stamp,
+    ));
+}
+
 #[tauri::command]
 async fn rename_vault(
     state: State<'_, AppState>,
@@ -268,11 +373,55 @@ async fn rename_vault(
     Ok(())
 }
 
+/// How the current vault stores new originals
+#[tauri::command]
+async fn get_archival_policy(state: State<'_, AppState>) -> Result<vault::ArchivalPolicy, String> {
+    let config_guard = state.config.lock().await;
+    let config = config_guard.as_ref().ok_or("Vault not loaded")?;
+    Ok(config.archival)
+}
+
+/// Change how the current vault stores new originals. Existing photos keep theirs.
+#[tauri::command]
+async fn set_archival_policy(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    policy: vault::ArchivalPolicy,
+) -> Result<(), String> {
+    let mut config_guard = state.config.lock().await;
+    let config = config_guard.as_mut().ok_or("Vault not loaded")?;
+    config.archival = policy;
+    store::save_vault(&app, config)
+}
+
+/// Which checks the current vault runs on uploads before recording them
+#[tauri::command]
+async fn get_upload_verification(state: State<'_, AppState>) -> Result<vault::UploadVerification, String> {
+    let config_guard = state.config.lock().await;
+    let config = config_guard.as_ref().ok_or("Vault not loaded")?;
+    Ok(config.verification)
+}
+
+/// Change the upload checks of the current vault. Applies to uploads started afterwards.
+#[tauri::command]
+async fn set_upload_verification(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    verification: vault::UploadVerification,
+) -> Result<(), String> {
+    let mut config_guard = state.config.lock().await;
+    let config = config_guard.as_mut().ok_or("Vault not loaded")?;
+    config.verification = verification;
+    store::save_vault(&app, config)
+}
+
 #[tauri::command]
 async fn delete_vault(
     app: AppHandle,
     state: State<'_, AppState>,
     upload_state: State<'_, UploadManagerState>,
+    sync_state: State<'_, SyncDaemonState>,
+    watcher_state: State<'_, FolderWatcherState>,
     id: String,
     delete_cloud: bool,
 ) -> Result<(), String> {
@@ -311,18 +460,29 @@ async fn delete_vault(
 
     // 3. Unload if active
     {
+        // Same lock order as `start_sync_daemon`: daemon before config
+        let mut daemon_guard = sync_state.daemon.lock().await;
         let mut config_guard = state.config.lock().await;
         let is_active = config_guard.as_ref().map(|c| c.id == id).unwrap_or(false);
 
         if is_active {
             // Stop any uploads first
-            let manager_guard = upload_state.manager.lock().await;
+            let mut manager_guard = upload_state.manager.lock().await;
             if let Some(manager) = manager_guard.as_ref() {
                 // We should ideally cancel all, but clearing is enough as we are deleting storage
                 manager.clear_finished().await;
             }
+            // Dropping the manager also stops its queue persistence
+            *manager_guard = None;
 
             // Reset State
+            if let Some(daemon) = daemon_guard.take() {
+                daemon.stop().await;
+            }
+            let old_watcher = watcher_state.watcher.lock().await.take();
+            if let Some(watcher) = old_watcher {
+                watcher.stop().await;
+            }
             *config_guard = None;
             *state.db.lock().await = None;
             *state.storage.lock().await = None;
@@ -331,6 +491,9 @@ async fn delete_vault(
 
     // 4. Delete Local Files
     let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    if let Err(e) = upload_queue::forget_vault(&app_dir.join(upload_queue::QUEUE_DB_FILE), &id) {
+        log::warn!("Failed to clear saved upload queue for vault {}: {}", id, e);
+    }
     let vault_dir = app_dir.join("vaults").join(&id);
     if vault_dir.exists() {
         std::fs::remove_dir_all(&vault_dir).map_err(|e| e.to_string())?;
@@ -459,7 +622,7 @@ async fn sync_manifest_download_internal(
     storage: &Storage,
     db: &Arc<Mutex<Option<Connection>>>,
     config: &VaultConfig,
-) -> Result<(), String> {
+) -> Result<manifest::MergeStats, String> {
     use crate::manifest;
 
     // Try to download manifest.enc from S3
@@ -468,7 +631,7 @@ async fn sync_manifest_download_internal(
         Err(e) => {
             // No manifest exists yet - this is fine for new vaults
             log::info!("[Manifest Sync] No manifest found on S3 ({})", e);
-            return Ok(());
+            return Ok(manifest::MergeStats::default());
         }
     };
 
@@ -486,6 +649,9 @@ async fn sync_manifest_download_internal(
     // Merge into local DB
     let db_guard = db.lock().await;
     if let Some(conn) = db_guard.as_ref() {
+        if let Err(e) = manifest::snapshot_local_db(conn) {
+            log::warn!("[Manifest Sync] Failed to snapshot local DB: {}", e);
+        }
         let stats = manifest::import_manifest(conn, remote_data)
             .map_err(|e| format!("Failed to merge manifest: {}", e))?;
         log::info!(
@@ -495,9 +661,10 @@ async fn sync_manifest_download_internal(
             stats.memories_added,
             stats.memories_updated
         );
+        return Ok(stats);
     }
 
-    Ok(())
+    Ok(manifest::MergeStats::default())
 }
 
 /// STRICT manifest sync for imported vaults - FAILS if manifest doesn't exist
@@ -551,6 +718,9 @@ async fn sync_manifest_download_strict(state: State<'_, AppState>) -> Result<(),
     // Merge into local DB
     let db_guard = state.db.lock().await;
     if let Some(conn) = db_guard.as_ref() {
+        if let Err(e) = manifest::snapshot_local_db(conn) {
+            log::warn!("[Strict Sync] Failed to snapshot local DB: {}", e);
+        }
         let stats = manifest::import_manifest(conn, remote_data)
             .map_err(|e| format!("Failed to merge manifest: {}", e))?;
         log::info!(
@@ -567,12 +737,13 @@ async fn sync_manifest_download_strict(state: State<'_, AppState>) -> Result<(),
     Ok(())
 }
 
-/// Internal function to export, encrypt, and upload manifest to S3
+/// Internal function to export, encrypt, and upload manifest to S3.
+/// Returns the ETag of the uploaded manifest.
 async fn sync_manifest_upload_internal(
     storage: &Storage,
     db: &Arc<Mutex<Option<Connection>>>,
     config: &VaultConfig,
-) -> Result<(), String> {
+) -> Result<Option<String>, String> {
     use crate::manifest;
 
     let vault_key = BASE64
@@ -585,6 +756,9 @@ async fn sync_manifest_upload_internal(
     // Export from DB
     let db_guard = db.lock().await;
     let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    if let Err(e) = devices::touch_current_device(conn) {
+        log::warn!("[Devices] Failed to update last sync time: {}", e);
+    }
     let data = manifest::export_manifest(conn).map_err(|e| format!("Export failed: {}", e))?;
     drop(db_guard);
 
@@ -593,18 +767,39 @@ async fn sync_manifest_upload_internal(
         .map_err(|e| format!("Encrypt failed: {}", e))?;
 
     // Upload
-    storage
-        .upload_file(manifest::MANIFEST_S3_KEY, enc_bytes)
+    let etag = storage
+        .upload_file_with_etag(manifest::MANIFEST_S3_KEY, enc_bytes)
         .await
         .map_err(|e| format!("Upload failed: {}", e))?;
 
     log::info!("[Manifest Sync] Uploaded manifest to S3");
-    Ok(())
+    Ok(etag)
 }
 
-/// Upload current manifest to S3
+/// Upload current manifest to S3.
+/// While the sync daemon is running it performs the upload, so it doesn't race its own.
 #[tauri::command]
-async fn sync_manifest_upload(state: State<'_, AppState>) -> Result<(), String> {
+async fn sync_manifest_upload(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncDaemonState>,
+) -> Result<(), String> {
+    push_manifest(&state, &sync_state).await
+}
+
+/// Upload the manifest through the running sync daemon (so it learns the new ETag),
+/// or directly if no daemon runs for the loaded vault
+async fn push_manifest(state: &AppState, sync_state: &SyncDaemonState) -> Result<(), String> {
+    let vault_id = {
+        let config_guard = state.config.lock().await;
+        config_guard.as_ref().ok_or("Vault not loaded")?.id.clone()
+    };
+
+    if let Some(daemon) = sync_state.daemon.lock().await.as_ref() {
+        if daemon.vault_id() == vault_id {
+            return daemon.push_now().await;
+        }
+    }
+
     let config_guard = state.config.lock().await;
     let config = config_guard.as_ref().ok_or("Vault not loaded")?;
 
@@ -612,7 +807,9 @@ async fn sync_manifest_upload(state: State<'_, AppState>) -> Result<(), String>
     let storage = storage_guard.as_ref().ok_or("Storage not initialized")?;
 
     // Use cloned references to pass to internal function
-    sync_manifest_upload_internal(storage, &state.db, config).await
+    sync_manifest_upload_internal(storage, &state.db, config)
+        .await
+        .map(|_| ())
 }
 
 /// Download and merge manifest from S3
@@ -629,7 +826,184 @@ async fn sync_manifest_download(state: State<'_, AppState>) -> Result<(), String
     let config = config_guard.as_ref().ok_or("Vault not loaded")?.clone();
     drop(config_guard);
 
-    sync_manifest_download_internal(&storage, &state.db, &config).await
+    sync_manifest_download_internal(&storage, &state.db, &config)
+        .await
+        .map(|_| ())
+}
+
+/// List previous versions of manifest.enc kept by bucket versioning, newest first
+#[tauri::command]
+async fn list_manifest_versions(
+    state: State<'_, AppState>,
+) -> Result<Vec<storage::ObjectVersion>, String> {
+    let storage_guard = state.storage.lock().await;
+    let storage = storage_guard
+        .as_ref()
+        .ok_or("Storage not initialized")?
+        .clone();
+    drop(storage_guard);
+
+    storage
+        .list_object_versions(manifest::MANIFEST_S3_KEY)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Download and decrypt a specific manifest.enc version
+async fn download_manifest_version(
+    state: &State<'_, AppState>,
+    version_id: &str,
+) -> Result<manifest::ManifestData, String> {
+    let storage_guard = state.storage.lock().await;
+    let storage = storage_guard
+        .as_ref()
+        .ok_or("Storage not initialized")?
+        .clone();
+    drop(storage_guard);
+
+    let config_guard = state.config.lock().await;
+    let config = config_guard.as_ref().ok_or("Vault not loaded")?.clone();
+    drop(config_guard);
+
+    let enc_bytes = storage
+        .download_file_version(manifest::MANIFEST_S3_KEY, version_id)
+        .await
+        .map_err(|e| format!("Failed to download manifest version: {}", e))?;
+
+    let vault_key = BASE64
+        .decode(&config.vault_key)
+        .map_err(|e| format!("Invalid vault key: {}", e))?;
+    let key_arr: [u8; 32] = vault_key
+        .try_into()
+        .map_err(|_| "Invalid key length".to_string())?;
+
+    manifest::decrypt_manifest(&enc_bytes, &key_arr)
+        .map_err(|e| format!("Failed to decrypt manifest: {}", e))
+}
+
+/// Preview photo and memory counts of a manifest version before restoring it
+#[tauri::command]
+async fn preview_manifest_version(
+    state: State<'_, AppState>,
+    version_id: String,
+) -> Result<manifest::ManifestPreview, String> {
+    let data = download_manifest_version(&state, &version_id).await?;
+    Ok(data.preview())
+}
+
+/// Merge a manifest version into the local DB (newest wins), then re-upload
+#[tauri::command]
+async fn merge_manifest_version(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncDaemonState>,
+    version_id: String,
+) -> Result<manifest::MergeStats, String> {
+    let data = download_manifest_version(&state, &version_id).await?;
+
+    let stats = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+        manifest::snapshot_local_db(conn)
+            .map_err(|e| format!("Failed to snapshot local DB: {}", e))?;
+        manifest::import_manifest(conn, data)
+            .map_err(|e| format!("Failed to merge manifest: {}", e))?
+    };
+
+    log::info!(
+        "[Manifest History] Merged version {}: {} photos added, {} updated; {} memories added, {} updated",
+        version_id,
+        stats.photos_added,
+        stats.photos_updated,
+        stats.memories_added,
+        stats.memories_updated
+    );
+
+    push_manifest(&state, &sync_state).await?;
+    Ok(stats)
+}
+
+/// Restore the local DB to exactly match a manifest version, then re-upload
+/// so it becomes the latest version for other devices
+#[tauri::command]
+async fn restore_manifest_version(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncDaemonState>,
+    version_id: String,
+) -> Result<manifest::RestoreStats, String> {
+    let data = download_manifest_version(&state, &version_id).await?;
+
+    let stats = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+        manifest::snapshot_local_db(conn)
+            .map_err(|e| format!("Failed to snapshot local DB: {}", e))?;
+        manifest::restore_manifest(conn, data)
+            .map_err(|e| format!("Failed to restore manifest: {}", e))?
+    };
+
+    log::info!(
+        "[Manifest History] Restored version {}: {} photos, {} removed; {} memories, {} removed",
+        version_id,
+        stats.photos_restored,
+        stats.photos_removed,
+        stats.memories_restored,
+        stats.memories_removed
+    );
+
+    push_manifest(&state, &sync_state).await?;
+    Ok(stats)
+}
+
+/// Disaster recovery: rebuild the photos table from the encrypted metadata sidecars
+/// in the bucket (e.g. after manifest.enc was lost), then upload a fresh manifest
+#[tauri::command]
+async fn rebuild_manifest_from_bucket(
+    state: State<'_, AppState>,
+    sync_state: State<'_, SyncDaemonState>,
+) -> Result<recovery::RebuildStats, String> {
+    let storage_guard = state.storage.lock().await;
+    let storage = storage_guard
+        .as_ref()
+        .ok_or("Storage not initialized")?
+        .clone();
+    drop(storage_guard);
+
+    let config_guard = state.config.lock().await;
+    let config = config_guard.as_ref().ok_or("Vault not loaded")?.clone();
+    drop(config_guard);
+
+    let vault_key = BASE64
+        .decode(&config.vault_key)
+        .map_err(|e| format!("Invalid vault key: {}", e))?;
+    let key_arr: [u8; 32] = vault_key
+        .try_into()
+        .map_err(|_| "Invalid key length".to_string())?;
+
+    let mut stats = recovery::RebuildStats::default();
+    let records = recovery::collect_photo_records(&storage, &key_arr, &mut stats)
+        .await
+        .map_err(|e| format!("Failed to scan bucket: {}", e))?;
+
+    {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+        manifest::snapshot_local_db(conn)
+            .map_err(|e| format!("Failed to snapshot local DB: {}", e))?;
+        recovery::import_records(conn, &records, &mut stats)
+            .map_err(|e| format!("Failed to import recovered photos: {}", e))?;
+    }
+
+    log::info!(
+        "[Recovery] Rebuilt {} photos from sidecars, {} from listing ({} failed); {} added, {} updated",
+        stats.from_sidecars,
+        stats.from_listing,
+        stats.failed,
+        stats.photos_added,
+        stats.photos_updated
+    );
+
+    push_manifest(&state, &sync_state).await?;
+    Ok(stats)
 }
 
 #[tauri::command]
@@ -693,7 +1067,7 @@ async fn upload_photo(app: tauri::AppHandle, state: State<'_, AppState>, path: S
         app: app.clone(),
     };
     
-    let processed = crate::media_processor::process_image(&transcoder, path_obj)
+    let processed = crate::media_processor::process_image(&transcoder, path_obj, Default::default())
         .await
         .map_err(|e| format!("Failed to process image: {}", e))?;
 
@@ -793,6 +1167,11 @@ struct Photo {
     iso: Option<i32>,
     f_number: Option<f64>,
     exposure_time: Option<String>,
+    favorite: bool,
+    rating: u8,
+    color_label: Option<String>,
+    /// Live Photo / Motion Photo with a video (see `get_motion_video`)
+    has_motion: bool,
 }
 
 #[tauri::command]
@@ -801,7 +1180,7 @@ async fn get_photos(state: State<'_, AppState>) -> Result<Vec<Photo>, String> {
     let conn = db_guard.as_ref().ok_or("DB not initialized")?;
 
     let mut stmt = conn
-        .prepare("SELECT id, filename, created_at, captured_at, tier, media_type, width, height, latitude, longitude, make, model, lens_model, iso, f_number, exposure_time FROM photos ORDER BY COALESCE(captured_at, created_at) DESC")
+        .prepare("SELECT id, filename, created_at, captured_at, tier, media_type, width, height, latitude, longitude, make, model, lens_model, iso, f_number, exposure_time, favorite, rating, color_label, motion_key IS NOT NULL FROM photos WHERE deleted_at IS NULL ORDER BY COALESCE(captured_at, created_at) DESC")
         .map_err(|e| e.to_string())?;
 
     let photos = stmt
@@ -838,6 +1217,10 @@ async fn get_photos(state: State<'_, AppState>) -> Result<Vec<Photo>, String> {
                 iso: row.get(13)?,
                 f_number: row.get(14)?,
                 exposure_time: row.get(15)?,
+                favorite: row.get(16)?,
+                rating: row.get(17)?,
+                color_label: row.get(18)?,
+                has_motion: row.get(19)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -872,6 +1255,10 @@ struct PhotoWithVault {
     iso: Option<i32>,
     f_number: Option<f64>,
     exposure_time: Option<String>,
+    favorite: bool,
+    rating: u8,
+    color_label: Option<String>,
+    has_motion: bool,
 }
 
 /// Geolocated photo for map display
@@ -910,10 +1297,10 @@ async fn get_all_photos(app: AppHandle) -> Result<Vec<PhotoWithVault>, String> {
             continue;
         }
 
-        let conn = db::init_db(&db_path).map_err(|e| e.to_string())?;
+        let conn = open_vault_db(&app, &vault_id, &db_path)?;
 
         let mut stmt = conn
-            .prepare("SELECT id, filename, created_at, captured_at, tier, media_type, width, height, latitude, longitude, make, model, lens_model, iso, f_number, exposure_time FROM photos ORDER BY COALESCE(captured_at, created_at) DESC")
+            .prepare("SELECT id, filename, created_at, captured_at, tier, media_type, width, height, latitude, longitude, make, model, lens_model, iso, f_number, exposure_time, favorite, rating, color_label, motion_key IS NOT NULL FROM photos WHERE deleted_at IS NULL ORDER BY COALESCE(captured_at, created_at) DESC")
             .map_err(|e| e.to_string())?;
 
         let photos = stmt
@@ -938,6 +1325,10 @@ async fn get_all_photos(app: AppHandle) -> Result<Vec<PhotoWithVault>, String> {
                     iso: row.get(13)?,
                     f_number: row.get(14)?,
                     exposure_time: row.get(15)?,
+                    favorite: row.get(16)?,
+                    rating: row.get(17)?,
+                    color_label: row.get(18)?,
+                    has_motion: row.get(19)?,
                 })
             })
             .map_err(|e| e.to_string())?;
@@ -974,10 +1365,10 @@ async fn get_all_photos_with_geolocation(app: AppHandle) -> Result<Vec<GeoPhoto>
             continue;
         }
 
-        let conn = db::init_db(&db_path).map_err(|e| e.to_string())?;
+        let conn = open_vault_db(&app, &vault_id, &db_path)?;
 
         let mut stmt = conn
-            .prepare("SELECT id, latitude, longitude, captured_at, filename, created_at, width, height, make, model, lens_model, iso, f_number, exposure_time FROM photos WHERE latitude IS NOT NULL AND longitude IS NOT NULL")
+            .prepare("SELECT id, latitude, longitude, captured_at, filename, created_at, width, height, make, model, lens_model, iso, f_number, exposure_time FROM photos WHERE latitude IS NOT NULL AND longitude IS NOT NULL AND deleted_at IS NULL")
             .map_err(|e| e.to_string())?;
 
         let photos = stmt
@@ -1081,7 +1472,7 @@ async fn update_photo_metadata(
         return Err(format!("Vault DB not found at {:?}", db_path));
     }
 
-    let conn = db::init_db(&db_path).map_err(|e| e.to_string())?;
+    let conn = open_vault_db(&app, &vault_id, &db_path)?;
 
     // Build dynamic UPDATE query based on provided fields
     let mut updates = Vec::new();
@@ -1426,6 +1817,74 @@ async fn get_original(
     Ok(BASE64.encode(&dec_bytes))
 }
 
+/// Get the display copy of a lossless original (instant, unlike an archived original)
+/// Returns base64 encoded decrypted bytes
+#[tauri::command]
+async fn get_display_copy(state: State<'_, AppState>, id: String) -> Result<String, String> {
+    let (storage, vault_key) = {
+        let storage_guard = state.storage.lock().await;
+        let config_guard = state.config.lock().await;
+        let storage = storage_guard.as_ref().ok_or("Storage not initialized")?.clone();
+        let config = config_guard.as_ref().ok_or("Vault not loaded")?;
+        let vault_key = BASE64.decode(&config.vault_key)
+            .map_err(|e| format!("Invalid vault key: {}", e))?;
+        (storage, vault_key)
+    };
+
+    let key_arr: [u8; 32] = vault_key.try_into().map_err(|_| "Invalid key length")?;
+
+    let display_key = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        conn.query_row("SELECT display_key FROM photos WHERE id = ?1", [&id], |row| {
+            row.get::<_, Option<String>>(0)
+        })
+        .map_err(|e| format!("Photo not found: {}", e))?
+        .ok_or("Photo has no display copy")?
+    };
+
+    let enc_bytes = storage.download_file(&display_key).await
+        .map_err(|e| format!("Failed to download: {}", e))?;
+    let dec_bytes = crypto::decrypt(&enc_bytes, &key_arr)
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+
+    Ok(BASE64.encode(&dec_bytes))
+}
+
+/// Get the video of a Live Photo / Motion Photo
+/// Returns base64 encoded decrypted bytes
+#[tauri::command]
+async fn get_motion_video(state: State<'_, AppState>, id: String) -> Result<String, String> {
+    let (storage, vault_key) = {
+        let storage_guard = state.storage.lock().await;
+        let config_guard = state.config.lock().await;
+        let storage = storage_guard.as_ref().ok_or("Storage not initialized")?.clone();
+        let config = config_guard.as_ref().ok_or("Vault not loaded")?;
+        let vault_key = BASE64.decode(&config.vault_key)
+            .map_err(|e| format!("Invalid vault key: {}", e))?;
+        (storage, vault_key)
+    };
+
+    let key_arr: [u8; 32] = vault_key.try_into().map_err(|_| "Invalid key length")?;
+
+    let motion_key = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        conn.query_row("SELECT motion_key FROM photos WHERE id = ?1", [&id], |row| {
+            row.get::<_, Option<String>>(0)
+        })
+        .map_err(|e| format!("Photo not found: {}", e))?
+        .ok_or("Photo has no motion video")?
+    };
+
+    let enc_bytes = storage.download_file(&motion_key).await
+        .map_err(|e| format!("Failed to download: {}", e))?;
+    let dec_bytes = crypto::decrypt(&enc_bytes, &key_arr)
+        .map_err(|e| format!("Decryption failed: {}", e))?;
+
+    Ok(BASE64.encode(&dec_bytes))
+}
+
 /// Response for get_pending_restores_for_vault
 #[derive(serde::Serialize)]
 struct PendingRestoreInfo {
@@ -1451,7 +1910,7 @@ async fn get_pending_restores_for_vault(
         return Ok(Vec::new());
     }
 
-    let conn = db::init_db(&db_path).map_err(|e| e.to_string())?;
+    let conn = open_vault_db(&app, &vault_id, &db_path)?;
     
     // Get pending restores with photo filename
     let mut stmt = conn.prepare(
@@ -1643,7 +2102,7 @@ async fn export_vault(app: AppHandle, id: String) -> Result<String, String> {
     let db_path = vault_dir.join("manifest.db");
 
     if db_path.exists() {
-        if let Ok(conn) = db::init_db(&db_path) {
+        if let Ok(conn) = db::init_db(&db_path, &config.db_key()?) {
             if let Ok(Some(name)) = db::get_metadata(&conn, "name") {
                 config.name = Some(name);
             }
@@ -1765,12 +2224,19 @@ struct AddFilesPayload {
     /// Optional map of "Path -> List of Base64 encoded Frames"
     /// Used for frontend-generated video thumbnails (especially on Mobile)
     thumbnails: Option<std::collections::HashMap<String, Vec<String>>>,
+    /// Drop files already in the vault or queue instead of listing them as duplicates
+    #[serde(default)]
+    skip_duplicates: bool,
+    /// Name of the upload batch (defaults to the import time)
+    #[serde(default)]
+    batch_name: Option<String>,
 }
 
 #[derive(serde::Serialize)]
 struct AddFilesResult {
     items: Vec<UploadItem>,
     fresh_upload_auto_disabled: bool,
+    skipped_duplicates: usize,
 }
 
 #[tauri::command]
@@ -1831,7 +2297,7 @@ async fn add_files_to_queue(
                 None
             };
 
-            UploadItem::new(p, payload.fresh_upload, thumb).ok()
+            UploadItem::new(p, payload.fresh_upload, thumb, None).ok()
         })
         .collect();
 
@@ -1844,14 +2310,21 @@ async fn add_files_to_queue(
     };
 
     // Now add with the correct fresh_upload flag
-    let items = manager
-        .add_files(valid_paths, actual_fresh_upload, payload.thumbnails)
+    let (items, skipped_duplicates) = manager
+        .add_files(
+            valid_paths,
+            actual_fresh_upload,
+            payload.thumbnails,
+            payload.skip_duplicates,
+            payload.batch_name,
+        )
         .await
         .map_err(|e| e.to_string())?;
 
     Ok(AddFilesResult {
         items,
         fresh_upload_auto_disabled,
+        skipped_duplicates,
     })
 }
 
@@ -1888,6 +2361,21 @@ async fn start_upload(
     manager.start_processing().await.map_err(|e| e.to_string())
 }
 
+/// Change how many items are prepared and uploaded at once
+#[tauri::command]
+async fn set_upload_concurrency(
+    upload_state: State<'_, UploadManagerState>,
+    concurrency: UploadConcurrency,
+) -> Result<(), String> {
+    let manager_guard = upload_state.manager.lock().await;
+    let manager = manager_guard
+        .as_ref()
+        .ok_or("Upload manager not initialized")?;
+
+    manager.set_concurrency(concurrency).await;
+    Ok(())
+}
+
 #[tauri::command]
 async fn cancel_upload(
     upload_state: State<'_, UploadManagerState>,
@@ -1955,6 +2443,77 @@ async fn retry_upload(
     Ok(())
 }
 
+/// Upload an item before everything else that is pending
+#[tauri::command]
+async fn move_upload_to_top(
+    upload_state: State<'_, UploadManagerState>,
+    id: String,
+) -> Result<(), String> {
+    let manager_guard = upload_state.manager.lock().await;
+    let manager = manager_guard
+        .as_ref()
+        .ok_or("Upload manager not initialized")?;
+
+    manager.move_to_top(&id).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_upload_queue_order(
+    upload_state: State<'_, UploadManagerState>,
+    order: QueueOrder,
+) -> Result<(), String> {
+    let manager_guard = upload_state.manager.lock().await;
+    let manager = manager_guard
+        .as_ref()
+        .ok_or("Upload manager not initialized")?;
+
+    manager.set_order(order).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn pause_upload_batch(
+    upload_state: State<'_, UploadManagerState>,
+    batch_id: String,
+) -> Result<(), String> {
+    let manager_guard = upload_state.manager.lock().await;
+    let manager = manager_guard
+        .as_ref()
+        .ok_or("Upload manager not initialized")?;
+
+    manager.pause_batch(&batch_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn resume_upload_batch(
+    upload_state: State<'_, UploadManagerState>,
+    batch_id: String,
+) -> Result<(), String> {
+    let manager_guard = upload_state.manager.lock().await;
+    let manager = manager_guard
+        .as_ref()
+        .ok_or("Upload manager not initialized")?;
+
+    manager.resume_batch(&batch_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+async fn cancel_upload_batch(
+    upload_state: State<'_, UploadManagerState>,
+    batch_id: String,
+) -> Result<(), String> {
+    let manager_guard = upload_state.manager.lock().await;
+    let manager = manager_guard
+        .as_ref()
+        .ok_or("Upload manager not initialized")?;
+
+    manager.cancel_batch(&batch_id).await;
+    Ok(())
+}
+
 #[tauri::command]
 async fn remove_upload_item(
     upload_state: State<'_, UploadManagerState>,
@@ -1982,14 +2541,36 @@ async fn initialize_upload_manager(
     upload_state: State<'_, UploadManagerState>,
     cache_state: State<'_, CacheState>,
 ) -> Result<(), String> {
-    let (manager, _cancel_rx) = UploadManager::new(
+    let (vault_id, queue_key) = {
+        let config_guard = state.config.lock().await;
+        let config = config_guard.as_ref().ok_or("Vault not loaded")?;
+        (config.id.clone(), config.upload_queue_key()?)
+    };
+
+    // Called again when the gallery mounts; keep the running manager for the same vault
+    let mut manager_guard = upload_state.manager.lock().await;
+    if manager_guard.as_ref().and_then(|m| m.vault_id()) == Some(vault_id.as_str()) {
+        return Ok(());
+    }
+
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let (mut manager, _cancel_rx) = UploadManager::new(
         app,
         state.storage.clone(),
         state.config.clone(),
         state.db.clone(),
         cache_state.thumbnail_cache.clone(), // Correctly accessing from CacheState
     );
-    *upload_state.manager.lock().await = Some(manager);
+
+    // Restore uploads left unfinished when the app was last closed
+    let store = upload_queue::UploadQueueStore::open(&app_dir.join(upload_queue::QUEUE_DB_FILE), &vault_id, queue_key)
+        .map_err(|e| e.to_string())?;
+    let pending = manager.restore_queue(store).await.map_err(|e| e.to_string())?;
+    if pending > 0 {
+        manager.start_processing().await.map_err(|e| e.to_string())?;
+    }
+
+    *manager_guard = Some(manager);
     Ok(())
 }
 
@@ -2355,9 +2936,12 @@ async fn init_embedding_models(
     for (vault_id, _) in vault_ids {
         let db_path = app_dir.join("vaults").join(&vault_id).join("manifest.db");
         if db_path.exists() {
-            if let Ok(conn) = rusqlite::Connection::open(&db_path) {
+            if let Ok(conn) = open_vault_db(&app, &vault_id, &db_path) {
                 let mut stmt = conn
-                    .prepare("SELECT photo_id, embedding FROM embeddings")
+                    .prepare(
+                        "SELECT photo_id, embedding FROM embeddings
+                         WHERE photo_id NOT IN (SELECT id FROM photos WHERE deleted_at IS NOT NULL)",
+                    )
                     .map_err(|e| e.to_string())?;
                 let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
                 while let Some(row) = rows.next().map_err(|e| e.to_string())? {
@@ -2422,14 +3006,13 @@ async fn search_photos_semantic(
     }
 
     // Filter out negative scores (completely irrelevant results)
-    const MIN_SCORE: f32 = 0.0525;
     let filtered: Vec<_> = results
         .into_iter()
-        .filter(|(_, score)| *score >= MIN_SCORE)
+        .filter(|(_, score)| *score >= embedding::MIN_SEMANTIC_SCORE)
         .map(|(id, score)| SemanticSearchResult { id, score })
         .collect();
     
-    log::info!("Returning {} results after filtering (threshold: {})", filtered.len(), MIN_SCORE);
+    log::info!("Returning {} results after filtering (threshold: {})", filtered.len(), embedding::MIN_SEMANTIC_SCORE);
 
     Ok(filtered)
 }
@@ -2536,7 +3119,7 @@ async fn embed_photo_for_search(
             rusqlite::params![
                 photo_id,
                 embedding_bytes,
-                "nomic-embed-vision-v1.5",
+                embedding::VISION_MODEL_VERSION,
                 chrono::Utc::now().to_rfc3339()
             ],
         )
@@ -2553,26 +3136,65 @@ async fn embed_photo_for_search(
 }
 
 /// Internal helper for embedding photos from a single vault
+/// Imports vectors synced by other devices first, then embeds what's still missing
+/// and uploads the new vectors.
 /// Returns (embedded_count, skipped_count, no_cache_count)
 async fn embed_all_photos_internal(
     app_dir: &std::path::Path,
-    vault_id: &str,
+    config: &VaultConfig,
+    storage: &Storage,
     embedding_state: &embedding::EmbeddingState,
 ) -> Result<(usize, usize, usize), String> {
-    let vault_dir = app_dir.join("vaults").join(vault_id);
+    let vault_dir = app_dir.join("vaults").join(&config.id);
     let db_path = vault_dir.join("manifest.db");
     let cache_dir = vault_dir.join("cache");
     
     if !db_path.exists() {
         return Ok((0, 0, 0));
     }
+
+    let vault_key = BASE64
+        .decode(&config.vault_key)
+        .map_err(|e| format!("Invalid vault key: {}", e))?;
+    let key_arr: [u8; 32] = vault_key
+        .try_into()
+        .map_err(|_| "Invalid key length".to_string())?;
+    let db_key = db::derive_db_key(&key_arr);
+
+    // Import embeddings computed on other devices before running the model.
+    // If the index can't be read, skip syncing entirely so we never overwrite it.
+    let mut sync_stats = embedding::sync::EmbeddingSyncStats::default();
+    let remote_index = match embedding::sync::download_index(storage, &key_arr, embedding::VISION_MODEL_VERSION).await {
+        Ok(index) => Some(index),
+        Err(e) => {
+            log::warn!("[Embedding Sync] Failed to read embedding index: {}", e);
+            None
+        }
+    };
+
+    if let Some(Some(index)) = remote_index.as_ref() {
+        match embedding::sync::pull_embeddings(storage, &db_path, &key_arr, index, &mut sync_stats).await {
+            Ok(imported) => {
+                let mut emb_index = embedding_state.index.lock().await;
+                for (photo_id, vector) in imported {
+                    emb_index.insert_vec(photo_id, vector);
+                }
+            }
+            Err(e) => log::warn!("[Embedding Sync] Import failed: {}", e),
+        }
+        log::info!(
+            "[Embedding Sync] Imported {} embeddings from {} chunks",
+            sync_stats.imported,
+            sync_stats.chunks_downloaded
+        );
+    }
     
     // Get all photo IDs and media types from this vault
     let photos: Vec<(String, Option<String>, String)> = {
-        let conn = rusqlite::Connection::open(&db_path)
+        let conn = db::init_db(&db_path, &db_key)
             .map_err(|e| e.to_string())?;
         let mut stmt = conn
-            .prepare("SELECT id, media_type, filename FROM photos")
+            .prepare("SELECT id, media_type, filename FROM photos WHERE deleted_at IS NULL")
             .map_err(|e| e.to_string())?;
         let rows = stmt
             .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
@@ -2627,7 +3249,7 @@ async fn embed_all_photos_internal(
                         match vision.embed(preprocessed) {
                             Ok(emb) => {
                                 // Store in DB
-                                if let Ok(conn) = rusqlite::Connection::open(&db_path) {
+                                if let Ok(conn) = db::init_db(&db_path, &db_key) {
                                     let embedding_bytes: Vec<u8> = emb
                                         .iter()
                                         .flat_map(|f| f.to_le_bytes())
@@ -2637,7 +3259,7 @@ async fn embed_all_photos_internal(
                                         rusqlite::params![
                                             photo_id,
                                             embedding_bytes,
-                                            "nomic-embed-vision-v1.5",
+                                            embedding::VISION_MODEL_VERSION,
                                             chrono::Utc::now().to_rfc3339()
                                         ],
                                     ).ok();
@@ -2663,143 +3285,94 @@ async fn embed_all_photos_internal(
             no_cache_count += 1;
         }
     }
+
+    // Share newly computed (or never uploaded) embeddings with other devices
+    if let Some(remote) = remote_index {
+        match embedding::sync::push_embeddings(
+            storage,
+            &db_path,
+            &key_arr,
+            embedding::VISION_MODEL_VERSION,
+            remote,
+            &mut sync_stats,
+        )
+        .await
+        {
+            Ok(()) => {
+                if sync_stats.uploaded > 0 {
+                    log::info!(
+                        "[Embedding Sync] Uploaded {} embeddings in {} chunks",
+                        sync_stats.uploaded,
+                        sync_stats.chunks_uploaded
+                    );
+                }
+            }
+            Err(e) => log::warn!("[Embedding Sync] Upload failed: {}", e),
+        }
+    }
     
     Ok((embedded_count, skipped_count, no_cache_count))
 }
 
+/// Result of embedding the photos of every vault
+#[derive(serde::Serialize)]
+struct EmbedAllSummary {
+    embedded: usize,
+    skipped: usize,
+    not_cached: usize,
+    /// Vaults that couldn't be embedded, with the reason
+    failed_vaults: Vec<(String, String)>,
+}
+
 /// Embed all photos in all vaults (background task)
-/// Scans the disk cache directory for cached thumbnails
+/// Syncs each vault's embedding index like the post-sync pass does. A vault that
+/// fails (bad key, storage offline) doesn't stop the others.
 #[tauri::command]
 async fn embed_all_photos(
     app: AppHandle,
     embedding_state: State<'_, embedding::EmbeddingState>,
-) -> Result<usize, String> {
+) -> Result<EmbedAllSummary, String> {
     log::info!("Starting batch photo embedding from disk cache...");
     
     let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
     let vault_ids = vault::store::get_vault_ids(&app)?;
     
-    let mut embedded_count = 0;
-    let mut skipped_count = 0;
-    let mut no_cache_count = 0;
+    let mut summary = EmbedAllSummary {
+        embedded: 0,
+        skipped: 0,
+        not_cached: 0,
+        failed_vaults: Vec::new(),
+    };
     
     for (vault_id, _) in vault_ids {
-        let vault_dir = app_dir.join("vaults").join(&vault_id);
-        let db_path = vault_dir.join("manifest.db");
-        let cache_dir = vault_dir.join("cache");
-        
-        if !db_path.exists() {
-            continue;
-        }
-        
-        // Get all photo IDs and media types from this vault
-        let photos: Vec<(String, Option<String>, String)> = {
-            let conn = rusqlite::Connection::open(&db_path)
-                .map_err(|e| e.to_string())?;
-            let mut stmt = conn
-                .prepare("SELECT id, media_type, filename FROM photos")
-                .map_err(|e| e.to_string())?;
-            let rows = stmt
-                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
-                .map_err(|e| e.to_string())?;
-            rows.filter_map(|r| r.ok()).collect()
-        };
-        
-        let images_videos = photos.iter().filter(|(_, t, f)| {
-            t.as_deref() != Some("audio") && 
-            !f.to_lowercase().ends_with(".mp3") &&
-            !f.to_lowercase().ends_with(".wav") &&
-            !f.to_lowercase().ends_with(".m4a")
-        }).count();
-        
-        log::info!("Vault {} has {} photos ({} images/videos, {} audio)", 
-                   vault_id, photos.len(), images_videos, photos.len() - images_videos);
-        
-        for (photo_id, media_type, filename) in photos {
-            // Skip audio files - they have no thumbnails to embed
-            let is_audio = media_type.as_deref() == Some("audio") || 
-                filename.to_lowercase().ends_with(".mp3") || 
-                filename.to_lowercase().ends_with(".wav") || 
-                filename.to_lowercase().ends_with(".m4a") || 
-                filename.to_lowercase().ends_with(".ogg") ||
-                filename.to_lowercase().ends_with(".flac");
-                
-            if is_audio {
+        let config = match store::load_vault(&app, &vault_id) {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("[AI] Skipping vault {}: {}", vault_id, e);
+                summary.failed_vaults.push((vault_id, e));
                 continue;
             }
-
-            let media_type_display = media_type.as_deref().unwrap_or("image");
-            
-            // Check if already embedded
-            {
-                let index = embedding_state.index.lock().await;
-                if index.contains(&photo_id) {
-                    skipped_count += 1;
-                    continue;
-                }
+        };
+        let storage = Storage::new(&config).await;
+        match embed_all_photos_internal(&app_dir, &config, &storage, embedding_state.inner()).await {
+            Ok((embedded, skipped, no_cache)) => {
+                log::info!(
+                    "Vault {}: {} embedded, {} skipped, {} not cached",
+                    vault_id, embedded, skipped, no_cache
+                );
+                summary.embedded += embedded;
+                summary.skipped += skipped;
+                summary.not_cached += no_cache;
             }
-            
-            // Try to read thumbnail from disk cache
-            let cache_path = cache_dir.join(format!("{}.webp", &photo_id));
-            let thumbnail_bytes = if cache_path.exists() {
-                std::fs::read(&cache_path).ok()
-            } else {
-                None
-            };
-            
-            if let Some(bytes) = thumbnail_bytes {
-                // Preprocess and embed
-                match embedding::preprocess::preprocess_image_bytes(&bytes) {
-                    Ok(preprocessed) => {
-                        let mut vision_guard = embedding_state.vision.lock().await;
-                        if let Some(vision) = vision_guard.as_mut() {
-                            match vision.embed(preprocessed) {
-                                Ok(emb) => {
-                                    // Store in DB
-                                    if let Ok(conn) = rusqlite::Connection::open(&db_path) {
-                                        let embedding_bytes: Vec<u8> = emb
-                                            .iter()
-                                            .flat_map(|f| f.to_le_bytes())
-                                            .collect();
-                                        conn.execute(
-                                            "INSERT OR REPLACE INTO embeddings (photo_id, embedding, model_version, created_at) VALUES (?1, ?2, ?3, ?4)",
-                                            rusqlite::params![
-                                                photo_id,
-                                                embedding_bytes,
-                                                "nomic-embed-vision-v1.5",
-                                                chrono::Utc::now().to_rfc3339()
-                                            ],
-                                        ).ok();
-                                    }
-                                    
-                                    // Add to index
-                                    let mut index = embedding_state.index.lock().await;
-                                    index.insert(photo_id.clone(), emb);
-                                    embedded_count += 1;
-                                    
-                                    if embedded_count % 10 == 0 {
-                                        log::info!("Embedded {} photos so far...", embedded_count);
-                                    }
-                                }
-                                Err(e) => {
-                                    log::warn!("Failed to embed photo {}: {}", photo_id, e);
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to preprocess photo {}: {}", photo_id, e);
-                    }
-                }
-            } else {
-                log::warn!("Missing cache for {} ({})", photo_id, media_type_display);
-                no_cache_count += 1;
+            Err(e) => {
+                log::warn!("[AI] Embedding failed for vault {}: {}", vault_id, e);
+                summary.failed_vaults.push((vault_id, e));
             }
         }
     }
-    log::info!("Batch embedding complete: {} embedded, {} skipped (already done), {} not in cache",
-               embedded_count, skipped_count, no_cache_count);
-    Ok(embedded_count)
+    log::info!("Batch embedding complete: {} embedded, {} skipped (already done), {} not in cache, {} vaults failed",
+               summary.embedded, summary.skipped, summary.not_cached, summary.failed_vaults.len());
+    Ok(summary)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -2935,6 +3508,14 @@ pub fn run() {
         .manage(TrayManagerState {
             manager: Arc::new(tokio::sync::RwLock::new(tray_manager::TrayManager::new())),
         })
+        // Background manifest sync for the loaded vault
+        .manage(SyncDaemonState {
+            daemon: Mutex::new(None),
+        })
+        // Watch folder scanner for the loaded vault
+        .manage(FolderWatcherState {
+            watcher: Mutex::new(None),
+        })
         // Originals cache state for Deep Glacier restore flow
         .manage(OriginalsCacheState {
             cache: Arc::new(Mutex::new(None)),
@@ -2961,6 +3542,14 @@ pub fn run() {
             add_files_to_queue,
             get_upload_queue_status,
             start_upload,
+            set_upload_concurrency,
+            watch_folders::get_watch_folders,
+            watch_folders::add_watch_folder,
+            watch_folders::update_watch_folder,
+            watch_folders::remove_watch_folder,
+            folder_import::preview_folder_import,
+            folder_import::import_folder,
+            library_import::import_library,
             cancel_upload,
             clear_finished_uploads,
             pause_upload,
@@ -2968,8 +3557,34 @@ pub fn run() {
             memories::get_memories,
             memories::update_memory,
             memories::delete_memory,
+            albums::create_album,
+            albums::get_albums,
+            albums::get_album_items,
+            albums::update_album,
+            albums::delete_album,
+            albums::add_to_album,
+            albums::remove_from_album,
+            albums::reorder_album,
+            curation::edit_photos,
+            curation::list_tags,
+            curation::get_photo_tags,
+            smart_albums::create_smart_album,
+            smart_albums::get_smart_albums,
+            smart_albums::update_smart_album,
+            smart_albums::delete_smart_album,
+            smart_albums::get_smart_album_items,
+            smart_albums::preview_smart_album,
+            stats::get_vault_stats,
+            timeline::get_timeline,
+            devices::list_devices,
+            devices::revoke_device,
             resume_upload,
             retry_upload,
+            move_upload_to_top,
+            set_upload_queue_order,
+            pause_upload_batch,
+            resume_upload_batch,
+            cancel_upload_batch,
             remove_upload_item,
             initialize_upload_manager,
             open_cache_folder,
@@ -2977,10 +3592,20 @@ pub fn run() {
             get_supported_extensions,
             get_supported_extensions,
             rename_vault,
+            get_archival_policy,
+            set_archival_policy,
+            get_upload_verification,
+            set_upload_verification,
+            verification::verify_photos,
             delete_vault,
             // Manifest sync commands
             sync_manifest_upload,
             sync_manifest_download,
+            list_manifest_versions,
+            preview_manifest_version,
+            merge_manifest_version,
+            restore_manifest_version,
+            rebuild_manifest_from_bucket,
             // Pairing commands
             start_pairing_mode,
             stop_pairing_mode,
@@ -3005,6 +3630,7 @@ pub fn run() {
             // Cross-vault commands (for Search/Map)
             get_all_photos,
             get_all_photos_with_geolocation,
+            photo_query::query_photos,
             get_thumbnail_for_vault,
             update_photo_metadata,
             // Embedding / Semantic Search commands
@@ -3012,6 +3638,15 @@ pub fn run() {
             get_embedding_count,
             init_embedding_models,
             search_photos_semantic,
+            text_search::search_text,
+            // Trash commands
+            trash::trash_photos,
+            trash::restore_photos,
+            trash::list_trash,
+            trash::get_trash_retention,
+            trash::set_trash_retention,
+            trash::preview_purge,
+            trash::purge_photos,
             embed_photo_for_search,
             embed_all_photos,
             // Cross-platform embedding persistence
@@ -3021,6 +3656,8 @@ pub fn run() {
             check_original_status,
             request_original_restore,
             get_original,
+            get_display_copy,
+            get_motion_video,
             get_pending_restores_for_vault,
             // Debugging
             debug_log,
@@ -3135,7 +3772,7 @@ async fn save_embedding(
         return Err(format!("Vault DB not found for {}", vault_id));
     }
 
-    let conn = db::init_db(&db_path).map_err(|e| e.to_string())?;
+    let conn = open_vault_db(&app, &vault_id, &db_path)?;
 
     db::save_embedding(&conn, &photo_id, &embedding).map_err(|e| e.to_string())
 }
@@ -3156,7 +3793,7 @@ async fn load_embeddings(
             continue;
         }
 
-        if let Ok(conn) = db::init_db(&db_path) {
+        if let Ok(conn) = open_vault_db(&app, &vault_id, &db_path) {
             // Load embeddings involves reading blobs, so we use the helper
             // Note: Since photo_id is unique across vaults (UUID), we can flatten the list
             if let Ok(vault_embeddings) = db::load_embeddings(&conn) {
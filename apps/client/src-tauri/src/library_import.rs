@@ -0,0 +1,823 @@
+//! Google Takeout and Apple Photos export import
+//!
+//! Google Takeout puts a JSON sidecar next to every photo (`IMG_1234.jpg.json`, or
+//! `IMG_1234.jpg.supplemental-metadata.json` in newer exports) with the capture
+//! time, location, description and favorite flag, and a `metadata.json` in every
+//! album folder. Apple Photos exports carry an XMP sidecar per photo ("Export IPTC
+//! as XMP"); their AAE files only hold edit instructions, so the unmodified
+//! original is imported as is.
+//!
+//! - Zip exports (every part of a split Takeout) are extracted into one staging folder,
+//!   removed once no unfinished upload reads from it
+//! - Sidecar values only fill what the file's own metadata lacks (see `ImportedMetadata::fill`)
+//! - Albums become Boreal albums or memories, filled in as their uploads complete
+//!   (see `attach_pending`); cancelled or removed uploads never join
+
+use crate::albums;
+use crate::exif_extractor::{self, ExifMetadata};
+use crate::file_filter;
+use crate::memories::{self, CreateMemoryPayload};
+use crate::motion_photo::MotionSource;
+use crate::upload_manager::{self, UploadItem, UploadStatus};
+use crate::{AppState, UploadManagerState};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+/// Longest Takeout sidecar name without `.json`; longer names are cut to this
+const TAKEOUT_NAME_LIMIT: usize = 46;
+
+const SUPPLEMENTAL_SUFFIX: &str = ".supplemental-metadata";
+
+/// Album description file of a Takeout folder
+const TAKEOUT_ALBUM_FILE: &str = "metadata.json";
+
+/// Where zip exports are extracted, inside the vault's app data folder
+const STAGING_DIR: &str = "library-import";
+
+/// Most one import extracts from zip exports, so a malformed or malicious
+/// archive can't fill the disk (Takeout parts are at most 50 GB each)
+const MAX_EXTRACTED_BYTES: u64 = 500 * 1024 * 1024 * 1024;
+const MAX_EXTRACTED_ENTRIES: usize = 1_000_000;
+
+/// Metadata read from the sidecars of an exported photo
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportedMetadata {
+    pub captured_at: Option<DateTime<Utc>>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+impl ImportedMetadata {
+    /// Fill the fields missing from the file's own metadata
+    pub fn fill(&self, mut metadata: ExifMetadata) -> ExifMetadata {
+        metadata.captured_at = metadata.captured_at.or(self.captured_at);
+        if metadata.latitude.is_none() || metadata.longitude.is_none() {
+            if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude) {
+                metadata.latitude = Some(latitude);
+                metadata.longitude = Some(longitude);
+            }
+        }
+        if metadata.description.is_none() {
+            metadata.description = self.description.clone();
+        }
+        metadata
+    }
+}
+
+/// What exported albums become
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlbumTarget {
+    #[default]
+    Album,
+    Memory,
+    /// Only import the photos
+    Skip,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LibraryImportRequest {
+    /// Export folders and zip archives
+    pub paths: Vec<String>,
+    pub albums: AlbumTarget,
+    /// Treat subfolders without Takeout album metadata as albums (e.g. an Apple
+    /// Photos export with one folder per album)
+    pub folder_albums: bool,
+    pub fresh_upload: bool,
+    /// Name of the upload batch (defaults to the import time)
+    pub batch_name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LibraryImportResult {
+    pub items: Vec<UploadItem>,
+    /// Queued files whose metadata was found in a sidecar
+    pub with_sidecar_count: usize,
+    /// Files already in the vault or repeated within the export (e.g. a photo in
+    /// both an album and its year folder)
+    pub duplicate_count: usize,
+    /// Albums or memories created
+    pub album_count: usize,
+    pub fresh_upload_auto_disabled: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct TakeoutSidecar {
+    description: Option<String>,
+    photo_taken_time: Option<TakeoutTime>,
+    geo_data: Option<TakeoutGeo>,
+    geo_data_exif: Option<TakeoutGeo>,
+    favorited: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TakeoutTime {
+    /// Seconds since epoch, as a string
+    timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TakeoutGeo {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TakeoutAlbum {
+    title: String,
+    description: Option<String>,
+}
+
+impl TakeoutSidecar {
+    fn into_metadata(self) -> ImportedMetadata {
+        // 0,0 marks a photo without a location
+        let geo = [self.geo_data, self.geo_data_exif]
+            .into_iter()
+            .flatten()
+            .find(|g| g.latitude != 0.0 || g.longitude != 0.0);
+        ImportedMetadata {
+            captured_at: self
+                .photo_taken_time
+                .and_then(|t| t.timestamp.parse::<i64>().ok())
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+            latitude: geo.as_ref().map(|g| g.latitude),
+            longitude: geo.as_ref().map(|g| g.longitude),
+            description: self.description.filter(|d| !d.trim().is_empty()),
+            favorite: self.favorited,
+        }
+    }
+}
+
+/// A media file of an export
+#[derive(Debug)]
+struct LibraryFile {
+    path: PathBuf,
+    size: u64,
+    metadata: Option<ImportedMetadata>,
+}
+
+/// An album of an export
+#[derive(Debug, PartialEq)]
+struct LibraryAlbum {
+    title: String,
+    description: Option<String>,
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct LibraryScan {
+    files: Vec<LibraryFile>,
+    albums: Vec<LibraryAlbum>,
+}
+
+/// Split Takeout's duplicate counter off a file name: `IMG(1).jpg` -> (`IMG.jpg`, `(1)`)
+fn split_counter(name: &str) -> (String, &str) {
+    let (stem, ext) = name.rfind('.').map_or((name, ""), |dot| name.split_at(dot));
+    if let Some(open) = stem.rfind('(').filter(|_| stem.ends_with(')')) {
+        let counter = &stem[open..];
+        let digits = &counter[1..counter.len() - 1];
+        if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            return (format!("{}{}", &stem[..open], ext), counter);
+        }
+    }
+    (name.to_string(), "")
+}
+
+/// Takeout sidecar of the media file `name`, among the JSON files of its folder
+/// (names without `.json`). Handles `.supplemental-metadata`, names cut to 46
+/// characters, `(n)` duplicate counters and `-edited` copies.
+fn takeout_sidecar<'a>(name: &str, json_stems: &'a [String]) -> Option<&'a str> {
+    let (base, counter) = split_counter(name);
+    // Edited copies share the sidecar of the original
+    let unedited = base
+        .rsplit_once('.')
+        .and_then(|(stem, ext)| Some(format!("{}.{}", stem.strip_suffix("-edited")?, ext)));
+    let base = unedited.unwrap_or(base);
+    let full = format!("{}{}", base, SUPPLEMENTAL_SUFFIX);
+    let without_extension = base.rsplit_once('.').map(|(stem, _)| stem.to_string());
+
+    let exact = [Some(base.clone()), Some(full.clone()), without_extension]
+        .into_iter()
+        .flatten()
+        .map(|candidate| format!("{}{}", candidate, counter));
+    for candidate in exact {
+        if let Some(found) = json_stems.iter().find(|s| **s == candidate) {
+            return Some(found.as_str());
+        }
+    }
+
+    // Cut names: the longest prefix of `{base}.supplemental-metadata` that is
+    // at least as long as `base` or hit the length limit
+    json_stems
+        .iter()
+        .filter_map(|s| Some((s, s.strip_suffix(counter)?)))
+        .filter(|(_, cut)| {
+            full.starts_with(cut) && (cut.len() >= base.len() || cut.chars().count() >= TAKEOUT_NAME_LIMIT)
+        })
+        .max_by_key(|(_, cut)| cut.len())
+        .map(|(s, _)| s.as_str())
+}
+
+fn read_takeout_sidecar(path: &Path) -> Option<ImportedMetadata> {
+    let json = std::fs::read(path).ok()?;
+    match serde_json::from_slice::<TakeoutSidecar>(&json) {
+        Ok(sidecar) => Some(sidecar.into_metadata()),
+        Err(e) => {
+            log::warn!("[Library Import] Unreadable sidecar {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Metadata from the XMP sidecar of an Apple Photos export (`IMG_0001.xmp`)
+fn read_xmp_sidecar(path: &Path) -> Option<ImportedMetadata> {
+    let xmp = ["xmp", "XMP"]
+        .iter()
+        .find_map(|ext| std::fs::read_to_string(path.with_extension(ext)).ok())?;
+    let meta = exif_extractor::parse_xmp_sidecar(&xmp);
+    Some(ImportedMetadata {
+        captured_at: meta.captured_at,
+        latitude: meta.latitude,
+        longitude: meta.longitude,
+        description: meta.description,
+        favorite: false,
+    })
+}
+
+/// Takeout's per-year folders hold every photo; they aren't albums
+fn is_year_folder(name: &str) -> bool {
+    name.strip_prefix("Photos from ")
+        .is_some_and(|year| year.len() == 4 && year.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// The album a folder of an export stands for, if any
+fn folder_album(dir: &Path, root: &Path, folder_albums: bool) -> Option<(String, Option<String>)> {
+    let name = dir.file_name()?.to_string_lossy().to_string();
+    if is_year_folder(&name) {
+        return None;
+    }
+    if let Ok(json) = std::fs::read(dir.join(TAKEOUT_ALBUM_FILE)) {
+        let album: TakeoutAlbum = serde_json::from_slice(&json).unwrap_or_default();
+        let title = album.title.trim();
+        if !title.is_empty() && !is_year_folder(title) {
+            return Some((title.to_string(), album.description.filter(|d| !d.trim().is_empty())));
+        }
+    }
+    (folder_albums && dir != root).then_some((name, None))
+}
+
+/// Find the media of the export folders, their sidecar metadata and albums
+fn scan(roots: &[PathBuf], folder_albums: bool) -> LibraryScan {
+    let mut scan = LibraryScan::default();
+
+    for root in roots {
+        // Folder -> file names, so sidecars are matched within their folder
+        let mut folders: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+        let entries = walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !file_filter::is_hidden_name(e.file_name()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file());
+        for entry in entries {
+            if let Some(parent) = entry.path().parent() {
+                folders
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+
+        for (dir, mut names) in folders {
+            names.sort();
+            let json_stems: Vec<String> = names
+                .iter()
+                .filter(|n| n.as_str() != TAKEOUT_ALBUM_FILE)
+                .filter_map(|n| n.strip_suffix(".json").map(str::to_string))
+                .collect();
+            let mut album_files = Vec::new();
+
+            for name in &names {
+                let path = dir.join(name);
+                if file_filter::detect_media_type(&path).is_err() {
+                    continue;
+                }
+                let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                let metadata = takeout_sidecar(name, &json_stems)
+                    .and_then(|stem| read_takeout_sidecar(&dir.join(format!("{}.json", stem))))
+                    .or_else(|| read_xmp_sidecar(&path));
+                album_files.push(path.clone());
+                scan.files.push(LibraryFile { path, size, metadata });
+            }
+
+            if album_files.is_empty() {
+                continue;
+            }
+            if let Some((title, description)) = folder_album(&dir, root, folder_albums) {
+                scan.albums.push(LibraryAlbum {
+                    title,
+                    description,
+                    files: album_files,
+                });
+            }
+        }
+    }
+    scan
+}
+
+/// What is left of an import's extraction limits
+struct ExtractBudget {
+    bytes: u64,
+    entries: usize,
+}
+
+impl Default for ExtractBudget {
+    fn default() -> Self {
+        Self {
+            bytes: MAX_EXTRACTED_BYTES,
+            entries: MAX_EXTRACTED_ENTRIES,
+        }
+    }
+}
+
+/// Extract a zip export into `dest`. Parts of a split Takeout go into the same
+/// folder, so sidecars are found even when they landed in another part.
+fn extract_zip(path: &Path, dest: &Path, budget: &mut ExtractBudget) -> anyhow::Result<()> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    budget.entries = budget
+        .entries
+        .checked_sub(archive.len())
+        .ok_or_else(|| anyhow::anyhow!("Export has more than {} files", MAX_EXTRACTED_ENTRIES))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        // Entries that would land outside `dest` have no enclosed name
+        let Some(relative) = entry.enclosed_name() else {
+            continue;
+        };
+        let target = dest.join(relative);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Declared sizes can't be trusted, so stop reading past the limit
+        let mut limited = std::io::Read::take(&mut entry, budget.bytes + 1);
+        let written = std::io::copy(&mut limited, &mut std::fs::File::create(&target)?)?;
+        budget.bytes = budget
+            .bytes
+            .checked_sub(written)
+            .ok_or_else(|| anyhow::anyhow!("Export is larger than {} bytes", MAX_EXTRACTED_BYTES))?;
+    }
+    Ok(())
+}
+
+/// Folder a vault's zip exports are extracted into
+pub fn staging_dir(app: &AppHandle, vault_id: &str) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("vaults")
+        .join(vault_id)
+        .join(STAGING_DIR))
+}
+
+/// Files that queued uploads which haven't finished still read from
+pub fn unfinished_sources<'a>(items: impl IntoIterator<Item = &'a UploadItem>) -> Vec<PathBuf> {
+    items
+        .into_iter()
+        .filter(|item| !matches!(item.status, UploadStatus::Completed | UploadStatus::Cancelled))
+        .flat_map(|item| {
+            let companion = match &item.motion {
+                Some(MotionSource::Companion { path }) => Some(path.clone()),
+                _ => None,
+            };
+            std::iter::once(item.path.clone()).chain(companion)
+        })
+        .collect()
+}
+
+/// Remove extracted exports that no unfinished upload reads from anymore
+pub fn clean_staging(staging: &Path, in_use: &[PathBuf]) {
+    let Ok(entries) = std::fs::read_dir(staging) else {
+        return;
+    };
+    for dir in entries.flatten().map(|e| e.path()) {
+        if !in_use.iter().any(|path| path.starts_with(&dir)) {
+            std::fs::remove_dir_all(&dir).ok();
+        }
+    }
+}
+
+/// Extract zips into `staging` and scan everything on the blocking pool
+async fn prepare(request: &LibraryImportRequest, staging: PathBuf) -> Result<LibraryScan, String> {
+    let paths: Vec<PathBuf> = request.paths.iter().map(PathBuf::from).collect();
+    let folder_albums = request.folder_albums;
+
+    tokio::task::spawn_blocking(move || {
+        let mut roots = Vec::new();
+        let mut extracted = None;
+        let mut budget = ExtractBudget::default();
+        for path in paths {
+            let is_zip = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+            if !is_zip {
+                roots.push(path);
+                continue;
+            }
+            let dest = extracted.get_or_insert_with(|| staging.join(uuid::Uuid::new_v4().to_string()));
+            extract_zip(&path, dest, &mut budget).map_err(|e| format!("Failed to extract {:?}: {}", path, e))?;
+        }
+        roots.extend(extracted);
+        Ok::<_, String>(scan(&roots, folder_albums))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// What an exported album became
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MembershipKind {
+    Album,
+    Memory,
+}
+
+impl MembershipKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MembershipKind::Album => "album",
+            MembershipKind::Memory => "memory",
+        }
+    }
+}
+
+/// Remember that a queued upload joins an album or memory once its photo exists
+fn record_pending(
+    conn: &Connection,
+    upload_id: &str,
+    kind: MembershipKind,
+    target_id: &str,
+    position: usize,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO pending_memberships (upload_id, target_kind, target_id, position)
+         VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![upload_id, kind.as_str(), target_id, position as i64],
+    )?;
+    Ok(())
+}
+
+/// Add the photo of a finished upload to the albums and memories it was imported into
+pub fn attach_pending(conn: &Connection, photo_id: &str) -> rusqlite::Result<()> {
+    let pending: Vec<(String, String, i64)> = {
+        let mut stmt = conn.prepare(
+            "SELECT target_kind, target_id, position FROM pending_memberships WHERE upload_id = ?1",
+        )?;
+        let rows = stmt.query_map([photo_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+
+    for (kind, target_id, position) in pending {
+        // The album or memory may have been deleted since the import
+        let result = if kind == MembershipKind::Memory.as_str() {
+            memories::insert_media_at(conn, &target_id, photo_id, position)
+        } else {
+            albums::insert_photo_at(conn, &target_id, photo_id, position)
+        };
+        if let Err(e) = result {
+            log::warn!("[Library Import] Failed to add {} to {} {}: {}", photo_id, kind, target_id, e);
+        }
+    }
+    forget_pending(conn, &[photo_id.to_string()])
+}
+
+/// Drop the memberships of uploads that won't produce a photo (cancelled or removed)
+pub fn forget_pending(conn: &Connection, upload_ids: &[String]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("DELETE FROM pending_memberships WHERE upload_id = ?1")?;
+    for id in upload_ids {
+        stmt.execute([id])?;
+    }
+    Ok(())
+}
+
+/// Remove extracted exports the upload queue doesn't need anymore
+async fn clean_unused_staging(staging: &Path, upload_state: &UploadManagerState) -> Result<(), String> {
+    let in_use = {
+        let manager_guard = upload_state.manager.lock().await;
+        let manager = manager_guard.as_ref().ok_or("Upload manager not initialized")?;
+        unfinished_sources(&manager.get_state().await.items)
+    };
+    clean_staging(staging, &in_use);
+    Ok(())
+}
+
+/// Queue a Google Takeout or Apple Photos export and recreate its albums
+#[tauri::command]
+pub async fn import_library(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    upload_state: State<'_, UploadManagerState>,
+    request: LibraryImportRequest,
+) -> Result<LibraryImportResult, String> {
+    let vault_id = state.config.lock().await.as_ref().ok_or("Vault not loaded")?.id.clone();
+    let staging = staging_dir(&app, &vault_id)?;
+
+    // Left over from imports whose uploads were removed from the queue
+    clean_unused_staging(&staging, &upload_state).await?;
+
+    let result = queue_export(&state, &upload_state, request, staging.clone()).await;
+    match &result {
+        Ok(result) if result.album_count == 0 => {}
+        Ok(_) => crate::sync_daemon::notify_local_change(&app).await,
+        Err(_) => {
+            // Drop what this import extracted, unless some of it was queued already
+            clean_unused_staging(&staging, &upload_state).await.ok();
+            // Albums created before the failure still need to sync
+            crate::sync_daemon::notify_local_change(&app).await;
+        }
+    }
+    result
+}
+
+async fn queue_export(
+    state: &AppState,
+    upload_state: &UploadManagerState,
+    request: LibraryImportRequest,
+    staging: PathBuf,
+) -> Result<LibraryImportResult, String> {
+    let scanned = prepare(&request, staging).await?;
+    let total_size: u64 = scanned.files.iter().map(|f| f.size).sum();
+    let fresh_upload_auto_disabled =
+        request.fresh_upload && upload_manager::exceeds_fresh_upload_limits(scanned.files.len(), total_size);
+    let fresh_upload = request.fresh_upload && !fresh_upload_auto_disabled;
+
+    let mut paths = Vec::with_capacity(scanned.files.len());
+    let mut imported = HashMap::new();
+    for file in scanned.files {
+        paths.push(file.path.clone());
+        if let Some(metadata) = file.metadata {
+            imported.insert(file.path, metadata);
+        }
+    }
+
+    let mut items = {
+        let manager_guard = upload_state.manager.lock().await;
+        let manager = manager_guard.as_ref().ok_or("Upload manager not initialized")?;
+        let (items, _) = manager
+            .add_files_with_metadata(paths, fresh_upload, None, false, request.batch_name, imported)
+            .await
+            .map_err(|e| e.to_string())?;
+        // Duplicates still count for album membership, but aren't listed
+        for item in items.iter().filter(|i| matches!(i.status, UploadStatus::Duplicate { .. })) {
+            manager.remove_item(&item.id).await;
+        }
+        items
+    };
+
+    // Photo each exported file ends up as (Live Photo videos belong to their still)
+    let mut photo_ids: HashMap<PathBuf, String> = HashMap::new();
+    for item in &items {
+        let id = match &item.status {
+            UploadStatus::Duplicate { of_id, .. } => of_id.clone(),
+            _ => item.id.clone(),
+        };
+        if let Some(MotionSource::Companion { path }) = &item.motion {
+            photo_ids.insert(path.clone(), id.clone());
+        }
+        photo_ids.insert(item.path.clone(), id);
+    }
+
+    let kind = match request.albums {
+        AlbumTarget::Album => Some(MembershipKind::Album),
+        AlbumTarget::Memory => Some(MembershipKind::Memory),
+        AlbumTarget::Skip => None,
+    };
+    let mut album_count = 0;
+    if let Some(kind) = kind {
+        // Photos that are still uploading join once they exist (see `attach_pending`).
+        // Holding the DB lock keeps uploads from finishing in between.
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        for album in &scanned.albums {
+            let mut ids: Vec<String> = Vec::new();
+            for id in album.files.iter().filter_map(|path| photo_ids.get(path)) {
+                if !ids.contains(id) {
+                    ids.push(id.clone());
+                }
+            }
+            if ids.is_empty() {
+                continue;
+            }
+
+            let target_id = match kind {
+                MembershipKind::Album => albums::insert_album(conn, &album.title)?,
+                MembershipKind::Memory => {
+                    let date = album
+                        .files
+                        .iter()
+                        .filter_map(|path| items.iter().find(|i| i.path == *path))
+                        .filter_map(|item| item.imported.as_ref()?.captured_at)
+                        .min()
+                        .unwrap_or_else(Utc::now);
+                    memories::insert_memory(
+                        conn,
+                        CreateMemoryPayload {
+                            title: album.title.clone(),
+                            text_content: album.description.clone().unwrap_or_default(),
+                            date: date.format("%Y-%m-%d").to_string(),
+                            media_ids: Vec::new(),
+                        },
+                    )?
+                    .id
+                }
+            };
+
+            for (position, id) in ids.iter().enumerate() {
+                let exists = conn
+                    .query_row("SELECT 1 FROM photos WHERE id = ?1", [id], |_| Ok(()))
+                    .is_ok();
+                if exists {
+                    match kind {
+                        MembershipKind::Album => albums::insert_photo_at(conn, &target_id, id, position as i64)?,
+                        MembershipKind::Memory => memories::insert_media_at(conn, &target_id, id, position as i64)?,
+                    }
+                } else {
+                    record_pending(conn, id, kind, &target_id, position).map_err(|e| e.to_string())?;
+                }
+            }
+            album_count += 1;
+        }
+    }
+
+    let duplicate_count = items
+        .iter()
+        .filter(|i| matches!(i.status, UploadStatus::Duplicate { .. }))
+        .count();
+    items.retain(|i| !matches!(i.status, UploadStatus::Duplicate { .. }));
+    let with_sidecar_count = items.iter().filter(|i| i.imported.is_some()).count();
+    log::info!(
+        "[Library Import] Queued {} files ({} with sidecars, {} duplicates), {} albums",
+        items.len(),
+        with_sidecar_count,
+        duplicate_count,
+        album_count
+    );
+
+    Ok(LibraryImportResult {
+        items,
+        with_sidecar_count,
+        duplicate_count,
+        album_count,
+        fresh_upload_auto_disabled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_takeout_sidecar_names() {
+        let stems: Vec<String> = [
+            "IMG_0001.jpg",
+            "IMG_0002.jpg.supplemental-metadata",
+            "IMG_0003.jpg(1)",
+            "IMG_0004.HEIC.supplemental-metad",
+            "PXL_20230101_101010123.NIGHT.RAW-01.MP.COVER.j",
+            "VID_0005",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let found = |name: &str| takeout_sidecar(name, &stems);
+        assert_eq!(found("IMG_0001.jpg"), Some("IMG_0001.jpg"));
+        assert_eq!(found("IMG_0001-edited.jpg"), Some("IMG_0001.jpg"));
+        assert_eq!(found("IMG_0002.jpg"), Some("IMG_0002.jpg.supplemental-metadata"));
+        assert_eq!(found("IMG_0003(1).jpg"), Some("IMG_0003.jpg(1)"));
+        assert_eq!(found("IMG_0004.HEIC"), Some("IMG_0004.HEIC.supplemental-metad"));
+        assert_eq!(
+            found("PXL_20230101_101010123.NIGHT.RAW-01.MP.COVER.jpg"),
+            Some("PXL_20230101_101010123.NIGHT.RAW-01.MP.COVER.j")
+        );
+        assert_eq!(found("VID_0005.mp4"), Some("VID_0005"));
+        assert_eq!(found("IMG_0006.jpg"), None);
+    }
+
+    #[test]
+    fn test_scan_takeout_folder() {
+        let root = std::env::temp_dir().join(format!("boreal-takeout-{}", uuid::Uuid::new_v4()));
+        let album = root.join("Google Photos/Iceland 2023");
+        let year = root.join("Google Photos/Photos from 2023");
+        std::fs::create_dir_all(&album).unwrap();
+        std::fs::create_dir_all(&year).unwrap();
+
+        let sidecar = r#"{
+            "title": "IMG_0001.jpg",
+            "description": "Midnight sun",
+            "photoTakenTime": { "timestamp": "1687389000", "formatted": "Jun 21, 2023" },
+            "geoData": { "latitude": 0.0, "longitude": 0.0, "altitude": 0.0 },
+            "geoDataExif": { "latitude": 64.1466, "longitude": -21.9426, "altitude": 12.0 },
+            "favorited": true
+        }"#;
+        for dir in [&album, &year] {
+            std::fs::write(dir.join("IMG_0001.jpg"), b"\xFF\xD8\xFF\xE0 jpeg").unwrap();
+            std::fs::write(dir.join("IMG_0001.jpg.supplemental-metadata.json"), sidecar).unwrap();
+        }
+        std::fs::write(album.join(TAKEOUT_ALBUM_FILE), r#"{ "title": "Iceland 2023" }"#).unwrap();
+
+        let scanned = scan(std::slice::from_ref(&root), false);
+        assert_eq!(scanned.files.len(), 2);
+        let metadata = scanned.files[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata.captured_at.unwrap().to_rfc3339(), "2023-06-21T23:10:00+00:00");
+        assert_eq!((metadata.latitude, metadata.longitude), (Some(64.1466), Some(-21.9426)));
+        assert_eq!(metadata.description.as_deref(), Some("Midnight sun"));
+        assert!(metadata.favorite);
+        assert_eq!(
+            scanned.albums,
+            vec![LibraryAlbum {
+                title: "Iceland 2023".to_string(),
+                description: None,
+                files: vec![album.join("IMG_0001.jpg")],
+            }]
+        );
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_extract_zip_limits() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("boreal-zip-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let zip_path = dir.join("takeout.zip");
+        {
+            let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+            for name in ["a.jpg", "b.jpg"] {
+                writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+                writer.write_all(&[0u8; 100]).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let mut budget = ExtractBudget::default();
+        extract_zip(&zip_path, &dir.join("ok"), &mut budget).unwrap();
+        assert_eq!(budget.bytes, MAX_EXTRACTED_BYTES - 200);
+        assert_eq!(budget.entries, MAX_EXTRACTED_ENTRIES - 2);
+
+        let mut too_many = ExtractBudget { bytes: 1000, entries: 1 };
+        assert!(extract_zip(&zip_path, &dir.join("entries"), &mut too_many).is_err());
+        let mut too_large = ExtractBudget { bytes: 150, entries: 10 };
+        assert!(extract_zip(&zip_path, &dir.join("bytes"), &mut too_large).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_pending_memberships() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        let album = albums::insert_album(&conn, "Trip").unwrap();
+        conn.execute(
+            "INSERT INTO photos (id, filename, s3_key, tier) VALUES ('p0', 'a.jpg', 'k0', 'Standard')",
+            [],
+        )
+        .unwrap();
+        albums::insert_photo_at(&conn, &album, "p0", 0).unwrap();
+        record_pending(&conn, "u2", MembershipKind::Album, &album, 2).unwrap();
+        record_pending(&conn, "u1", MembershipKind::Album, &album, 1).unwrap();
+
+        // u1 finishes first, u2 is cancelled
+        conn.execute(
+            "INSERT INTO photos (id, filename, s3_key, tier) VALUES ('u1', 'b.jpg', 'k1', 'Standard')",
+            [],
+        )
+        .unwrap();
+        attach_pending(&conn, "u1").unwrap();
+        forget_pending(&conn, &["u2".to_string()]).unwrap();
+
+        let items: Vec<(String, i64)> = conn
+            .prepare("SELECT photo_id, position FROM album_items WHERE album_id = ?1 ORDER BY position")
+            .unwrap()
+            .query_map([&album], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(items, vec![("p0".to_string(), 0), ("u1".to_string(), 1)]);
+        let pending: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pending_memberships", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(pending, 0);
+    }
+}
@@ -5,10 +5,13 @@
 //! across devices.
 
 use crate::crypto;
+use crate::curation;
 use crate::db;
 use anyhow::{Context, Result};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 /// Current manifest version for migration compatibility
 const MANIFEST_VERSION: u32 = 1;
@@ -16,8 +19,11 @@ const MANIFEST_VERSION: u32 = 1;
 /// S3 key for the encrypted manifest
 pub const MANIFEST_S3_KEY: &str = "manifest.enc";
 
+/// Number of local manifest.db snapshots kept before merges
+const MAX_LOCAL_SNAPSHOTS: usize = 10;
+
 /// Represents a photo record for sync
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PhotoRecord {
     pub id: String,
     pub filename: String,
@@ -39,6 +45,55 @@ pub struct PhotoRecord {
     pub iso: Option<i32>,
     pub f_number: Option<f64>,
     pub exposure_time: Option<String>,
+    /// Set while the photo is in the trash
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    /// When the photo was last trashed or restored (orders trash state across devices)
+    #[serde(default)]
+    pub trash_updated_at: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// 0 (unrated) to 5 stars
+    #[serde(default)]
+    pub rating: u8,
+    #[serde(default)]
+    pub color_label: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// When favorite/rating/color label/tags last changed (orders curation across devices)
+    #[serde(default)]
+    pub curation_updated_at: Option<String>,
+    /// Keyed hash of the source file, for duplicate detection (see `crypto::content_hash`)
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// How the original was stored (see `vault::OriginalPolicy`)
+    #[serde(default)]
+    pub original_policy: Option<String>,
+    /// Optimized copy kept next to a lossless original
+    #[serde(default)]
+    pub display_key: Option<String>,
+    #[serde(default)]
+    pub display_size_bytes: Option<i64>,
+    /// Video of a Live Photo / Motion Photo
+    #[serde(default)]
+    pub motion_key: Option<String>,
+    #[serde(default)]
+    pub motion_size_bytes: Option<i64>,
+    /// Caption (XMP `dc:description` or an imported library's description)
+    #[serde(default)]
+    pub description: Option<String>,
+    /// How the stored objects were checked after upload (see `verification::Verification`)
+    #[serde(default)]
+    pub verification: Option<String>,
+    #[serde(default)]
+    pub verified_at: Option<String>,
+}
+
+/// A permanently purged photo, kept so merges don't resurrect it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhotoTombstone {
+    pub id: String,
+    pub purged_at: String,
 }
 
 /// Represents a memory record for sync
@@ -60,6 +115,52 @@ pub struct MemoryMediaRecord {
     pub display_order: i32,
 }
 
+/// Represents an album for sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlbumRecord {
+    pub id: String,
+    pub name: String,
+    pub cover_photo_id: Option<String>,
+    pub sort_mode: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub deleted_at: Option<String>,
+}
+
+/// Represents an album membership for sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlbumItemRecord {
+    pub album_id: String,
+    pub photo_id: String,
+    pub position: i64,
+    pub added_at: String,
+}
+
+/// Represents a smart album (saved query) for sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartAlbumRecord {
+    pub id: String,
+    pub name: String,
+    /// JSON-encoded `smart_albums::SmartAlbumRule`
+    pub rule: String,
+    pub sort: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub deleted_at: Option<String>,
+}
+
+/// Represents a device registry entry for sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceRecord {
+    pub id: String,
+    pub hostname: String,
+    pub platform: String,
+    pub app_version: String,
+    pub first_sync_at: String,
+    pub last_sync_at: String,
+    pub revoked_at: Option<String>,
+}
+
 /// The complete manifest data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManifestData {
@@ -69,18 +170,72 @@ pub struct ManifestData {
     pub photos: Vec<PhotoRecord>,
     pub memories: Vec<MemoryRecord>,
     pub memory_media: Vec<MemoryMediaRecord>,
+    /// Missing in manifests written before the device registry existed
+    #[serde(default)]
+    pub devices: Vec<DeviceRecord>,
+    /// Missing in manifests written before the trash existed
+    #[serde(default)]
+    pub tombstones: Vec<PhotoTombstone>,
+    /// Missing in manifests written before albums existed
+    #[serde(default)]
+    pub albums: Vec<AlbumRecord>,
+    #[serde(default)]
+    pub album_items: Vec<AlbumItemRecord>,
+    /// Missing in manifests written before smart albums existed
+    #[serde(default)]
+    pub smart_albums: Vec<SmartAlbumRecord>,
     pub updated_at: String,
 }
 
 /// Statistics from a merge operation
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MergeStats {
     pub photos_added: u32,
     pub photos_updated: u32,
     pub memories_added: u32,
     pub memories_updated: u32,
+    /// Local photos removed because another device purged them
+    pub photos_purged: u32,
+    pub albums_added: u32,
+    pub albums_updated: u32,
+    pub smart_albums_added: u32,
+    pub smart_albums_updated: u32,
+}
+
+/// Statistics from a point-in-time restore
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreStats {
+    pub photos_restored: u32,
+    pub photos_removed: u32,
+    pub memories_restored: u32,
+    pub memories_removed: u32,
+    pub albums_restored: u32,
+    pub albums_removed: u32,
+    pub smart_albums_restored: u32,
+    pub smart_albums_removed: u32,
+}
+
+/// Summary of a manifest version, shown before restoring it
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestPreview {
+    pub name: String,
+    pub photo_count: usize,
+    pub memory_count: usize,
+    pub album_count: usize,
+    pub updated_at: String,
 }
 
+impl ManifestData {
+    pub fn preview(&self) -> ManifestPreview {
+        ManifestPreview {
+            name: self.name.clone(),
+            photo_count: self.photos.len(),
+            memory_count: self.memories.len(),
+            album_count: self.albums.iter().filter(|a| a.deleted_at.is_none()).count(),
+            updated_at: self.updated_at.clone(),
+        }
+    }
+}
 
 
 /// Export all vault data from SQLite to a ManifestData struct
@@ -92,7 +247,7 @@ pub fn export_manifest(conn: &Connection) -> Result<ManifestData> {
         .unwrap_or(0);
 
     // Export photos
-    let photos = export_photos(conn)?;
+    let photos = export_photos(conn, None)?;
 
     // Export memories
     let memories = export_memories(conn)?;
@@ -100,6 +255,19 @@ pub fn export_manifest(conn: &Connection) -> Result<ManifestData> {
     // Export memory_media associations
     let memory_media = export_memory_media(conn)?;
 
+    // Export device registry
+    let devices = export_devices(conn)?;
+
+    // Export purged photo IDs
+    let tombstones = export_tombstones(conn)?;
+
+    // Export albums and their items
+    let albums = export_albums(conn)?;
+    let album_items = export_album_items(conn)?;
+
+    // Export smart albums
+    let smart_albums = export_smart_albums(conn)?;
+
     Ok(ManifestData {
         version: MANIFEST_VERSION,
         name,
@@ -107,21 +275,42 @@ pub fn export_manifest(conn: &Connection) -> Result<ManifestData> {
         photos,
         memories,
         memory_media,
+        devices,
+        tombstones,
+        albums,
+        album_items,
+        smart_albums,
         updated_at: chrono::Utc::now().to_rfc3339(),
     })
 }
 
-fn export_photos(conn: &Connection) -> Result<Vec<PhotoRecord>> {
+/// Records of the given photos, e.g. to rewrite their metadata sidecars
+pub fn export_photo_records(conn: &Connection, ids: &[String]) -> Result<Vec<PhotoRecord>> {
+    export_photos(conn, Some(ids))
+}
+
+/// All photos, or only `ids`
+fn export_photos(conn: &Connection, ids: Option<&[String]>) -> Result<Vec<PhotoRecord>> {
+    let mut tags = curation::load_all_photo_tags(conn)?;
+    // Passed as one JSON array to stay clear of SQLite's parameter limit
+    let ids_json = ids.map(serde_json::to_string).transpose()?;
+
     let mut stmt = conn.prepare(
         "SELECT id, filename, width, height, created_at, captured_at, size_bytes, 
                 s3_key, thumbnail_key, tier, media_type, latitude, longitude, thumbnail_size_bytes,
-                make, model, lens_model, iso, f_number, exposure_time
-         FROM photos",
+                make, model, lens_model, iso, f_number, exposure_time,
+                deleted_at, trash_updated_at, favorite, rating, color_label, curation_updated_at,
+                content_hash, original_policy, display_key, display_size_bytes,
+                motion_key, motion_size_bytes, description, verification, verified_at
+         FROM photos
+         WHERE ?1 IS NULL OR id IN (SELECT value FROM json_each(?1))",
     )?;
 
-    let photos = stmt.query_map([], |row| {
+    let photos = stmt.query_map([ids_json], |row| {
+        let id: String = row.get(0)?;
         Ok(PhotoRecord {
-            id: row.get(0)?,
+            tags: tags.remove(&id).unwrap_or_default(),
+            id,
             filename: row.get(1)?,
             width: row.get(2)?,
             height: row.get(3)?,
@@ -143,6 +332,21 @@ fn export_photos(conn: &Connection) -> Result<Vec<PhotoRecord>> {
             iso: row.get(17)?,
             f_number: row.get(18)?,
             exposure_time: row.get(19)?,
+            deleted_at: row.get(20)?,
+            trash_updated_at: row.get(21)?,
+            favorite: row.get(22)?,
+            rating: row.get(23)?,
+            color_label: row.get(24)?,
+            curation_updated_at: row.get(25)?,
+            content_hash: row.get(26)?,
+            original_policy: row.get(27)?,
+            display_key: row.get(28)?,
+            display_size_bytes: row.get(29)?,
+            motion_key: row.get(30)?,
+            motion_size_bytes: row.get(31)?,
+            description: row.get(32)?,
+            verification: row.get(33)?,
+            verified_at: row.get(34)?,
         })
     })?;
 
@@ -187,6 +391,106 @@ fn export_memory_media(conn: &Connection) -> Result<Vec<MemoryMediaRecord>> {
         .context("Failed to export memory_media")
 }
 
+fn export_devices(conn: &Connection) -> Result<Vec<DeviceRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, hostname, platform, app_version, first_sync_at, last_sync_at, revoked_at
+         FROM devices",
+    )?;
+
+    let records = stmt.query_map([], |row| {
+        Ok(DeviceRecord {
+            id: row.get(0)?,
+            hostname: row.get(1)?,
+            platform: row.get(2)?,
+            app_version: row.get(3)?,
+            first_sync_at: row.get(4)?,
+            last_sync_at: row.get(5)?,
+            revoked_at: row.get(6)?,
+        })
+    })?;
+
+    records
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to export devices")
+}
+
+fn export_tombstones(conn: &Connection) -> Result<Vec<PhotoTombstone>> {
+    let mut stmt = conn.prepare("SELECT id, purged_at FROM photo_tombstones")?;
+
+    let records = stmt.query_map([], |row| {
+        Ok(PhotoTombstone {
+            id: row.get(0)?,
+            purged_at: row.get(1)?,
+        })
+    })?;
+
+    records
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to export tombstones")
+}
+
+fn export_albums(conn: &Connection) -> Result<Vec<AlbumRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, cover_photo_id, sort_mode, created_at, updated_at, deleted_at FROM albums",
+    )?;
+
+    let records = stmt.query_map([], |row| {
+        Ok(AlbumRecord {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            cover_photo_id: row.get(2)?,
+            sort_mode: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            deleted_at: row.get(6)?,
+        })
+    })?;
+
+    records
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to export albums")
+}
+
+fn export_album_items(conn: &Connection) -> Result<Vec<AlbumItemRecord>> {
+    let mut stmt =
+        conn.prepare("SELECT album_id, photo_id, position, added_at FROM album_items")?;
+
+    let records = stmt.query_map([], |row| {
+        Ok(AlbumItemRecord {
+            album_id: row.get(0)?,
+            photo_id: row.get(1)?,
+            position: row.get(2)?,
+            added_at: row.get(3)?,
+        })
+    })?;
+
+    records
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to export album items")
+}
+
+fn export_smart_albums(conn: &Connection) -> Result<Vec<SmartAlbumRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, rule, sort, created_at, updated_at, deleted_at FROM smart_albums",
+    )?;
+
+    let records = stmt.query_map([], |row| {
+        Ok(SmartAlbumRecord {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            rule: row.get(2)?,
+            sort: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            deleted_at: row.get(6)?,
+        })
+    })?;
+
+    records
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to export smart albums")
+}
+
 /// Import manifest data into SQLite, merging with existing data
 /// Uses "newest updated_at wins" conflict resolution
 pub fn import_manifest(conn: &Connection, data: ManifestData) -> Result<MergeStats> {
@@ -210,16 +514,16 @@ pub fn import_manifest(conn: &Connection, data: ManifestData) -> Result<MergeSta
         db::set_metadata(conn, "name", &data.name)?;
     }
 
-    // Merge photos
-    for photo in data.photos {
-        let result = merge_photo(conn, &photo)?;
-        match result {
-            MergeResult::Added => stats.photos_added += 1,
-            MergeResult::Updated => stats.photos_updated += 1,
-            MergeResult::Skipped => {}
+    // Apply purges from other devices before merging photos
+    for tombstone in &data.tombstones {
+        if merge_tombstone(conn, tombstone)? {
+            stats.photos_purged += 1;
         }
     }
 
+    // Merge photos
+    merge_photos(conn, &data.photos, &mut stats)?;
+
     // Merge memories
     for memory in data.memories {
         let result = merge_memory(conn, &memory)?;
@@ -232,6 +536,9 @@ pub fn import_manifest(conn: &Connection, data: ManifestData) -> Result<MergeSta
 
     // Merge memory_media (simple upsert)
     for mm in data.memory_media {
+        if is_tombstoned(conn, &mm.media_id)? {
+            continue;
+        }
         conn.execute(
             "INSERT OR REPLACE INTO memory_media (memory_id, media_id, display_order) 
              VALUES (?1, ?2, ?3)",
@@ -239,9 +546,47 @@ pub fn import_manifest(conn: &Connection, data: ManifestData) -> Result<MergeSta
         )?;
     }
 
+    // Merge albums (an album's items travel with it)
+    let items_by_album = group_album_items(&data.album_items);
+    for album in &data.albums {
+        let items = items_by_album.get(album.id.as_str()).map(Vec::as_slice).unwrap_or(&[]);
+        match merge_album(conn, album, items)? {
+            MergeResult::Added => stats.albums_added += 1,
+            MergeResult::Updated => stats.albums_updated += 1,
+            MergeResult::Skipped => {}
+        }
+    }
+
+    // Merge smart albums
+    for smart_album in &data.smart_albums {
+        match merge_smart_album(conn, smart_album)? {
+            MergeResult::Added => stats.smart_albums_added += 1,
+            MergeResult::Updated => stats.smart_albums_updated += 1,
+            MergeResult::Skipped => {}
+        }
+    }
+
+    // Merge device registry
+    for device in &data.devices {
+        merge_device(conn, device)?;
+    }
+
     Ok(stats)
 }
 
+/// Merge photo records into SQLite using "newest wins" conflict resolution
+pub fn merge_photos(conn: &Connection, photos: &[PhotoRecord], stats: &mut MergeStats) -> Result<()> {
+    for photo in photos {
+        let result = merge_photo(conn, photo)?;
+        match result {
+            MergeResult::Added => stats.photos_added += 1,
+            MergeResult::Updated => stats.photos_updated += 1,
+            MergeResult::Skipped => {}
+        }
+    }
+    Ok(())
+}
+
 enum MergeResult {
     Added,
     Updated,
@@ -249,6 +594,10 @@ enum MergeResult {
 }
 
 fn merge_photo(conn: &Connection, photo: &PhotoRecord) -> Result<MergeResult> {
+    if is_tombstoned(conn, &photo.id)? {
+        return Ok(MergeResult::Skipped);
+    }
+
     // Check if photo exists locally
     let existing: Option<String> = conn
         .query_row(
@@ -261,73 +610,26 @@ fn merge_photo(conn: &Connection, photo: &PhotoRecord) -> Result<MergeResult> {
     match existing {
         None => {
             // New photo - insert
-            conn.execute(
-                "INSERT INTO photos (id, filename, width, height, created_at, captured_at, 
-                                    size_bytes, s3_key, thumbnail_key, tier, media_type, 
-                                    latitude, longitude, thumbnail_size_bytes,
-                                    make, model, lens_model, iso, f_number, exposure_time)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
-                rusqlite::params![
-                    photo.id,
-                    photo.filename,
-                    photo.width,
-                    photo.height,
-                    photo.created_at,
-                    photo.captured_at,
-                    photo.size_bytes,
-                    photo.s3_key,
-                    photo.thumbnail_key,
-                    photo.tier,
-                    photo.media_type,
-                    photo.latitude,
-                    photo.longitude,
-                    photo.thumbnail_size_bytes,
-                    photo.make,
-                    photo.model,
-                    photo.lens_model,
-                    photo.iso,
-                    photo.f_number,
-                    photo.exposure_time,
-                ],
-            )?;
+            insert_photo(conn, photo)?;
             Ok(MergeResult::Added)
         }
         Some(local_created) => {
             // Photo exists - compare timestamps (newest wins)
-            let remote_created = photo.created_at.as_deref().unwrap_or("");
-            if remote_created > local_created.as_str() {
+            // Compared in canonical UTC form: manifests from older versions may use other offsets
+            let remote_created = photo.created_at.as_deref().map(db::normalize_timestamp).unwrap_or_default();
+            let mut changed = false;
+            if remote_created > db::normalize_timestamp(&local_created) {
                 // Remote is newer - update
-                conn.execute(
-                    "UPDATE photos SET filename = ?2, width = ?3, height = ?4, 
-                                       created_at = ?5, captured_at = ?6, size_bytes = ?7,
-                                       s3_key = ?8, thumbnail_key = ?9, tier = ?10,
-                                       media_type = ?11, latitude = ?12, longitude = ?13,
-                                       thumbnail_size_bytes = ?14, make = ?15, model = ?16,
-                                       lens_model = ?17, iso = ?18, f_number = ?19, exposure_time = ?20
-                     WHERE id = ?1",
-                    rusqlite::params![
-                        photo.id,
-                        photo.filename,
-                        photo.width,
-                        photo.height,
-                        photo.created_at,
-                        photo.captured_at,
-                        photo.size_bytes,
-                        photo.s3_key,
-                        photo.thumbnail_key,
-                        photo.tier,
-                        photo.media_type,
-                        photo.latitude,
-                        photo.longitude,
-                        photo.thumbnail_size_bytes,
-                        photo.make,
-                        photo.model,
-                        photo.lens_model,
-                        photo.iso,
-                        photo.f_number,
-                        photo.exposure_time,
-                    ],
-                )?;
+                update_photo(conn, photo)?;
+                changed = true;
+            }
+
+            // Trash state and curation carry their own timestamps
+            changed |= merge_trash_state(conn, photo)?;
+            changed |= merge_curation(conn, photo)?;
+            changed |= merge_content_hash(conn, photo)?;
+
+            if changed {
                 Ok(MergeResult::Updated)
             } else {
                 // Local is newer or same - skip
@@ -337,6 +639,208 @@ fn merge_photo(conn: &Connection, photo: &PhotoRecord) -> Result<MergeResult> {
     }
 }
 
+/// Trash state has its own timestamp since trashing doesn't change `created_at`:
+/// the most recent trash/restore action wins. Returns whether the local row changed.
+fn merge_trash_state(conn: &Connection, photo: &PhotoRecord) -> Result<bool> {
+    let Some(remote_updated) = photo.trash_updated_at.as_deref() else {
+        return Ok(false);
+    };
+    let updated = conn.execute(
+        "UPDATE photos SET deleted_at = ?2, trash_updated_at = ?3
+         WHERE id = ?1 AND COALESCE(trash_updated_at, '') < ?3",
+        rusqlite::params![photo.id, photo.deleted_at, remote_updated],
+    )?;
+    Ok(updated > 0)
+}
+
+/// The most recent curation edit wins, covering favorite, rating, color label and tags.
+/// Returns whether the local row changed.
+fn merge_curation(conn: &Connection, photo: &PhotoRecord) -> Result<bool> {
+    let Some(remote_updated) = photo.curation_updated_at.as_deref() else {
+        return Ok(false);
+    };
+    let local_updated: Option<String> = conn.query_row(
+        "SELECT curation_updated_at FROM photos WHERE id = ?1",
+        [&photo.id],
+        |row| row.get(0),
+    )?;
+    if local_updated.as_deref().unwrap_or("") >= remote_updated {
+        return Ok(false);
+    }
+    write_curation(conn, photo)?;
+    Ok(true)
+}
+
+/// A file's content hash never changes, so a known hash fills in a missing one
+/// (photos uploaded by older versions). Returns whether the local row changed.
+fn merge_content_hash(conn: &Connection, photo: &PhotoRecord) -> Result<bool> {
+    let Some(hash) = photo.content_hash.as_deref() else {
+        return Ok(false);
+    };
+    let updated = conn.execute(
+        "UPDATE photos SET content_hash = ?2 WHERE id = ?1 AND content_hash IS NULL",
+        rusqlite::params![photo.id, hash],
+    )?;
+    Ok(updated > 0)
+}
+
+fn write_curation(conn: &Connection, photo: &PhotoRecord) -> Result<()> {
+    conn.execute(
+        "UPDATE photos SET favorite = ?2, rating = ?3, color_label = ?4, curation_updated_at = ?5
+         WHERE id = ?1",
+        rusqlite::params![
+            photo.id,
+            photo.favorite,
+            photo.rating,
+            photo.color_label,
+            photo.curation_updated_at,
+        ],
+    )?;
+    curation::set_photo_tags(conn, &photo.id, &photo.tags)?;
+    Ok(())
+}
+
+fn write_trash_state(conn: &Connection, photo: &PhotoRecord) -> Result<()> {
+    conn.execute(
+        "UPDATE photos SET deleted_at = ?2, trash_updated_at = ?3 WHERE id = ?1",
+        rusqlite::params![photo.id, photo.deleted_at, photo.trash_updated_at],
+    )?;
+    Ok(())
+}
+
+fn is_tombstoned(conn: &Connection, id: &str) -> Result<bool> {
+    let found = conn
+        .query_row("SELECT 1 FROM photo_tombstones WHERE id = ?1", [id], |_| Ok(()))
+        .is_ok();
+    Ok(found)
+}
+
+/// Record a purge and drop the photo locally if it still exists.
+/// Returns whether a local photo was removed.
+fn merge_tombstone(conn: &Connection, tombstone: &PhotoTombstone) -> Result<bool> {
+    conn.execute(
+        "INSERT INTO photo_tombstones (id, purged_at) VALUES (?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET purged_at = MIN(purged_at, excluded.purged_at)",
+        rusqlite::params![tombstone.id, tombstone.purged_at],
+    )?;
+    delete_photo_rows(conn, &tombstone.id)
+}
+
+/// Delete a photo and every row that references it. Returns whether the photo existed.
+pub fn delete_photo_rows(conn: &Connection, id: &str) -> Result<bool> {
+    conn.execute("DELETE FROM embeddings WHERE photo_id = ?1", [id])?;
+    conn.execute("DELETE FROM original_restores WHERE photo_id = ?1", [id])?;
+    conn.execute("DELETE FROM memory_media WHERE media_id = ?1", [id])?;
+    conn.execute("DELETE FROM album_items WHERE photo_id = ?1", [id])?;
+    conn.execute("DELETE FROM photo_tags WHERE photo_id = ?1", [id])?;
+    let removed = conn.execute("DELETE FROM photos WHERE id = ?1", [id])?;
+    Ok(removed > 0)
+}
+
+fn insert_photo(conn: &Connection, photo: &PhotoRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO photos (id, filename, width, height, created_at, captured_at, 
+                            size_bytes, s3_key, thumbnail_key, tier, media_type, 
+                            latitude, longitude, thumbnail_size_bytes,
+                            make, model, lens_model, iso, f_number, exposure_time,
+                            deleted_at, trash_updated_at,
+                            favorite, rating, color_label, curation_updated_at, content_hash,
+                            original_policy, display_key, display_size_bytes, motion_key, motion_size_bytes,
+                            description, verification, verified_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+                 ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35)",
+        rusqlite::params![
+            photo.id,
+            photo.filename,
+            photo.width,
+            photo.height,
+            photo.created_at.as_deref().map(db::normalize_timestamp),
+            photo.captured_at.as_deref().map(db::normalize_timestamp),
+            photo.size_bytes,
+            photo.s3_key,
+            photo.thumbnail_key,
+            photo.tier,
+            photo.media_type,
+            photo.latitude,
+            photo.longitude,
+            photo.thumbnail_size_bytes,
+            photo.make,
+            photo.model,
+            photo.lens_model,
+            photo.iso,
+            photo.f_number,
+            photo.exposure_time,
+            photo.deleted_at,
+            photo.trash_updated_at,
+            photo.favorite,
+            photo.rating,
+            photo.color_label,
+            photo.curation_updated_at,
+            photo.content_hash,
+            photo.original_policy,
+            photo.display_key,
+            photo.display_size_bytes,
+            photo.motion_key,
+            photo.motion_size_bytes,
+            photo.description,
+            photo.verification,
+            photo.verified_at,
+        ],
+    )?;
+    curation::set_photo_tags(conn, &photo.id, &photo.tags)?;
+    Ok(())
+}
+
+/// Update the upload metadata of a photo. Trash state and curation are merged separately.
+fn update_photo(conn: &Connection, photo: &PhotoRecord) -> Result<()> {
+    conn.execute(
+        "UPDATE photos SET filename = ?2, width = ?3, height = ?4, 
+                           created_at = ?5, captured_at = ?6, size_bytes = ?7,
+                           s3_key = ?8, thumbnail_key = ?9, tier = ?10,
+                           media_type = ?11, latitude = ?12, longitude = ?13,
+                           thumbnail_size_bytes = ?14, make = ?15, model = ?16,
+                           lens_model = ?17, iso = ?18, f_number = ?19, exposure_time = ?20,
+                           content_hash = COALESCE(?21, content_hash),
+                           original_policy = ?22, display_key = ?23, display_size_bytes = ?24,
+                           motion_key = ?25, motion_size_bytes = ?26, description = ?27,
+                           verification = COALESCE(?28, verification),
+                           verified_at = COALESCE(?29, verified_at)
+         WHERE id = ?1",
+        rusqlite::params![
+            photo.id,
+            photo.filename,
+            photo.width,
+            photo.height,
+            photo.created_at.as_deref().map(db::normalize_timestamp),
+            photo.captured_at.as_deref().map(db::normalize_timestamp),
+            photo.size_bytes,
+            photo.s3_key,
+            photo.thumbnail_key,
+            photo.tier,
+            photo.media_type,
+            photo.latitude,
+            photo.longitude,
+            photo.thumbnail_size_bytes,
+            photo.make,
+            photo.model,
+            photo.lens_model,
+            photo.iso,
+            photo.f_number,
+            photo.exposure_time,
+            photo.content_hash,
+            photo.original_policy,
+            photo.display_key,
+            photo.display_size_bytes,
+            photo.motion_key,
+            photo.motion_size_bytes,
+            photo.description,
+            photo.verification,
+            photo.verified_at,
+        ],
+    )?;
+    Ok(())
+}
+
 fn merge_memory(conn: &Connection, memory: &MemoryRecord) -> Result<MergeResult> {
     // Check if memory exists locally
     let existing: Option<String> = conn
@@ -350,37 +854,14 @@ fn merge_memory(conn: &Connection, memory: &MemoryRecord) -> Result<MergeResult>
     match existing {
         None => {
             // New memory - insert
-            conn.execute(
-                "INSERT INTO memories (id, title, text_content, date, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                rusqlite::params![
-                    memory.id,
-                    memory.title,
-                    memory.text_content,
-                    memory.date,
-                    memory.created_at,
-                    memory.updated_at,
-                ],
-            )?;
+            insert_memory(conn, memory)?;
             Ok(MergeResult::Added)
         }
         Some(local_updated) => {
             // Memory exists - compare timestamps (newest wins)
             if memory.updated_at > local_updated {
                 // Remote is newer - update
-                conn.execute(
-                    "UPDATE memories SET title = ?2, text_content = ?3, date = ?4,
-                                         created_at = ?5, updated_at = ?6
-                     WHERE id = ?1",
-                    rusqlite::params![
-                        memory.id,
-                        memory.title,
-                        memory.text_content,
-                        memory.date,
-                        memory.created_at,
-                        memory.updated_at,
-                    ],
-                )?;
+                update_memory(conn, memory)?;
                 Ok(MergeResult::Updated)
             } else {
                 // Local is newer or same - skip
@@ -390,6 +871,364 @@ fn merge_memory(conn: &Connection, memory: &MemoryRecord) -> Result<MergeResult>
     }
 }
 
+fn insert_memory(conn: &Connection, memory: &MemoryRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO memories (id, title, text_content, date, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            memory.id,
+            memory.title,
+            memory.text_content,
+            memory.date,
+            memory.created_at,
+            memory.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+fn update_memory(conn: &Connection, memory: &MemoryRecord) -> Result<()> {
+    conn.execute(
+        "UPDATE memories SET title = ?2, text_content = ?3, date = ?4,
+                             created_at = ?5, updated_at = ?6
+         WHERE id = ?1",
+        rusqlite::params![
+            memory.id,
+            memory.title,
+            memory.text_content,
+            memory.date,
+            memory.created_at,
+            memory.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+fn group_album_items(items: &[AlbumItemRecord]) -> HashMap<&str, Vec<&AlbumItemRecord>> {
+    let mut grouped: HashMap<&str, Vec<&AlbumItemRecord>> = HashMap::new();
+    for item in items {
+        grouped.entry(item.album_id.as_str()).or_default().push(item);
+    }
+    grouped
+}
+
+/// Albums use "newest updated_at wins"; every membership or order change bumps
+/// `updated_at`, so the winning side's items replace the local ones.
+fn merge_album(conn: &Connection, album: &AlbumRecord, items: &[&AlbumItemRecord]) -> Result<MergeResult> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT updated_at FROM albums WHERE id = ?1",
+            [&album.id],
+            |row| row.get(0),
+        )
+        .ok();
+
+    let result = match existing {
+        None => MergeResult::Added,
+        Some(local_updated) if album.updated_at > local_updated => MergeResult::Updated,
+        Some(_) => return Ok(MergeResult::Skipped),
+    };
+
+    upsert_album(conn, album, items)?;
+    Ok(result)
+}
+
+fn upsert_album(conn: &Connection, album: &AlbumRecord, items: &[&AlbumItemRecord]) -> Result<()> {
+    conn.execute(
+        "INSERT INTO albums (id, name, cover_photo_id, sort_mode, created_at, updated_at, deleted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            cover_photo_id = excluded.cover_photo_id,
+            sort_mode = excluded.sort_mode,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            deleted_at = excluded.deleted_at",
+        rusqlite::params![
+            album.id,
+            album.name,
+            album.cover_photo_id,
+            album.sort_mode,
+            album.created_at,
+            album.updated_at,
+            album.deleted_at,
+        ],
+    )?;
+
+    conn.execute("DELETE FROM album_items WHERE album_id = ?1", [&album.id])?;
+    for item in items {
+        if is_tombstoned(conn, &item.photo_id)? {
+            continue;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO album_items (album_id, photo_id, position, added_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![item.album_id, item.photo_id, item.position, item.added_at],
+        )?;
+    }
+    Ok(())
+}
+
+/// Smart albums use "newest updated_at wins", like albums
+fn merge_smart_album(conn: &Connection, smart_album: &SmartAlbumRecord) -> Result<MergeResult> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT updated_at FROM smart_albums WHERE id = ?1",
+            [&smart_album.id],
+            |row| row.get(0),
+        )
+        .ok();
+
+    let result = match existing {
+        None => MergeResult::Added,
+        Some(local_updated) if smart_album.updated_at > local_updated => MergeResult::Updated,
+        Some(_) => return Ok(MergeResult::Skipped),
+    };
+
+    upsert_smart_album(conn, smart_album)?;
+    Ok(result)
+}
+
+fn upsert_smart_album(conn: &Connection, smart_album: &SmartAlbumRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO smart_albums (id, name, rule, sort, created_at, updated_at, deleted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            rule = excluded.rule,
+            sort = excluded.sort,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            deleted_at = excluded.deleted_at",
+        rusqlite::params![
+            smart_album.id,
+            smart_album.name,
+            smart_album.rule,
+            smart_album.sort,
+            smart_album.created_at,
+            smart_album.updated_at,
+            smart_album.deleted_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Devices are merged field by field: the most recently synced entry wins for
+/// hostname/platform/version, and a revocation from any device is kept.
+fn merge_device(conn: &Connection, device: &DeviceRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO devices (id, hostname, platform, app_version, first_sync_at, last_sync_at, revoked_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+            hostname = CASE WHEN excluded.last_sync_at > last_sync_at THEN excluded.hostname ELSE hostname END,
+            platform = CASE WHEN excluded.last_sync_at > last_sync_at THEN excluded.platform ELSE platform END,
+            app_version = CASE WHEN excluded.last_sync_at > last_sync_at THEN excluded.app_version ELSE app_version END,
+            first_sync_at = MIN(first_sync_at, excluded.first_sync_at),
+            last_sync_at = MAX(last_sync_at, excluded.last_sync_at),
+            revoked_at = COALESCE(revoked_at, excluded.revoked_at)",
+        rusqlite::params![
+            device.id,
+            device.hostname,
+            device.platform,
+            device.app_version,
+            device.first_sync_at,
+            device.last_sync_at,
+            device.revoked_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Replace local photos and memories with exactly the contents of a manifest version.
+/// Unlike `import_manifest`, timestamps are ignored and records missing from `data`
+/// are removed locally. Removed photos are tombstoned, so the restore can't be undone
+/// by a later restore or merge. Callers should snapshot the DB first.
+pub fn restore_manifest(conn: &Connection, data: ManifestData) -> Result<RestoreStats> {
+    let mut stats = RestoreStats::default();
+    let tx = conn.unchecked_transaction()?;
+
+    let photo_ids: HashSet<&str> = data.photos.iter().map(|p| p.id.as_str()).collect();
+    let memory_ids: HashSet<&str> = data.memories.iter().map(|m| m.id.as_str()).collect();
+
+    // Remove photos that didn't exist at that point in time. They are tombstoned
+    // so merges from devices that still have them don't bring them back.
+    let local_photo_ids: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT id FROM photos")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()?
+    };
+    let removed_at = chrono::Utc::now().to_rfc3339();
+    for id in local_photo_ids.into_iter().filter(|id| !photo_ids.contains(id.as_str())) {
+        let tombstone = PhotoTombstone { id, purged_at: removed_at.clone() };
+        if merge_tombstone(&tx, &tombstone)? {
+            stats.photos_removed += 1;
+        }
+    }
+
+    // Purges are never rolled back: their objects are gone from the bucket
+    for tombstone in &data.tombstones {
+        merge_tombstone(&tx, tombstone)?;
+    }
+
+    // Remove memories that didn't exist at that point in time
+    let local_memory_ids: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT id FROM memories")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()?
+    };
+    for id in local_memory_ids.iter().filter(|id| !memory_ids.contains(id.as_str())) {
+        tx.execute("DELETE FROM memories WHERE id = ?1", [id])?;
+        stats.memories_removed += 1;
+    }
+
+    for photo in &data.photos {
+        if is_tombstoned(&tx, &photo.id)? {
+            continue;
+        }
+        let exists: bool = tx
+            .query_row("SELECT 1 FROM photos WHERE id = ?1", [&photo.id], |_| Ok(()))
+            .is_ok();
+        if exists {
+            update_photo(&tx, photo)?;
+            write_trash_state(&tx, photo)?;
+            write_curation(&tx, photo)?;
+        } else {
+            insert_photo(&tx, photo)?;
+        }
+        stats.photos_restored += 1;
+    }
+
+    for memory in &data.memories {
+        let exists: bool = tx
+            .query_row("SELECT 1 FROM memories WHERE id = ?1", [&memory.id], |_| Ok(()))
+            .is_ok();
+        if exists {
+            update_memory(&tx, memory)?;
+        } else {
+            insert_memory(&tx, memory)?;
+        }
+        stats.memories_restored += 1;
+    }
+
+    // Memory media associations are replaced wholesale
+    tx.execute("DELETE FROM memory_media", [])?;
+    for mm in &data.memory_media {
+        if is_tombstoned(&tx, &mm.media_id)? {
+            continue;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO memory_media (memory_id, media_id, display_order) 
+             VALUES (?1, ?2, ?3)",
+            rusqlite::params![mm.memory_id, mm.media_id, mm.display_order],
+        )?;
+    }
+
+    // Albums are replaced like memories
+    let album_ids: HashSet<&str> = data.albums.iter().map(|a| a.id.as_str()).collect();
+    let local_album_ids: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT id FROM albums")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()?
+    };
+    for id in local_album_ids.iter().filter(|id| !album_ids.contains(id.as_str())) {
+        tx.execute("DELETE FROM album_items WHERE album_id = ?1", [id])?;
+        tx.execute("DELETE FROM albums WHERE id = ?1", [id])?;
+        stats.albums_removed += 1;
+    }
+    let items_by_album = group_album_items(&data.album_items);
+    for album in &data.albums {
+        let items = items_by_album.get(album.id.as_str()).map(Vec::as_slice).unwrap_or(&[]);
+        upsert_album(&tx, album, items)?;
+        stats.albums_restored += 1;
+    }
+
+    let smart_album_ids: HashSet<&str> = data.smart_albums.iter().map(|a| a.id.as_str()).collect();
+    let local_smart_album_ids: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT id FROM smart_albums")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<std::result::Result<Vec<_>, _>>()?
+    };
+    for id in local_smart_album_ids.iter().filter(|id| !smart_album_ids.contains(id.as_str())) {
+        tx.execute("DELETE FROM smart_albums WHERE id = ?1", [id])?;
+        stats.smart_albums_removed += 1;
+    }
+    for smart_album in &data.smart_albums {
+        upsert_smart_album(&tx, smart_album)?;
+        stats.smart_albums_restored += 1;
+    }
+
+    // The device registry is never rolled back, so revocations survive a restore
+    for device in &data.devices {
+        merge_device(&tx, device)?;
+    }
+
+    db::set_metadata(&tx, "name", &data.name)?;
+
+    tx.commit()?;
+    Ok(stats)
+}
+
+/// Copy the vault DB to a `snapshots/` directory next to it before a merge,
+/// keeping only the most recent MAX_LOCAL_SNAPSHOTS copies.
+/// Returns None for in-memory databases.
+pub fn snapshot_local_db(conn: &Connection) -> Result<Option<PathBuf>> {
+    let Some(db_path) = conn
+        .path()
+        .map(PathBuf::from)
+        .filter(|p| !p.as_os_str().is_empty())
+    else {
+        return Ok(None);
+    };
+    let Some(vault_dir) = db_path.parent() else {
+        return Ok(None);
+    };
+
+    let snapshot_dir = vault_dir.join("snapshots");
+    std::fs::create_dir_all(&snapshot_dir).context("Failed to create snapshot directory")?;
+
+    let filename = format!(
+        "manifest-{}.db",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    );
+    let snapshot_path = snapshot_dir.join(filename);
+
+    // Attaching without a KEY clause reuses the main DB's SQLCipher key,
+    // so the snapshot is encrypted at rest like the DB itself
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    conn.execute(
+        "ATTACH DATABASE ?1 AS snapshot",
+        [snapshot_path.to_string_lossy().as_ref()],
+    )
+    .context("Failed to create snapshot")?;
+    let exported = conn
+        .query_row("SELECT sqlcipher_export('snapshot')", [], |_| Ok(()))
+        .and_then(|_| {
+            conn.execute_batch(&format!("PRAGMA snapshot.user_version = {};", version))
+        });
+    conn.execute_batch("DETACH DATABASE snapshot;")?;
+    exported.context("Failed to snapshot vault DB")?;
+
+    // Prune oldest snapshots (filenames sort chronologically)
+    let mut snapshots: Vec<PathBuf> = std::fs::read_dir(&snapshot_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("manifest-") && n.ends_with(".db"))
+                .unwrap_or(false)
+        })
+        .collect();
+    snapshots.sort();
+    if snapshots.len() > MAX_LOCAL_SNAPSHOTS {
+        for old in &snapshots[..snapshots.len() - MAX_LOCAL_SNAPSHOTS] {
+            std::fs::remove_file(old).ok();
+        }
+    }
+
+    Ok(Some(snapshot_path))
+}
+
 /// Encrypt manifest data using the vault key
 pub fn encrypt_manifest(data: &ManifestData, key: &[u8; 32]) -> Result<Vec<u8>> {
     let json = serde_json::to_vec(data).context("Failed to serialize manifest")?;
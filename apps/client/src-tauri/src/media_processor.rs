@@ -4,7 +4,11 @@
 //! - Images: WebP at quality 90 (original), quality 70 thumbnails (max 720px)
 //! - Videos: H.265 MP4 at CRF 23, animated WebP thumbnail (320px)
 //! - Audio: Opus at 64kbps
+//!
+//! Vaults with a lossless `OriginalPolicy` store the source file (or a lossless image
+//! encoding) instead, optionally keeping the optimized encoding as a display copy.
 
+use crate::vault::{ArchivalPolicy, OriginalPolicy};
 use anyhow::{Context, Result};
 use std::path::Path;
 use image::GenericImageView;
@@ -80,6 +84,200 @@ pub struct ProcessedMedia {
     pub width: u32,
     /// Media height in pixels
     pub height: u32,
+    /// How `original` was produced: the vault policy, or `Source` when the file was
+    /// stored as is
+    pub policy: OriginalPolicy,
+    /// Optimized encoding kept next to a lossless original
+    pub display: Option<DisplayCopy>,
+}
+
+pub struct DisplayCopy {
+    pub bytes: Vec<u8>,
+    pub extension: String,
+}
+
+/// Lowercase extension of the source file
+fn source_extension(path: &Path, fallback: &str) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Lossless WebP of an 8-bit image. Higher bit depths would be truncated, so they
+/// are not re-encoded.
+fn lossless_webp(img: &image::DynamicImage) -> Option<Vec<u8>> {
+    use image::DynamicImage;
+
+    let (width, height) = img.dimensions();
+    match img {
+        DynamicImage::ImageRgb8(rgb) => {
+            Some(webp::Encoder::from_rgb(rgb.as_raw(), width, height).encode_lossless().to_vec())
+        }
+        DynamicImage::ImageRgba8(_) | DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_) => {
+            let rgba = img.to_rgba8();
+            Some(webp::Encoder::from_rgba(rgba.as_raw(), width, height).encode_lossless().to_vec())
+        }
+        _ => None,
+    }
+}
+
+/// Metadata of a source image that a lossless re-encode has to carry over
+#[derive(Debug, Default)]
+struct ImageMetadata {
+    icc: Option<Vec<u8>>,
+    /// TIFF-structured EXIF, without the JPEG "Exif\0\0" prefix
+    exif: Option<Vec<u8>>,
+    xmp: Option<Vec<u8>>,
+}
+
+impl ImageMetadata {
+    fn is_empty(&self) -> bool {
+        self.icc.is_none() && self.exif.is_none() && self.xmp.is_none()
+    }
+}
+
+/// Read the metadata of a source image. None when the format can't be read here
+/// (e.g. HEIC) or holds metadata WebP has no chunk for (IPTC), so it can't be kept.
+fn read_image_metadata(path: &Path) -> Option<ImageMetadata> {
+    use image::ImageDecoder;
+
+    let mut decoder = image::ImageReader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    if decoder.iptc_metadata().ok()?.is_some() {
+        return None;
+    }
+    let exif = decoder.exif_metadata().ok()?.map(|exif| match exif.strip_prefix(b"Exif\0\0") {
+        Some(tiff) => tiff.to_vec(),
+        None => exif,
+    });
+    Some(ImageMetadata {
+        icc: decoder.icc_profile().ok()?,
+        exif,
+        xmp: decoder.xmp_metadata().ok()?,
+    })
+}
+
+fn push_riff_chunk(out: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(fourcc);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/// Rewrap a simple lossless WebP (a single VP8L chunk) in the extended format with
+/// the source's ICC profile, EXIF and XMP
+fn webp_with_metadata(webp: &[u8], width: u32, height: u32, has_alpha: bool, metadata: &ImageMetadata) -> Option<Vec<u8>> {
+    let image_chunk = webp.get(12..).filter(|chunk| chunk.starts_with(b"VP8L"))?;
+
+    let mut flags = 0u8;
+    if metadata.icc.is_some() {
+        flags |= 0x20;
+    }
+    if has_alpha {
+        flags |= 0x10;
+    }
+    if metadata.exif.is_some() {
+        flags |= 0x08;
+    }
+    if metadata.xmp.is_some() {
+        flags |= 0x04;
+    }
+    let mut vp8x = vec![flags, 0, 0, 0];
+    vp8x.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+
+    // Chunk order is fixed by the container spec: VP8X, ICCP, image, EXIF, XMP
+    let mut body = b"WEBP".to_vec();
+    push_riff_chunk(&mut body, b"VP8X", &vp8x);
+    if let Some(icc) = &metadata.icc {
+        push_riff_chunk(&mut body, b"ICCP", icc);
+    }
+    body.extend_from_slice(image_chunk);
+    if let Some(exif) = &metadata.exif {
+        push_riff_chunk(&mut body, b"EXIF", exif);
+    }
+    if let Some(xmp) = &metadata.xmp {
+        push_riff_chunk(&mut body, b"XMP ", xmp);
+    }
+
+    let mut out = b"RIFF".to_vec();
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&body);
+    Some(out)
+}
+
+/// Lossless JPEG XL via FFmpeg (needs libjxl; unavailable on mobile)
+async fn lossless_jxl(transcoder: &impl Transcoder, path: &Path) -> Option<Vec<u8>> {
+    let output = std::env::temp_dir().join(format!("lossless_{}.jxl", uuid::Uuid::new_v4()));
+    let args = vec![
+        "-i".to_string(),
+        path.to_string_lossy().to_string(),
+        "-c:v".to_string(),
+        "libjxl".to_string(),
+        "-distance".to_string(),
+        "0".to_string(),
+        "-y".to_string(),
+        output.to_string_lossy().to_string(),
+    ];
+
+    let result = transcoder.run_ffmpeg(&args).await;
+    std::fs::remove_file(&output).ok();
+    match result {
+        Ok(bytes) if !bytes.is_empty() => Some(bytes),
+        Ok(_) => None,
+        Err(e) => {
+            log::warn!("[Image] Lossless JPEG XL encoding failed: {}", e);
+            None
+        }
+    }
+}
+
+/// The original to store for an image under a lossless `policy`: the lossless
+/// encoding if it is smaller than the source and keeps its metadata, otherwise the
+/// source bytes
+async fn lossless_image_original(
+    transcoder: &impl Transcoder,
+    img: &image::DynamicImage,
+    path: &Path,
+    policy: OriginalPolicy,
+) -> Result<(Vec<u8>, String, OriginalPolicy)> {
+    let source = std::fs::read(path).context("Failed to read original image")?;
+    let encoded = match (policy, read_image_metadata(path)) {
+        (OriginalPolicy::LosslessWebp, Some(metadata)) => lossless_webp(img)
+            .and_then(|bytes| {
+                if metadata.is_empty() {
+                    return Some(bytes);
+                }
+                let (width, height) = img.dimensions();
+                webp_with_metadata(&bytes, width, height, img.color().has_alpha(), &metadata)
+            })
+            .map(|b| (b, "webp")),
+        // FFmpeg's JPEG XL output doesn't reliably keep EXIF, XMP or ICC
+        (OriginalPolicy::LosslessJxl, Some(metadata)) if metadata.is_empty() => {
+            lossless_jxl(transcoder, path).await.map(|b| (b, "jxl"))
+        }
+        _ => None,
+    };
+
+    match encoded {
+        Some((bytes, extension)) if bytes.len() < source.len() => {
+            log::info!(
+                "[Image] Lossless {}: {} -> {} bytes",
+                extension.to_uppercase(),
+                source.len(),
+                bytes.len()
+            );
+            Ok((bytes, extension.to_string(), policy))
+        }
+        _ => Ok((source, source_extension(path, "jpg"), OriginalPolicy::Source)),
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -298,13 +496,14 @@ fn resize_and_process_frame(img: &image::DynamicImage, max_dim: u32) -> Result<(
     Ok((target_w, target_h, resized.to_rgba8()))
 }
 
-/// Process an image file: create WebP original and thumbnail
+/// Process an image file: create the original (per `archival`) and thumbnail
 /// Uses image crate directly for reliability, with FFmpeg fallback for exotic formats.
 /// IMPORTANT: If FFmpeg doesn't support the format (e.g., HEIC without libheif), we passthrough
 /// the original file to prevent data corruption.
 pub async fn process_image(
     transcoder: &impl Transcoder,
     path: &Path,
+    archival: ArchivalPolicy,
 ) -> Result<ProcessedMedia> {
     use image::GenericImageView;
 
@@ -326,7 +525,7 @@ pub async fn process_image(
     
     // If image crate succeeded, proceed normally
     if let Ok(img) = img_result {
-        return process_image_from_decoded(img, path).await;
+        return process_image_from_decoded(transcoder, img, path, archival).await;
     }
 
     // Image crate failed - decide whether to try FFmpeg or fallback to passthrough
@@ -388,7 +587,7 @@ pub async fn process_image(
                     Ok(img) => {
                         std::fs::remove_file(&temp_png).ok();
                         log::info!("[FFmpeg Fallback] Successfully converted {} to PNG", ext.to_uppercase());
-                        return process_image_from_decoded(img, path).await;
+                        return process_image_from_decoded(transcoder, img, path, archival).await;
                     }
                     Err(e) => {
                         log::warn!("[FFmpeg Fallback] Converted PNG is unreadable: {}", e);
@@ -433,20 +632,28 @@ pub async fn process_image(
         preview: None,
         width: 0,   // Unknown dimensions
         height: 0,
+        policy: OriginalPolicy::Source,
+        display: None,
     })
 }
 
-/// Helper: Process an already-decoded image to WebP
+/// Helper: Process an already-decoded image to WebP (or a lossless original per `archival`)
 async fn process_image_from_decoded(
+    transcoder: &impl Transcoder,
     img: image::DynamicImage,
     path: &Path,
+    archival: ArchivalPolicy,
 ) -> Result<ProcessedMedia> {
     use image::GenericImageView;
 
     let (width, height) = img.dimensions();
 
-    // Create original WebP (Q90) or Passthrough if inflated
-    let (original_buf, original_extension) = {
+    // Optimized WebP (Q90), or None if it wouldn't save enough over the source or
+    // isn't needed
+    let wants_optimized = archival.original == OriginalPolicy::Optimized || archival.display_copy;
+    let optimized = if !wants_optimized {
+        None
+    } else {
         let rgb = img.to_rgb8();
         let encoder = webp::Encoder::from_rgb(rgb.as_raw(), width, height);
         let webp_memory = encoder.encode(90.0);
@@ -464,13 +671,7 @@ async fn process_image_from_decoded(
                 input_size,
                 webp_bytes.len()
             );
-            let input_bytes = std::fs::read(path).context("Failed to read original image for passthrough")?;
-            let ext = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("jpg")
-                .to_lowercase();
-            (input_bytes, ext)
+            None
         } else {
             log::info!(
                 "[Image] Transcoded: {:.1}% compression ({} -> {} bytes)",
@@ -478,7 +679,27 @@ async fn process_image_from_decoded(
                 input_size,
                 webp_bytes.len()
             );
-            (webp_bytes, "webp".to_string())
+            Some(webp_bytes)
+        }
+    };
+
+    let (original_buf, original_extension, policy, display) = match (archival.original, optimized) {
+        (OriginalPolicy::Optimized, Some(webp_bytes)) => {
+            (webp_bytes, "webp".to_string(), OriginalPolicy::Optimized, None)
+        }
+        (OriginalPolicy::Optimized, None) => {
+            let input_bytes = std::fs::read(path).context("Failed to read original image for passthrough")?;
+            (input_bytes, source_extension(path, "jpg"), OriginalPolicy::Source, None)
+        }
+        (lossless, optimized) => {
+            let (bytes, extension, policy) = lossless_image_original(transcoder, &img, path, lossless).await?;
+            let display = optimized
+                .filter(|_| archival.display_copy)
+                .map(|bytes| DisplayCopy {
+                    bytes,
+                    extension: "webp".to_string(),
+                });
+            (bytes, extension, policy, display)
         }
     };
 
@@ -498,6 +719,8 @@ async fn process_image_from_decoded(
         preview: None,
         width,
         height,
+        policy,
+        display,
     })
 }
 
@@ -543,7 +766,7 @@ fn create_animated_thumbnail(frames: Vec<Vec<u8>>) -> Result<Vec<u8>> {
     Ok(webp_data.to_vec())
 }
 
-/// Process a video file: create H.265 MP4 and animated WebP thumbnail
+/// Process a video file: create H.265 MP4 (unless `archival` keeps the source) and animated WebP thumbnail
 /// Mobile: Skip transcode, use provided frames for thumbnail
 /// Desktop: Transcode (Fast), use provided frames OR sidecar for thumbnail
 pub async fn process_video(
@@ -551,6 +774,7 @@ pub async fn process_video(
     path: &Path,
     output_path: &Path,
     pre_generated_frames: Option<Vec<Vec<u8>>>,
+    archival: ArchivalPolicy,
 ) -> Result<ProcessedMedia> {
     
     // 1. Generate Thumbnail (Platform agnostic if frames provided)
@@ -583,7 +807,7 @@ pub async fn process_video(
 
     // 2. Transcode Video (Platform Specific) with passthrough heuristic
     #[cfg(desktop)]
-    let (original, ext) = {
+    let (original, ext, policy, display) = {
         let input_ext = path
             .extension()
             .and_then(|e| e.to_str())
@@ -594,40 +818,61 @@ pub async fn process_video(
         let original_bytes = std::fs::read(path).context("Failed to read video file")?;
         let original_size = original_bytes.len() as u64;
 
-        // Transcode to H.265
-        let transcoded = transcode_video_h265(transcoder, path, output_path).await?;
-        let transcoded_size = transcoded.len() as u64;
-
-        // Apply passthrough heuristic
-        if let Some(reason) = should_passthrough(original_size, transcoded_size) {
-            log::info!(
-                "[Video] Passthrough: {} (original {} bytes, transcoded {} bytes)",
-                reason,
-                original_size,
-                transcoded_size
-            );
-            (original_bytes, input_ext)
+        // Lossless policies keep the source; the transcode is only needed for a display copy
+        let keep_source = archival.original != OriginalPolicy::Optimized;
+        if keep_source && !archival.display_copy {
+            log::info!("[Video] Keeping source file ({:?} policy)", archival.original);
+            (original_bytes, input_ext, OriginalPolicy::Source, None)
         } else {
-            log::info!(
-                "[Video] Transcoded: {:.1}% compression ({} -> {} bytes)",
-                (transcoded_size as f64 / original_size as f64) * 100.0,
-                original_size,
-                transcoded_size
-            );
-            (transcoded, "mp4".to_string())
+            // Transcode to H.265
+            let transcoded = transcode_video_h265(transcoder, path, output_path).await?;
+            let transcoded_size = transcoded.len() as u64;
+
+            // Apply passthrough heuristic
+            let transcoded = if let Some(reason) = should_passthrough(original_size, transcoded_size) {
+                log::info!(
+                    "[Video] Passthrough: {} (original {} bytes, transcoded {} bytes)",
+                    reason,
+                    original_size,
+                    transcoded_size
+                );
+                None
+            } else {
+                log::info!(
+                    "[Video] Transcoded: {:.1}% compression ({} -> {} bytes)",
+                    (transcoded_size as f64 / original_size as f64) * 100.0,
+                    original_size,
+                    transcoded_size
+                );
+                Some(transcoded)
+            };
+
+            match (keep_source, transcoded) {
+                (false, Some(transcoded)) => (transcoded, "mp4".to_string(), OriginalPolicy::Optimized, None),
+                (false, None) => (original_bytes, input_ext, OriginalPolicy::Source, None),
+                (true, transcoded) => {
+                    let display = transcoded.map(|bytes| DisplayCopy {
+                        bytes,
+                        extension: "mp4".to_string(),
+                    });
+                    (original_bytes, input_ext, OriginalPolicy::Source, display)
+                }
+            }
         }
     };
 
     #[cfg(not(desktop))] // mobile
-    let (original, ext) = {
+    let (original, ext, policy, display) = {
         log::info!("Mobile: Skipping video transcoding, using original file");
+        // Without a transcoder the source is stored under any policy
+        let _ = archival;
         let bytes = std::fs::read(path).context("Failed to read video file")?;
         // Detect original extension
         let ext = path.extension()
             .and_then(|e| e.to_str())
             .unwrap_or("mp4")
             .to_string();
-        (bytes, ext)
+        (bytes, ext, OriginalPolicy::Source, None)
     };
 
 
@@ -645,6 +890,8 @@ pub async fn process_video(
         preview: None,
         width, // Todo: Improve metadata extraction on Mobile without FFmpeg
         height,
+        policy,
+        display,
     })
 }
 
@@ -692,10 +939,12 @@ async fn generate_thumbnail_ffmpeg(
 /// Process an audio file: convert to Opus (with format-aware passthrough)
 /// Already compressed formats (.ogg, .mp3, .m4a, .aac) skip transcoding entirely.
 /// Uncompressed formats (.wav, .flac) are transcoded but with passthrough fallback if savings are minimal.
+/// Lossless `archival` policies always keep the source.
 pub async fn process_audio(
     transcoder: &impl Transcoder,
     path: &Path,
     output_path: &Path,
+    archival: ArchivalPolicy,
 ) -> Result<ProcessedMedia> {
     let input_ext = path
         .extension()
@@ -704,15 +953,19 @@ pub async fn process_audio(
         .to_lowercase();
 
     #[cfg(desktop)]
-    let (original, ext) = {
-        // Check if format is already compressed - skip transcoding entirely
-        if should_skip_audio_transcode(&input_ext) {
+    let (original, ext, policy) = {
+        if archival.original != OriginalPolicy::Optimized {
+            log::info!("[Audio] Keeping source file ({:?} policy)", archival.original);
+            let bytes = std::fs::read(path).context("Failed to read audio file")?;
+            (bytes, input_ext.clone(), OriginalPolicy::Source)
+        } else if should_skip_audio_transcode(&input_ext) {
+            // Format is already compressed - skip transcoding entirely
             log::info!(
                 "[Audio] Passthrough: {} is already compressed, skipping transcode",
                 input_ext
             );
             let bytes = std::fs::read(path).context("Failed to read audio file")?;
-            (bytes, input_ext.clone())
+            (bytes, input_ext.clone(), OriginalPolicy::Source)
         } else {
             // Uncompressed format - transcode but apply passthrough heuristic
             let original_bytes = std::fs::read(path).context("Failed to read audio file")?;
@@ -729,7 +982,7 @@ pub async fn process_audio(
                     original_size,
                     transcoded_size
                 );
-                (original_bytes, input_ext.clone())
+                (original_bytes, input_ext.clone(), OriginalPolicy::Source)
             } else {
                 log::info!(
                     "[Audio] Transcoded: {:.1}% compression ({} -> {} bytes)",
@@ -737,16 +990,17 @@ pub async fn process_audio(
                     original_size,
                     transcoded_size
                 );
-                (transcoded, "opus".to_string())
+                (transcoded, "opus".to_string(), OriginalPolicy::Optimized)
             }
         }
     };
 
     #[cfg(not(desktop))]
-    let (original, ext) = {
+    let (original, ext, policy) = {
         // Mobile: always passthrough (no FFmpeg available)
+        let _ = archival;
         let bytes = std::fs::read(path).context("Failed to read audio file")?;
-        (bytes, input_ext)
+        (bytes, input_ext, OriginalPolicy::Source)
     };
 
     Ok(ProcessedMedia {
@@ -756,6 +1010,8 @@ pub async fn process_audio(
         preview: None,
         width: 0,
         height: 0,
+        policy,
+        display: None,
     })
 }
 
@@ -852,4 +1108,43 @@ mod tests {
         assert_eq!(w, 400);
         assert_eq!(h, 300);
     }
+
+    #[test]
+    fn test_lossless_webp_round_trip() {
+        let rgb = image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([x as u8 * 4, y as u8 * 5, (x ^ y) as u8]));
+        let img = image::DynamicImage::ImageRgb8(rgb.clone());
+
+        let encoded = lossless_webp(&img).unwrap();
+        let decoded = image::load_from_memory(&encoded).unwrap().to_rgb8();
+        assert_eq!(decoded.as_raw(), rgb.as_raw());
+
+        // 16-bit images would lose precision
+        let deep = image::DynamicImage::ImageRgb16(image::ImageBuffer::new(4, 4));
+        assert!(lossless_webp(&deep).is_none());
+    }
+
+    #[test]
+    fn test_lossless_webp_keeps_metadata() {
+        use image::ImageDecoder;
+
+        let rgba = image::RgbaImage::from_fn(5, 3, |x, y| image::Rgba([x as u8 * 40, y as u8 * 80, 7, 200]));
+        let img = image::DynamicImage::ImageRgba8(rgba.clone());
+        let metadata = ImageMetadata {
+            icc: None,
+            exif: Some(b"MM\0\x2a\0\0\0\x08\0\0".to_vec()),
+            xmp: Some(b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>".to_vec()),
+        };
+
+        let simple = lossless_webp(&img).unwrap();
+        let extended = webp_with_metadata(&simple, 5, 3, true, &metadata).unwrap();
+        assert_eq!(&extended[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(extended[4..8].try_into().unwrap()) as usize, extended.len() - 8);
+        assert_eq!(&extended[12..16], b"VP8X");
+
+        let mut decoder = image::codecs::webp::WebPDecoder::new(std::io::Cursor::new(&extended)).unwrap();
+        assert_eq!(decoder.exif_metadata().unwrap(), metadata.exif);
+        assert_eq!(decoder.xmp_metadata().unwrap(), metadata.xmp);
+        let decoded = image::load_from_memory(&extended).unwrap().to_rgba8();
+        assert_eq!(decoded.as_raw(), rgba.as_raw());
+    }
 }
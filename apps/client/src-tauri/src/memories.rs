@@ -1,4 +1,5 @@
 use crate::AppState;
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
@@ -28,7 +29,10 @@ pub async fn create_memory(
 ) -> Result<Memory, String> {
     let db_guard = state.db.lock().await;
     let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    insert_memory(conn, payload)
+}
 
+pub(crate) fn insert_memory(conn: &Connection, payload: CreateMemoryPayload) -> Result<Memory, String> {
     let id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().to_rfc3339();
 
@@ -70,6 +74,25 @@ pub async fn create_memory(
     })
 }
 
+/// Add media at a given position, e.g. once an imported photo finished uploading
+pub(crate) fn insert_media_at(conn: &Connection, id: &str, media_id: &str, display_order: i64) -> Result<(), String> {
+    let updated = conn
+        .execute(
+            "UPDATE memories SET updated_at = ?1 WHERE id = ?2",
+            rusqlite::params![chrono::Utc::now().to_rfc3339(), id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Memory not found".to_string());
+    }
+    conn.execute(
+        "INSERT OR IGNORE INTO memory_media (memory_id, media_id, display_order) VALUES (?1, ?2, ?3)",
+        rusqlite::params![id, media_id, display_order],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_memories(state: State<'_, AppState>) -> Result<Vec<Memory>, String> {
     let db_guard = state.db.lock().await;
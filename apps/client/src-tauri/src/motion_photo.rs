@@ -0,0 +1,335 @@
+//! Live Photo and Motion Photo detection
+//!
+//! An Apple Live Photo is a still (HEIC/JPEG) plus a short companion MOV. Both
+//! carry the same ContentIdentifier (Apple MakerNote tag / QuickTime metadata) and
+//! usually share a basename. Google Motion Photos (`MVIMG_*.jpg`, `PXL_*.MP.jpg`)
+//! instead append an MP4 to the JPEG, located through the XMP metadata.
+//!
+//! Either way the video is uploaded as a motion component of the still rather
+//! than as a separate photo.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const STILL_EXTENSIONS: &[&str] = &["heic", "heif", "jpg", "jpeg"];
+const COMPANION_EXTENSIONS: &[&str] = &["mov", "mp4"];
+
+/// Live Photo videos are a few seconds long; larger videos are never companions
+const MAX_COMPANION_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Where the ContentIdentifier of a still is searched (the Exif block is near the start)
+const STILL_HEADER_BYTES: u64 = 1024 * 1024;
+
+/// QuickTime metadata key that precedes the ContentIdentifier in a MOV
+const QUICKTIME_CONTENT_ID_KEY: &[u8] = b"com.apple.quicktime.content.identifier";
+
+/// Start of an Apple MakerNote, followed by a version and the byte order. Offsets
+/// in its IFD are relative to this header.
+const APPLE_MAKERNOTE_HEADER: &[u8] = b"Apple iOS\0";
+
+/// MakerNote tag holding the ContentIdentifier of a Live Photo still
+const MAKERNOTE_CONTENT_ID_TAG: u16 = 0x0011;
+
+/// Motion component of a photo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum MotionSource {
+    /// Separate video file (Apple Live Photo)
+    Companion { path: PathBuf },
+    /// MP4 appended to the still (Google Motion Photo)
+    Embedded { offset: u64, length: u64 },
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Read the first `limit` bytes of a file
+fn read_head(path: &Path, limit: u64) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path).ok()?.take(limit).read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// First ASCII UUID (`8-4-4-4-12` hex digits) in `bytes`
+fn find_uuid(bytes: &[u8]) -> Option<String> {
+    const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+    const LEN: usize = 36;
+
+    bytes.windows(LEN).find_map(|window| {
+        let mut pos = 0;
+        for (i, group) in GROUPS.iter().enumerate() {
+            if !window[pos..pos + group].iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            pos += group;
+            if i < GROUPS.len() - 1 {
+                if window[pos] != b'-' {
+                    return None;
+                }
+                pos += 1;
+            }
+        }
+        Some(String::from_utf8_lossy(window).to_uppercase())
+    })
+}
+
+/// ContentIdentifier tag of the Apple MakerNote in `bytes`
+fn makernote_content_identifier(bytes: &[u8]) -> Option<String> {
+    let start = bytes
+        .windows(APPLE_MAKERNOTE_HEADER.len())
+        .position(|w| w == APPLE_MAKERNOTE_HEADER)?;
+    let note = &bytes[start..];
+    let big_endian = match note.get(12..14)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| {
+        let b: [u8; 2] = note.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) })
+    };
+    let u32_at = |pos: usize| {
+        let b: [u8; 4] = note.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+    };
+
+    let entries = u16_at(14)? as usize;
+    (0..entries).find_map(|i| {
+        let entry = 16 + i * 12;
+        if u16_at(entry)? != MAKERNOTE_CONTENT_ID_TAG {
+            return None;
+        }
+        // An ASCII UUID never fits in the entry itself, so the value is at an offset
+        let len = u32_at(entry + 4)? as usize;
+        let offset = u32_at(entry + 8)? as usize;
+        find_uuid(note.get(offset..offset.checked_add(len)?)?)
+    })
+}
+
+/// Apple ContentIdentifier of a Live Photo still or video, if present
+fn content_identifier(path: &Path) -> Option<String> {
+    if COMPANION_EXTENSIONS.contains(&extension(path).as_str()) {
+        // The metadata atom may sit at the end of the file, so read it whole
+        let bytes = read_head(path, MAX_COMPANION_BYTES)?;
+        let key = bytes
+            .windows(QUICKTIME_CONTENT_ID_KEY.len())
+            .position(|w| w == QUICKTIME_CONTENT_ID_KEY)?;
+        find_uuid(&bytes[key..])
+    } else {
+        makernote_content_identifier(&read_head(path, STILL_HEADER_BYTES)?)
+    }
+}
+
+/// Match Live Photo stills with their companion videos. Returns still -> video.
+/// Pairs share a ContentIdentifier, or else a folder and basename (unless their
+/// identifiers differ).
+pub fn pair_live_photos(paths: &[PathBuf]) -> HashMap<PathBuf, PathBuf> {
+    let videos: Vec<&PathBuf> = paths
+        .iter()
+        .filter(|p| COMPANION_EXTENSIONS.contains(&extension(p).as_str()))
+        .filter(|p| std::fs::metadata(p).map(|m| m.len() <= MAX_COMPANION_BYTES).unwrap_or(false))
+        .collect();
+    if videos.is_empty() {
+        return HashMap::new();
+    }
+    let stills: Vec<&PathBuf> = paths
+        .iter()
+        .filter(|p| STILL_EXTENSIONS.contains(&extension(p).as_str()))
+        .collect();
+
+    let video_ids: HashMap<&PathBuf, String> = videos
+        .iter()
+        .filter_map(|v| content_identifier(v).map(|id| (*v, id)))
+        .collect();
+    // Stills are only read when some video has an identifier to match
+    let still_ids: HashMap<&PathBuf, String> = if video_ids.is_empty() {
+        HashMap::new()
+    } else {
+        stills
+            .iter()
+            .filter_map(|s| content_identifier(s).map(|id| (*s, id)))
+            .collect()
+    };
+
+    let stem_key = |p: &Path| {
+        (
+            p.parent().map(Path::to_path_buf),
+            p.file_stem().map(|s| s.to_string_lossy().to_lowercase()),
+        )
+    };
+
+    let mut pairs = HashMap::new();
+    for video in videos {
+        let video_id = video_ids.get(video);
+        let by_id = video_id.and_then(|id| {
+            stills
+                .iter()
+                .find(|s| still_ids.get(*s) == Some(id) && !pairs.contains_key(**s))
+        });
+        let by_name = || {
+            stills.iter().find(|s| {
+                stem_key(s) == stem_key(video)
+                    && !pairs.contains_key(**s)
+                    && match (still_ids.get(*s), video_id) {
+                        (Some(a), Some(b)) => a == b,
+                        _ => true,
+                    }
+            })
+        };
+        if let Some(still) = by_id.or_else(by_name) {
+            pairs.insert((*still).clone(), video.clone());
+        }
+    }
+    pairs
+}
+
+/// Parse a numeric XMP attribute value, e.g. `MicroVideoOffset="1234"`
+fn xmp_number(xmp: &str, attribute: &str) -> Option<u64> {
+    let start = xmp.find(&format!("{}=\"", attribute))? + attribute.len() + 2;
+    let end = start + xmp[start..].find('"')?;
+    xmp[start..end].trim().parse().ok()
+}
+
+/// Location of the MP4 embedded in a Google Motion Photo
+pub fn embedded_video(path: &Path) -> Option<MotionSource> {
+    if !matches!(extension(path).as_str(), "jpg" | "jpeg") {
+        return None;
+    }
+    let len = std::fs::metadata(path).ok()?.len();
+    let head = read_head(path, STILL_HEADER_BYTES)?;
+    let xmp = String::from_utf8_lossy(&head);
+
+    // Both forms give the video's distance from the end of the file
+    let from_end = xmp_number(&xmp, "GCamera:MicroVideoOffset").or_else(|| {
+        let item = xmp.find("Item:Semantic=\"MotionPhoto\"")?;
+        // The length attribute belongs to the same Container:Item element
+        let element_start = xmp[..item].rfind('<')?;
+        let element_end = item + xmp[item..].find('>')?;
+        xmp_number(&xmp[element_start..element_end], "Item:Length")
+    })?;
+    if from_end == 0 || from_end >= len {
+        return None;
+    }
+
+    // Check the trailer really starts with an MP4 `ftyp` box
+    let offset = len - from_end;
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut header = [0u8; 8];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut header).ok()?;
+    (&header[4..] == b"ftyp").then_some(MotionSource::Embedded {
+        offset,
+        length: from_end,
+    })
+}
+
+/// Bytes and file extension of the motion component of the photo at `path`
+pub fn read_motion(path: &Path, source: &MotionSource) -> Result<(Vec<u8>, String)> {
+    match source {
+        MotionSource::Companion { path: video } => {
+            let bytes = std::fs::read(video).context("Failed to read Live Photo video")?;
+            Ok((bytes, extension(video)))
+        }
+        MotionSource::Embedded { offset, length } => {
+            let mut file = std::fs::File::open(path).context("Failed to open motion photo")?;
+            file.seek(SeekFrom::Start(*offset))?;
+            let mut bytes = Vec::with_capacity(*length as usize);
+            file.take(*length).read_to_end(&mut bytes)?;
+            Ok((bytes, "mp4".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("boreal-motion-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Big-endian Apple MakerNote with a ContentIdentifier tag
+    fn apple_makernote(id: &str) -> Vec<u8> {
+        let mut note = b"Apple iOS\0\0\x01MM".to_vec();
+        note.extend(1u16.to_be_bytes());
+        note.extend(MAKERNOTE_CONTENT_ID_TAG.to_be_bytes());
+        note.extend(2u16.to_be_bytes()); // ASCII
+        note.extend(37u32.to_be_bytes());
+        note.extend(32u32.to_be_bytes()); // value offset
+        note.extend(0u32.to_be_bytes()); // no next IFD
+        note.extend(id.as_bytes());
+        note.push(0);
+        note
+    }
+
+    #[test]
+    fn test_makernote_content_identifier() {
+        let id = "1F2E3D4C-5B6A-4978-8695-A4B3C2D1E0F9";
+        // Other UUIDs, e.g. an XMP DocumentID, come before the MakerNote
+        let mut exif = b"xmp.did:0A1B2C3D-4E5F-4A6B-8C7D-9E0F1A2B3C4D ".to_vec();
+        exif.extend(apple_makernote(id));
+        assert_eq!(makernote_content_identifier(&exif).as_deref(), Some(id));
+        assert_eq!(makernote_content_identifier(b"xmp.did:0A1B2C3D-4E5F-4A6B-8C7D-9E0F1A2B3C4D"), None);
+    }
+
+    #[test]
+    fn test_pair_live_photos() {
+        let dir = temp_dir();
+        let id = "1F2E3D4C-5B6A-4978-8695-A4B3C2D1E0F9";
+        let mut still = b"exif ".to_vec();
+        still.extend(apple_makernote(id));
+        let files = [
+            ("IMG_0001.HEIC", still),
+            ("renamed.MOV", format!("moov com.apple.quicktime.content.identifier data {}", id).into_bytes()),
+            ("IMG_0002.JPG", b"jpeg".to_vec()),
+            ("IMG_0002.MOV", b"moov".to_vec()),
+            ("IMG_0003.JPG", b"jpeg".to_vec()),
+        ];
+        let paths: Vec<PathBuf> = files
+            .iter()
+            .map(|(name, body)| {
+                let path = dir.join(name);
+                std::fs::write(&path, body).unwrap();
+                path
+            })
+            .collect();
+
+        let pairs = pair_live_photos(&paths);
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[&paths[0]], paths[1]);
+        assert_eq!(pairs[&paths[2]], paths[3]);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_embedded_video() {
+        let dir = temp_dir();
+        let path = dir.join("PXL_20240101_000000000.MP.jpg");
+        let video = b"\0\0\0\x18ftypmp42 video payload".to_vec();
+        let mut bytes = b"\xFF\xD8 ".to_vec();
+        bytes.extend(
+            format!(
+                "<Container:Item Item:Mime=\"video/mp4\" Item:Semantic=\"MotionPhoto\" Item:Length=\"{}\"/>",
+                video.len()
+            )
+            .as_bytes(),
+        );
+        bytes.extend(b" \xFF\xD9");
+        bytes.extend(&video);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let source = embedded_video(&path).unwrap();
+        assert_eq!(read_motion(&path, &source).unwrap(), (video, "mp4".to_string()));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
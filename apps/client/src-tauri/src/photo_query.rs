@@ -0,0 +1,654 @@
+//! Paginated, filterable photo queries across vaults
+//!
+//! Uses keyset (cursor) pagination: each page returns an opaque cursor that
+//! encodes the sort key, vault ID and photo ID of its last item. Every vault DB
+//! is queried for at most `limit` rows after the cursor, and the per-vault
+//! results are merged, so pages stay cheap no matter how deep the user scrolls.
+
+use crate::PhotoWithVault;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine as _};
+use rusqlite::types::{ToSqlOutput, Value};
+use rusqlite::{Connection, ToSql};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use tauri::{AppHandle, Manager};
+
+/// Page size used when the caller doesn't specify one
+const DEFAULT_LIMIT: u32 = 200;
+
+/// Upper bound on page size to keep IPC payloads small
+const MAX_LIMIT: u32 = 1000;
+
+/// Date used for sorting and date filters: capture date, falling back to upload date
+pub const DISPLAY_DATE_SQL: &str = "COALESCE(captured_at, created_at, '')";
+
+/// Which timestamp a date range applies to
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateField {
+    /// `captured_at`, falling back to `created_at` when EXIF had no date
+    #[default]
+    Captured,
+    /// `created_at` (when the photo was added to the vault)
+    Created,
+}
+
+/// Geographic bounding box. `west > east` means the box crosses the antimeridian.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoBounds {
+    pub north: f64,
+    pub south: f64,
+    pub east: f64,
+    pub west: f64,
+}
+
+/// Filters shared by listing and aggregation queries. All fields are optional
+/// and combined with AND.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhotoFilter {
+    pub vault_ids: Option<Vec<String>>,
+    pub date_field: DateField,
+    /// Inclusive lower bound (RFC3339 or YYYY-MM-DD)
+    pub date_from: Option<String>,
+    /// Exclusive upper bound (RFC3339 or YYYY-MM-DD)
+    pub date_to: Option<String>,
+    pub media_types: Option<Vec<String>>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub lens_model: Option<String>,
+    pub iso_min: Option<i32>,
+    pub iso_max: Option<i32>,
+    pub f_number_min: Option<f64>,
+    pub f_number_max: Option<f64>,
+    pub bounds: Option<GeoBounds>,
+    pub has_gps: Option<bool>,
+    pub favorite: Option<bool>,
+    /// Minimum star rating (inclusive)
+    pub rating_min: Option<u8>,
+    /// Any of these color labels
+    pub color_labels: Option<Vec<String>>,
+    /// All of these tags
+    pub tags: Option<Vec<String>>,
+}
+
+/// Sort order for photo listings
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PhotoSort {
+    #[default]
+    DateDesc,
+    DateAsc,
+    AddedDesc,
+    AddedAsc,
+    FilenameAsc,
+    FilenameDesc,
+    SizeDesc,
+    SizeAsc,
+}
+
+impl PhotoSort {
+    fn key_sql(self) -> &'static str {
+        match self {
+            PhotoSort::DateDesc | PhotoSort::DateAsc => DISPLAY_DATE_SQL,
+            PhotoSort::AddedDesc | PhotoSort::AddedAsc => "COALESCE(created_at, '')",
+            PhotoSort::FilenameAsc | PhotoSort::FilenameDesc => "filename",
+            PhotoSort::SizeDesc | PhotoSort::SizeAsc => "COALESCE(size_bytes, 0)",
+        }
+    }
+
+    fn is_desc(self) -> bool {
+        matches!(
+            self,
+            PhotoSort::DateDesc | PhotoSort::AddedDesc | PhotoSort::FilenameDesc | PhotoSort::SizeDesc
+        )
+    }
+}
+
+/// A page request
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PhotoQuery {
+    #[serde(flatten)]
+    pub filter: PhotoFilter,
+    pub sort: PhotoSort,
+    /// Cursor returned by the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PhotoPage {
+    pub items: Vec<PhotoWithVault>,
+    /// Pass back as `cursor` to fetch the next page; None on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Value of the sort column (text for dates/filenames, integer for sizes)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SortValue {
+    Int(i64),
+    Text(String),
+}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (SortValue::Int(a), SortValue::Int(b)) => a.partial_cmp(b),
+            (SortValue::Text(a), SortValue::Text(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl ToSql for SortValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        match self {
+            SortValue::Int(v) => v.to_sql(),
+            SortValue::Text(v) => v.to_sql(),
+        }
+    }
+}
+
+impl SortValue {
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::Integer(v) => SortValue::Int(v),
+            Value::Real(v) => SortValue::Int(v as i64),
+            Value::Text(v) => SortValue::Text(v),
+            Value::Null | Value::Blob(_) => SortValue::Text(String::new()),
+        }
+    }
+}
+
+/// Position of the last item of a page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    pub key: SortValue,
+    pub vault_id: String,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        BASE64_URL.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(s: &str) -> Result<Self, String> {
+        let bytes = BASE64_URL
+            .decode(s)
+            .map_err(|e| format!("Invalid cursor: {}", e))?;
+        serde_json::from_slice(&bytes).map_err(|e| format!("Invalid cursor: {}", e))
+    }
+}
+
+/// SQL WHERE clauses and their parameters, built with `?` placeholders
+#[derive(Default)]
+pub struct SqlFilter {
+    pub clauses: Vec<String>,
+    pub params: Vec<Box<dyn ToSql>>,
+}
+
+impl SqlFilter {
+    pub fn push(&mut self, clause: impl Into<String>, params: Vec<Box<dyn ToSql>>) {
+        self.clauses.push(clause.into());
+        self.params.extend(params);
+    }
+
+    /// `WHERE a AND b ...`, or an empty string without clauses
+    pub fn where_sql(&self) -> String {
+        if self.clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", self.clauses.join(" AND "))
+        }
+    }
+
+    pub fn param_refs(&self) -> Vec<&dyn ToSql> {
+        self.params.iter().map(|p| p.as_ref()).collect()
+    }
+}
+
+/// Build the WHERE clauses for a filter (vault_ids is applied by the caller)
+pub fn build_filter(filter: &PhotoFilter) -> SqlFilter {
+    let mut sql = SqlFilter::default();
+
+    // Trashed photos are only listed by the trash
+    sql.push("deleted_at IS NULL", vec![]);
+
+    let date_sql = match filter.date_field {
+        DateField::Captured => DISPLAY_DATE_SQL,
+        DateField::Created => "COALESCE(created_at, '')",
+    };
+    if let Some(from) = &filter.date_from {
+        sql.push(format!("{} >= ?", date_sql), vec![Box::new(from.clone())]);
+    }
+    if let Some(to) = &filter.date_to {
+        sql.push(format!("{} < ?", date_sql), vec![Box::new(to.clone())]);
+    }
+
+    if let Some(media_types) = filter.media_types.as_ref().filter(|m| !m.is_empty()) {
+        let placeholders = vec!["?"; media_types.len()].join(", ");
+        sql.push(
+            format!("media_type IN ({})", placeholders),
+            media_types
+                .iter()
+                .map(|m| Box::new(m.clone()) as Box<dyn ToSql>)
+                .collect(),
+        );
+    }
+
+    if let Some(make) = &filter.make {
+        sql.push("make = ? COLLATE NOCASE", vec![Box::new(make.clone())]);
+    }
+    if let Some(model) = &filter.model {
+        sql.push("model = ? COLLATE NOCASE", vec![Box::new(model.clone())]);
+    }
+    if let Some(lens) = &filter.lens_model {
+        sql.push("lens_model = ? COLLATE NOCASE", vec![Box::new(lens.clone())]);
+    }
+
+    if let Some(min) = filter.iso_min {
+        sql.push("iso >= ?", vec![Box::new(min)]);
+    }
+    if let Some(max) = filter.iso_max {
+        sql.push("iso <= ?", vec![Box::new(max)]);
+    }
+    if let Some(min) = filter.f_number_min {
+        sql.push("f_number >= ?", vec![Box::new(min)]);
+    }
+    if let Some(max) = filter.f_number_max {
+        sql.push("f_number <= ?", vec![Box::new(max)]);
+    }
+
+    if let Some(bounds) = &filter.bounds {
+        sql.push(
+            "latitude BETWEEN ? AND ?",
+            vec![Box::new(bounds.south), Box::new(bounds.north)],
+        );
+        if bounds.west <= bounds.east {
+            sql.push(
+                "longitude BETWEEN ? AND ?",
+                vec![Box::new(bounds.west), Box::new(bounds.east)],
+            );
+        } else {
+            // Box crosses the antimeridian
+            sql.push(
+                "(longitude >= ? OR longitude <= ?)",
+                vec![Box::new(bounds.west), Box::new(bounds.east)],
+            );
+        }
+    }
+
+    match filter.has_gps {
+        Some(true) => sql.push("latitude IS NOT NULL AND longitude IS NOT NULL", vec![]),
+        Some(false) => sql.push("(latitude IS NULL OR longitude IS NULL)", vec![]),
+        None => {}
+    }
+
+    if let Some(favorite) = filter.favorite {
+        sql.push("favorite = ?", vec![Box::new(favorite)]);
+    }
+    if let Some(min) = filter.rating_min.filter(|r| *r > 0) {
+        sql.push("rating >= ?", vec![Box::new(min)]);
+    }
+    if let Some(labels) = filter.color_labels.as_ref().filter(|l| !l.is_empty()) {
+        let placeholders = vec!["?"; labels.len()].join(", ");
+        sql.push(
+            format!("color_label IN ({})", placeholders),
+            labels
+                .iter()
+                .map(|l| Box::new(l.clone()) as Box<dyn ToSql>)
+                .collect(),
+        );
+    }
+    let tags: Vec<String> = filter
+        .tags
+        .iter()
+        .flatten()
+        .filter_map(|t| crate::curation::normalize_tag(t))
+        .collect();
+    if !tags.is_empty() {
+        let placeholders = vec!["?"; tags.len()].join(", ");
+        let mut params: Vec<Box<dyn ToSql>> = tags
+            .iter()
+            .map(|t| Box::new(t.clone()) as Box<dyn ToSql>)
+            .collect();
+        params.push(Box::new(tags.len() as i64));
+        sql.push(
+            format!(
+                "id IN (SELECT photo_id FROM photo_tags WHERE tag IN ({}) \
+                 GROUP BY photo_id HAVING COUNT(DISTINCT tag) = ?)",
+                placeholders
+            ),
+            params,
+        );
+    }
+
+    sql
+}
+
+/// Query one vault DB for up to `limit` rows after `cursor`
+pub fn query_vault(
+    conn: &Connection,
+    vault_id: &str,
+    filter: &PhotoFilter,
+    sort: PhotoSort,
+    cursor: Option<&Cursor>,
+    limit: u32,
+) -> rusqlite::Result<Vec<(SortValue, PhotoWithVault)>> {
+    query_vault_sql(conn, vault_id, build_filter(filter), sort, cursor, limit)
+}
+
+/// Like `query_vault`, for callers that add their own clauses to the filter
+pub fn query_vault_sql(
+    conn: &Connection,
+    vault_id: &str,
+    mut sql: SqlFilter,
+    sort: PhotoSort,
+    cursor: Option<&Cursor>,
+    limit: u32,
+) -> rusqlite::Result<Vec<(SortValue, PhotoWithVault)>> {
+    let key_sql = sort.key_sql();
+    let (after, order) = if sort.is_desc() { ("<", "DESC") } else { (">", "ASC") };
+
+    // Keyset condition on (key, vault_id, id), specialised for this vault
+    if let Some(cursor) = cursor {
+        let vault_cmp = vault_id.cmp(cursor.vault_id.as_str());
+        let vault_after = if sort.is_desc() {
+            vault_cmp == Ordering::Less
+        } else {
+            vault_cmp == Ordering::Greater
+        };
+
+        if vault_cmp == Ordering::Equal {
+            sql.push(
+                format!("({k} {a} ? OR ({k} = ? AND id {a} ?))", k = key_sql, a = after),
+                vec![
+                    Box::new(cursor.key.clone()),
+                    Box::new(cursor.key.clone()),
+                    Box::new(cursor.id.clone()),
+                ],
+            );
+        } else if vault_after {
+            sql.push(format!("{} {}= ?", key_sql, after), vec![Box::new(cursor.key.clone())]);
+        } else {
+            sql.push(format!("{} {} ?", key_sql, after), vec![Box::new(cursor.key.clone())]);
+        }
+    }
+
+    let query = format!(
+        "SELECT id, filename, COALESCE(created_at, ''), captured_at, tier, media_type, width, height,
+                latitude, longitude, make, model, lens_model, iso, f_number, exposure_time,
+                favorite, rating, color_label, {key}
+         FROM photos {where_sql}
+         ORDER BY {key} {order}, id {order}
+         LIMIT {limit}",
+        key = key_sql,
+        where_sql = sql.where_sql(),
+        order = order,
+        limit = limit,
+    );
+
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt.query_map(sql.param_refs().as_slice(), |row| {
+        Ok((
+            SortValue::from_value(row.get(19)?),
+            PhotoWithVault {
+                id: row.get(0)?,
+                vault_id: vault_id.to_string(),
+                filename: row.get(1)?,
+                created_at: row.get(2)?,
+                captured_at: row.get(3)?,
+                tier: row.get(4)?,
+                media_type: row
+                    .get::<_, Option<String>>(5)?
+                    .unwrap_or_else(|| "image".to_string()),
+                width: row.get::<_, Option<u32>>(6)?.unwrap_or(0),
+                height: row.get::<_, Option<u32>>(7)?.unwrap_or(0),
+                latitude: row.get(8)?,
+                longitude: row.get(9)?,
+                make: row.get(10)?,
+                model: row.get(11)?,
+                lens_model: row.get(12)?,
+                iso: row.get(13)?,
+                f_number: row.get(14)?,
+                exposure_time: row.get(15)?,
+                favorite: row.get(16)?,
+                rating: row.get(17)?,
+                color_label: row.get(18)?,
+            },
+        ))
+    })?;
+
+    rows.collect()
+}
+
+/// Order of two rows under `sort`, including the (vault_id, id) tie-breaker
+fn compare_rows(sort: PhotoSort, a: &(SortValue, PhotoWithVault), b: &(SortValue, PhotoWithVault)) -> Ordering {
+    let ord = a
+        .0
+        .partial_cmp(&b.0)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| a.1.vault_id.cmp(&b.1.vault_id))
+        .then_with(|| a.1.id.cmp(&b.1.id));
+    if sort.is_desc() {
+        ord.reverse()
+    } else {
+        ord
+    }
+}
+
+/// Vault IDs a filter applies to
+pub fn selected_vaults(app: &AppHandle, filter: &PhotoFilter) -> Result<Vec<String>, String> {
+    let vault_ids = crate::vault::store::get_vault_ids(app)?;
+    Ok(vault_ids
+        .into_iter()
+        .map(|(id, _)| id)
+        .filter(|id| {
+            filter
+                .vault_ids
+                .as_ref()
+                .map(|ids| ids.contains(id))
+                .unwrap_or(true)
+        })
+        .collect())
+}
+
+/// Requested page size, defaulted and clamped
+pub fn page_limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+/// Query photos across vaults with filters, sorting and cursor pagination
+#[tauri::command]
+pub async fn query_photos(app: AppHandle, query: PhotoQuery) -> Result<PhotoPage, String> {
+    let limit = page_limit(query.limit);
+    let cursor = query.cursor.as_deref().map(Cursor::decode).transpose()?;
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let mut rows = Vec::new();
+    for vault_id in selected_vaults(&app, &query.filter)? {
+        let db_path = app_dir.join("vaults").join(&vault_id).join("manifest.db");
+        if !db_path.exists() {
+            continue;
+        }
+
+        let conn = crate::open_vault_db(&app, &vault_id, &db_path)?;
+        // One extra row tells us whether another page exists
+        let vault_rows = query_vault(&conn, &vault_id, &query.filter, query.sort, cursor.as_ref(), limit + 1)
+            .map_err(|e| e.to_string())?;
+        rows.extend(vault_rows);
+    }
+
+    Ok(into_page(rows, query.sort, limit))
+}
+
+/// Merge rows fetched with `limit + 1` per vault into a page
+pub fn into_page(mut rows: Vec<(SortValue, PhotoWithVault)>, sort: PhotoSort, limit: u32) -> PhotoPage {
+    rows.sort_by(|a, b| compare_rows(sort, a, b));
+
+    let has_more = rows.len() > limit as usize;
+    rows.truncate(limit as usize);
+
+    let next_cursor = if has_more {
+        rows.last().map(|(key, photo)| {
+            Cursor {
+                key: key.clone(),
+                vault_id: photo.vault_id.clone(),
+                id: photo.id.clone(),
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    PhotoPage {
+        items: rows.into_iter().map(|(_, photo)| photo).collect(),
+        next_cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        for (i, (captured, make, iso, lat)) in [
+            (Some("2024-01-01T10:00:00+00:00"), "Canon", 100, Some(48.8)),
+            (Some("2024-02-01T10:00:00+00:00"), "Sony", 800, None),
+            (None, "Canon", 3200, Some(-33.9)),
+            (Some("2023-06-01T10:00:00+00:00"), "Fujifilm", 200, Some(35.6)),
+            (Some("2024-02-01T10:00:00+00:00"), "canon", 400, None),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            conn.execute(
+                "INSERT INTO photos (id, filename, created_at, captured_at, s3_key, tier, make, iso, latitude, longitude, size_bytes)
+                 VALUES (?1, ?2, '2024-03-01T00:00:00+00:00', ?3, 'k', 'Standard', ?4, ?5, ?6, ?6, ?7)",
+                rusqlite::params![format!("p{}", i), format!("IMG_{}.jpg", i), captured, make, iso, lat, i as i64 * 10],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn ids(rows: &[(SortValue, PhotoWithVault)]) -> Vec<&str> {
+        rows.iter().map(|(_, p)| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_cursor_pagination_visits_every_row_once() {
+        let conn = test_db();
+        let filter = PhotoFilter::default();
+        let mut seen = Vec::new();
+        let mut cursor: Option<Cursor> = None;
+
+        loop {
+            let page = query_vault(&conn, "v1", &filter, PhotoSort::DateDesc, cursor.as_ref(), 2).unwrap();
+            if page.is_empty() {
+                break;
+            }
+            seen.extend(ids(&page).into_iter().map(|s| s.to_string()));
+            let (key, last) = page.last().unwrap();
+            cursor = Some(Cursor {
+                key: key.clone(),
+                vault_id: "v1".to_string(),
+                id: last.id.clone(),
+            });
+        }
+
+        // p2 has no capture date and falls back to created_at (newest)
+        assert_eq!(seen, vec!["p2", "p4", "p1", "p0", "p3"]);
+    }
+
+    #[test]
+    fn test_filters() {
+        let conn = test_db();
+
+        let filter = PhotoFilter {
+            make: Some("CANON".to_string()),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p0", "p2", "p4"]);
+
+        let filter = PhotoFilter {
+            iso_min: Some(200),
+            iso_max: Some(800),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p1", "p3", "p4"]);
+
+        let filter = PhotoFilter {
+            has_gps: Some(false),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p1", "p4"]);
+
+        let filter = PhotoFilter {
+            date_from: Some("2024-01-01".to_string()),
+            date_to: Some("2024-02-01".to_string()),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p0"]);
+    }
+
+    #[test]
+    fn test_curation_filters() {
+        let conn = test_db();
+        conn.execute_batch(
+            "UPDATE photos SET rating = 4, favorite = 1 WHERE id IN ('p1', 'p3');
+             UPDATE photos SET rating = 2, color_label = 'red' WHERE id = 'p0';",
+        )
+        .unwrap();
+        crate::curation::set_photo_tags(&conn, "p1", &["Iceland".to_string(), "aurora".to_string()]).unwrap();
+        crate::curation::set_photo_tags(&conn, "p3", &["iceland".to_string()]).unwrap();
+
+        let filter = PhotoFilter {
+            rating_min: Some(3),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p1", "p3"]);
+        assert!(rows[0].1.favorite);
+
+        let filter = PhotoFilter {
+            tags: Some(vec!["ICELAND".to_string(), "aurora".to_string()]),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p1"]);
+
+        let filter = PhotoFilter {
+            color_labels: Some(vec!["red".to_string()]),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p0"]);
+    }
+
+    #[test]
+    fn test_bounds_across_antimeridian() {
+        let conn = test_db();
+        let filter = PhotoFilter {
+            bounds: Some(GeoBounds {
+                north: 60.0,
+                south: -60.0,
+                east: -30.0,
+                west: 40.0,
+            }),
+            ..Default::default()
+        };
+        let rows = query_vault(&conn, "v1", &filter, PhotoSort::SizeAsc, None, 10).unwrap();
+        assert_eq!(ids(&rows), vec!["p0", "p2"]);
+    }
+}
@@ -0,0 +1,67 @@
+//! AWS S3 prices (us-east-1, USD) used for cost estimates in the app and the
+//! compression benchmark. Tiers are the strings stored in `photos.tier`.
+
+/// === STORAGE COSTS (USD per GB per month) ===
+pub const COST_S3_STANDARD: f64 = 0.023;
+pub const COST_GLACIER_DEEP: f64 = 0.00099;
+pub const COST_S3_INSTANT: f64 = 0.004; // Glacier Instant Retrieval
+
+/// === TRANSITION COSTS (one-time, USD per GB) ===
+/// Lifecycle transition from Standard to Glacier tiers
+pub const COST_TRANSITION_TO_GLACIER: f64 = 0.02;
+
+/// === PUT REQUEST COSTS (USD per 1,000 requests) ===
+pub const COST_PUT_STANDARD: f64 = 0.005;
+pub const COST_PUT_GLACIER_IR: f64 = 0.02;
+pub const COST_PUT_DEEP_ARCHIVE: f64 = 0.05;
+
+/// === RETRIEVAL COSTS (USD per GB) ===
+/// Glacier Instant Retrieval - immediate access
+pub const COST_RETRIEVE_GLACIER_IR: f64 = 0.03;
+/// Deep Archive Standard - 12 hour retrieval
+pub const COST_RETRIEVE_DA_STANDARD: f64 = 0.01;
+/// Deep Archive Bulk - 48 hour retrieval (cheapest)
+pub const COST_RETRIEVE_DA_BULK: f64 = 0.0025;
+
+/// === MINIMUM STORAGE DURATIONS (days) ===
+/// Objects deleted earlier are billed for the remainder
+pub const DEEP_ARCHIVE_MIN_DAYS: f64 = 180.0;
+pub const GLACIER_IR_MIN_DAYS: f64 = 90.0;
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Storage price of a tier in USD per GB-month
+pub fn storage_price(tier: &str) -> f64 {
+    match tier {
+        "DeepArchive" => COST_GLACIER_DEEP,
+        "GlacierIR" => COST_S3_INSTANT,
+        _ => COST_S3_STANDARD,
+    }
+}
+
+/// PUT price of a tier in USD per 1,000 requests
+pub fn put_price(tier: &str) -> f64 {
+    match tier {
+        "DeepArchive" => COST_PUT_DEEP_ARCHIVE,
+        "GlacierIR" => COST_PUT_GLACIER_IR,
+        _ => COST_PUT_STANDARD,
+    }
+}
+
+/// Minimum billed storage duration of a tier, if it has one
+pub fn minimum_storage_days(tier: &str) -> Option<f64> {
+    match tier {
+        "DeepArchive" => Some(DEEP_ARCHIVE_MIN_DAYS),
+        "GlacierIR" => Some(GLACIER_IR_MIN_DAYS),
+        _ => None,
+    }
+}
+
+pub fn bytes_to_gb(bytes: u64) -> f64 {
+    bytes as f64 / BYTES_PER_GB
+}
+
+/// Monthly cost of storing `bytes` in `tier`
+pub fn monthly_storage_cost(tier: &str, bytes: u64) -> f64 {
+    bytes_to_gb(bytes) * storage_price(tier)
+}
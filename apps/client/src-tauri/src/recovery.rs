@@ -0,0 +1,245 @@
+//! Disaster Recovery Module
+//!
+//! Every upload also writes a small encrypted metadata sidecar (`metadata/{id}.enc`)
+//! holding the photo's `PhotoRecord`. If `manifest.enc` is lost or corrupted, the
+//! `photos` table can be rebuilt from these sidecars. Originals uploaded before
+//! sidecars existed are recovered with the little the bucket itself knows
+//! (key, size, storage class).
+//!
+//! Sidecars are rewritten when a photo's curation, trash or verification state
+//! changes, so they stay current with the manifest.
+
+use crate::crypto;
+use crate::manifest::{self, MergeStats, PhotoRecord};
+use crate::storage::{ObjectSummary, Storage};
+use crate::vault::VaultConfig;
+use crate::AppState;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::stream::{self, StreamExt};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+/// S3 prefix for per-photo metadata sidecars
+pub const METADATA_PREFIX: &str = "metadata/";
+
+/// Number of sidecars transferred in parallel
+const SIDECAR_CONCURRENCY: usize = 16;
+
+/// S3 key of the metadata sidecar for a photo
+pub fn sidecar_key(photo_id: &str) -> String {
+    format!("{}{}.enc", METADATA_PREFIX, photo_id)
+}
+
+/// Encrypt a photo record for upload as a sidecar
+pub fn encrypt_sidecar(record: &PhotoRecord, key: &[u8; 32]) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(record).context("Failed to serialize metadata sidecar")?;
+    crypto::encrypt(&json, key).context("Failed to encrypt metadata sidecar")
+}
+
+/// Re-upload the sidecars of photos changed after their upload (curation, trash)
+/// so a rebuild doesn't restore stale metadata. Runs in the background; failures
+/// are only logged since the manifest still holds the change.
+pub fn refresh_sidecars(app: &AppHandle, ids: Vec<String>) {
+    if ids.is_empty() {
+        return;
+    }
+    let state = app.state::<AppState>();
+    let (storage, db, config) = (
+        Arc::clone(&state.storage),
+        Arc::clone(&state.db),
+        Arc::clone(&state.config),
+    );
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = upload_sidecars(&storage, &db, &config, &ids).await {
+            log::warn!("[Recovery] Failed to refresh metadata sidecars: {:#}", e);
+        }
+    });
+}
+
+async fn upload_sidecars(
+    storage: &Mutex<Option<Storage>>,
+    db: &Mutex<Option<Connection>>,
+    config: &Mutex<Option<VaultConfig>>,
+    ids: &[String],
+) -> Result<()> {
+    let storage = storage.lock().await.clone().context("Storage not initialized")?;
+    let key: [u8; 32] = {
+        let config = config.lock().await;
+        let config = config.as_ref().context("Vault not loaded")?;
+        BASE64
+            .decode(&config.vault_key)
+            .context("Invalid vault key encoding")?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid key length"))?
+    };
+    let records = {
+        let db = db.lock().await;
+        let conn = db.as_ref().context("DB not initialized")?;
+        manifest::export_photo_records(conn, ids)?
+    };
+
+    let results: Vec<Result<()>> = stream::iter(records)
+        .map(|record| {
+            let storage = &storage;
+            async move {
+                let encrypted = encrypt_sidecar(&record, &key)?;
+                storage.upload_file(&sidecar_key(&record.id), encrypted).await
+            }
+        })
+        .buffer_unordered(SIDECAR_CONCURRENCY)
+        .collect()
+        .await;
+    results.into_iter().collect()
+}
+
+fn decrypt_sidecar(encrypted: &[u8], key: &[u8; 32]) -> Result<PhotoRecord> {
+    let json = crypto::decrypt(encrypted, key).context("Failed to decrypt metadata sidecar")?;
+    serde_json::from_slice(&json).context("Failed to deserialize metadata sidecar")
+}
+
+/// Result of a rebuild
+#[derive(Debug, Default, Serialize)]
+pub struct RebuildStats {
+    /// Photos recovered with full metadata from sidecars
+    pub from_sidecars: u32,
+    /// Originals without a sidecar, recovered from the object listing only
+    pub from_listing: u32,
+    /// Sidecars that could not be downloaded or decrypted
+    pub failed: u32,
+    pub photos_added: u32,
+    pub photos_updated: u32,
+}
+
+/// List the bucket and reconstruct photo records for every original in it
+pub async fn collect_photo_records(
+    storage: &Storage,
+    key: &[u8; 32],
+    stats: &mut RebuildStats,
+) -> Result<Vec<PhotoRecord>> {
+    let sidecars = storage.list_objects(METADATA_PREFIX).await?;
+
+    let results: Vec<(String, Result<PhotoRecord>)> = stream::iter(sidecars)
+        .map(|object| async move {
+            let record = match storage.download_file(&object.key).await {
+                Ok(bytes) => decrypt_sidecar(&bytes, key),
+                Err(e) => Err(e),
+            };
+            (object.key, record)
+        })
+        .buffer_unordered(SIDECAR_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut records = Vec::new();
+    for (sidecar, result) in results {
+        match result {
+            Ok(record) => {
+                records.push(record);
+                stats.from_sidecars += 1;
+            }
+            Err(e) => {
+                log::warn!("[Recovery] Skipping sidecar {}: {}", sidecar, e);
+                stats.failed += 1;
+            }
+        }
+    }
+
+    // Fall back to the listing for originals that have no sidecar
+    let recovered: HashSet<String> = records.iter().map(|r| r.s3_key.clone()).collect();
+
+    let thumbnails: HashMap<String, String> = storage
+        .list_objects("thumbnails/")
+        .await?
+        .into_iter()
+        .filter_map(|o| file_stem(&o.key).map(|stem| (stem.to_string(), o.key.clone())))
+        .collect();
+
+    let mut originals = storage.list_objects("originals/").await?;
+    originals.extend(storage.list_objects("audio/").await?);
+
+    for object in originals {
+        if recovered.contains(&object.key) {
+            continue;
+        }
+        if let Some(record) = record_from_listing(&object, &thumbnails) {
+            records.push(record);
+            stats.from_listing += 1;
+        }
+    }
+
+    Ok(records)
+}
+
+/// Merge recovered records into the photos table
+pub fn import_records(
+    conn: &Connection,
+    records: &[PhotoRecord],
+    stats: &mut RebuildStats,
+) -> Result<()> {
+    let mut merge_stats = MergeStats::default();
+    manifest::merge_photos(conn, records, &mut merge_stats)?;
+    stats.photos_added = merge_stats.photos_added;
+    stats.photos_updated = merge_stats.photos_updated;
+    Ok(())
+}
+
+fn file_stem(key: &str) -> Option<&str> {
+    let name = key.rsplit('/').next()?;
+    name.split('.').next().filter(|s| !s.is_empty())
+}
+
+/// Build a minimal record from an original's key, e.g. `originals/images/{id}.avif`
+fn record_from_listing(
+    object: &ObjectSummary,
+    thumbnails: &HashMap<String, String>,
+) -> Option<PhotoRecord> {
+    let media_type = if object.key.starts_with("originals/images/") {
+        "image"
+    } else if object.key.starts_with("originals/videos/") {
+        "video"
+    } else if object.key.starts_with("audio/") {
+        "audio"
+    } else {
+        return None;
+    };
+
+    let id = file_stem(&object.key)?.to_string();
+    let filename = object.key.rsplit('/').next()?.to_string();
+
+    let tier = match object.storage_class.as_deref() {
+        Some("DEEP_ARCHIVE") => "DeepArchive",
+        Some("GLACIER_IR") => "GlacierIR",
+        Some("STANDARD") | None => "Standard",
+        Some(_) => "Unknown",
+    };
+
+    Some(PhotoRecord {
+        thumbnail_key: thumbnails.get(&id).cloned(),
+        id,
+        filename,
+        width: None,
+        height: None,
+        // Oldest possible timestamp so any real record wins the merge
+        created_at: None,
+        captured_at: None,
+        size_bytes: Some(object.size_bytes as i64),
+        thumbnail_size_bytes: None,
+        s3_key: object.key.clone(),
+        tier: tier.to_string(),
+        media_type: media_type.to_string(),
+        latitude: None,
+        longitude: None,
+        make: None,
+        model: None,
+        lens_model: None,
+        iso: None,
+        f_number: None,
+        exposure_time: None,
+        ..Default::default()
+    })
+}
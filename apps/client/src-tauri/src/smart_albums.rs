@@ -0,0 +1,353 @@
+//! Smart albums: named, saved photo queries
+//!
+//! A smart album stores a rule (the listing filters plus an optional semantic
+//! clause) rather than a list of photos. The rule is evaluated against the vault
+//! DB each time the album is opened, so new uploads show up without any upkeep.
+
+use crate::embedding::{self, EmbeddingState};
+use crate::photo_query::{self, PhotoFilter, PhotoPage, PhotoSort, SqlFilter};
+use crate::AppState;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+/// Natural-language clause matched against image embeddings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticClause {
+    pub query: String,
+    /// Similarity threshold; defaults to the one used by semantic search
+    #[serde(default)]
+    pub min_score: Option<f32>,
+}
+
+/// The saved query. All clauses are combined with AND. `vault_ids` is ignored:
+/// a smart album always applies to the vault it is stored in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartAlbumRule {
+    #[serde(flatten)]
+    pub filter: PhotoFilter,
+    pub semantic: Option<SemanticClause>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SmartAlbum {
+    pub id: String,
+    pub name: String,
+    pub rule: SmartAlbumRule,
+    pub sort: PhotoSort,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct UpdateSmartAlbumPayload {
+    pub name: Option<String>,
+    pub rule: Option<SmartAlbumRule>,
+    pub sort: Option<PhotoSort>,
+}
+
+fn sort_to_string(sort: PhotoSort) -> String {
+    serde_json::to_value(sort)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn sort_from_string(value: &str) -> PhotoSort {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).unwrap_or_default()
+}
+
+fn load_smart_albums(conn: &Connection, id: Option<&str>) -> Result<Vec<SmartAlbum>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, rule, sort, created_at, updated_at FROM smart_albums
+             WHERE deleted_at IS NULL AND (?1 IS NULL OR id = ?1)
+             ORDER BY name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut albums = Vec::new();
+    for (id, name, rule, sort, created_at, updated_at) in rows {
+        // A rule written by a newer app version may not parse; skip it rather than fail the list
+        let rule = match serde_json::from_str(&rule) {
+            Ok(rule) => rule,
+            Err(e) => {
+                log::warn!("[SmartAlbums] Skipping {} with unreadable rule: {}", id, e);
+                continue;
+            }
+        };
+        albums.push(SmartAlbum {
+            id,
+            name,
+            rule,
+            sort: sort_from_string(&sort),
+            created_at,
+            updated_at,
+        });
+    }
+    Ok(albums)
+}
+
+fn load_smart_album(conn: &Connection, id: &str) -> Result<SmartAlbum, String> {
+    load_smart_albums(conn, Some(id))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Smart album not found".to_string())
+}
+
+/// IDs of photos matching a semantic clause, using the in-memory embedding index
+async fn semantic_matches(
+    embedding_state: &EmbeddingState,
+    clause: &SemanticClause,
+) -> Result<Vec<String>, String> {
+    let query_embedding = {
+        let mut text_guard = embedding_state.text.lock().await;
+        let text_embedder = text_guard
+            .as_mut()
+            .ok_or("Text embedding model not initialized")?;
+        text_embedder.embed_query(&clause.query)?
+    };
+
+    let min_score = clause.min_score.unwrap_or(embedding::MIN_SEMANTIC_SCORE);
+    let index = embedding_state.index.lock().await;
+    Ok(index
+        .search(&query_embedding, index.len())
+        .into_iter()
+        .filter(|(_, score)| *score >= min_score)
+        .map(|(id, _)| id)
+        .collect())
+}
+
+/// SQL for a rule, given the photo IDs matched by its semantic clause (if any)
+pub fn build_rule_filter(rule: &SmartAlbumRule, semantic_ids: Option<&[String]>) -> SqlFilter {
+    let mut sql = photo_query::build_filter(&rule.filter);
+    if let Some(ids) = semantic_ids {
+        // Passed as one JSON array to stay clear of SQLite's parameter limit
+        sql.push(
+            "id IN (SELECT value FROM json_each(?))",
+            vec![Box::new(serde_json::to_string(ids).unwrap_or_default())],
+        );
+    }
+    sql
+}
+
+/// Evaluate a rule against the current vault and return one page of matches
+async fn evaluate(
+    state: &AppState,
+    embedding_state: &EmbeddingState,
+    rule: &SmartAlbumRule,
+    sort: PhotoSort,
+    cursor: Option<String>,
+    limit: Option<u32>,
+) -> Result<PhotoPage, String> {
+    let limit = photo_query::page_limit(limit);
+    let cursor = cursor.as_deref().map(photo_query::Cursor::decode).transpose()?;
+
+    let semantic_ids = match &rule.semantic {
+        Some(clause) => Some(semantic_matches(embedding_state, clause).await?),
+        None => None,
+    };
+
+    let vault_id = {
+        let config_guard = state.config.lock().await;
+        config_guard.as_ref().map(|c| c.id.clone()).unwrap_or_default()
+    };
+
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+    let sql = build_rule_filter(rule, semantic_ids.as_deref());
+    let rows = photo_query::query_vault_sql(conn, &vault_id, sql, sort, cursor.as_ref(), limit + 1)
+        .map_err(|e| e.to_string())?;
+
+    Ok(photo_query::into_page(rows, sort, limit))
+}
+
+#[tauri::command]
+pub async fn create_smart_album(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    rule: SmartAlbumRule,
+    sort: Option<PhotoSort>,
+) -> Result<SmartAlbum, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+        let rule_json = serde_json::to_string(&rule).map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "INSERT INTO smart_albums (id, name, rule, sort, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            rusqlite::params![id, name, rule_json, sort_to_string(sort.unwrap_or_default()), now],
+        )
+        .map_err(|e| e.to_string())?;
+
+        load_smart_album(conn, &id)?
+    };
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(album)
+}
+
+#[tauri::command]
+pub async fn get_smart_albums(state: State<'_, AppState>) -> Result<Vec<SmartAlbum>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    load_smart_albums(conn, None)
+}
+
+#[tauri::command]
+pub async fn update_smart_album(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    payload: UpdateSmartAlbumPayload,
+) -> Result<SmartAlbum, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        load_smart_album(conn, &id)?;
+
+        if let Some(name) = &payload.name {
+            conn.execute("UPDATE smart_albums SET name = ?1 WHERE id = ?2", [name, &id])
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(rule) = &payload.rule {
+            let rule_json = serde_json::to_string(rule).map_err(|e| e.to_string())?;
+            conn.execute("UPDATE smart_albums SET rule = ?1 WHERE id = ?2", [&rule_json, &id])
+                .map_err(|e| e.to_string())?;
+        }
+        if let Some(sort) = payload.sort {
+            conn.execute(
+                "UPDATE smart_albums SET sort = ?1 WHERE id = ?2",
+                [&sort_to_string(sort), &id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        conn.execute(
+            "UPDATE smart_albums SET updated_at = ?1 WHERE id = ?2",
+            [&chrono::Utc::now().to_rfc3339(), &id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        load_smart_album(conn, &id)?
+    };
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(album)
+}
+
+/// Delete a smart album. The row is kept as a tombstone for sync.
+#[tauri::command]
+pub async fn delete_smart_album(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE smart_albums SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2",
+            rusqlite::params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    crate::sync_daemon::notify_local_change(&app).await;
+    Ok(())
+}
+
+/// Evaluate a smart album and return one page of its photos
+#[tauri::command]
+pub async fn get_smart_album_items(
+    state: State<'_, AppState>,
+    embedding_state: State<'_, EmbeddingState>,
+    id: String,
+    cursor: Option<String>,
+    limit: Option<u32>,
+) -> Result<PhotoPage, String> {
+    let album = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        load_smart_album(conn, &id)?
+    };
+
+    evaluate(&state, &embedding_state, &album.rule, album.sort, cursor, limit).await
+}
+
+/// Evaluate an unsaved rule, so the editor can show matches while it is being built
+#[tauri::command]
+pub async fn preview_smart_album(
+    state: State<'_, AppState>,
+    embedding_state: State<'_, EmbeddingState>,
+    rule: SmartAlbumRule,
+    sort: Option<PhotoSort>,
+    cursor: Option<String>,
+    limit: Option<u32>,
+) -> Result<PhotoPage, String> {
+    evaluate(&state, &embedding_state, &rule, sort.unwrap_or_default(), cursor, limit).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_round_trip_and_evaluation() {
+        let json = r#"{"media_types": ["video"], "has_gps": true, "rating_min": 4,
+                       "semantic": {"query": "snow"}}"#;
+        let rule: SmartAlbumRule = serde_json::from_str(json).unwrap();
+        assert_eq!(rule.filter.rating_min, Some(4));
+        assert_eq!(rule.semantic.as_ref().unwrap().query, "snow");
+        let reparsed: SmartAlbumRule = serde_json::from_str(&serde_json::to_string(&rule).unwrap()).unwrap();
+        assert_eq!(reparsed.filter.media_types, Some(vec!["video".to_string()]));
+
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO photos (id, filename, s3_key, tier, media_type, latitude, longitude, rating)
+             VALUES ('v1', 'a.mp4', 'k', 'Standard', 'video', 38.7, -9.1, 5),
+                    ('v2', 'b.mp4', 'k', 'Standard', 'video', 41.1, -8.6, 4),
+                    ('v3', 'c.mp4', 'k', 'Standard', 'video', NULL, NULL, 5),
+                    ('i1', 'd.jpg', 'k', 'Standard', 'image', 38.7, -9.1, 5);",
+        )
+        .unwrap();
+
+        let semantic = vec!["v2".to_string(), "v3".to_string(), "i1".to_string()];
+        let sql = build_rule_filter(&rule, Some(&semantic));
+        let rows = photo_query::query_vault_sql(&conn, "vault", sql, PhotoSort::FilenameAsc, None, 10).unwrap();
+        let ids: Vec<&str> = rows.iter().map(|(_, p)| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["v2"]);
+    }
+
+    #[test]
+    fn test_sort_string_round_trip() {
+        assert_eq!(sort_to_string(PhotoSort::FilenameDesc), "filename_desc");
+        assert_eq!(sort_from_string("size_asc"), PhotoSort::SizeAsc);
+        assert_eq!(sort_from_string("bogus"), PhotoSort::DateDesc);
+    }
+}
@@ -0,0 +1,237 @@
+//! Vault statistics and storage breakdown
+//!
+//! Aggregates the `photos` table by tier, media type, year/month, camera and file
+//! extension. Originals are priced at their own tier; thumbnails always live in
+//! Glacier IR, as do display copies and Live Photo videos. Trashed photos are
+//! still billed until purged, so they count towards the total and are reported
+//! separately rather than in the breakdowns.
+
+use crate::pricing;
+use crate::AppState;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::State;
+
+/// Tier thumbnails, display copies and motion videos are uploaded to (see `UploadManager::upload_item`)
+const THUMBNAIL_TIER: &str = "GlacierIR";
+
+/// Key used when a photo has no value for a breakdown
+const UNKNOWN: &str = "unknown";
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct StatsBucket {
+    pub key: String,
+    pub count: u64,
+    pub original_bytes: u64,
+    /// Encrypted thumbnail bytes (0 for photos uploaded before sizes were recorded)
+    pub thumbnail_bytes: u64,
+    /// Encrypted display copies kept next to lossless originals
+    pub display_bytes: u64,
+    /// Encrypted Live Photo / Motion Photo videos
+    pub motion_bytes: u64,
+    pub monthly_cost_usd: f64,
+}
+
+impl StatsBucket {
+    fn add(&mut self, photo: &PhotoSizes) {
+        self.count += 1;
+        self.original_bytes += photo.original_bytes;
+        self.thumbnail_bytes += photo.thumbnail_bytes;
+        self.display_bytes += photo.display_bytes;
+        self.motion_bytes += photo.motion_bytes;
+        self.monthly_cost_usd += pricing::monthly_storage_cost(&photo.tier, photo.original_bytes)
+            + pricing::monthly_storage_cost(
+                THUMBNAIL_TIER,
+                photo.thumbnail_bytes + photo.display_bytes + photo.motion_bytes,
+            );
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct VaultStats {
+    /// Everything stored in the bucket, including the trash
+    pub total: StatsBucket,
+    pub trash: StatsBucket,
+    pub by_tier: Vec<StatsBucket>,
+    pub by_media_type: Vec<StatsBucket>,
+    /// Keyed by `YYYY`, oldest first
+    pub by_year: Vec<StatsBucket>,
+    /// Keyed by `YYYY-MM`, oldest first
+    pub by_month: Vec<StatsBucket>,
+    pub by_camera: Vec<StatsBucket>,
+    /// Lowercase extension of the uploaded file
+    pub by_extension: Vec<StatsBucket>,
+}
+
+struct PhotoSizes {
+    tier: String,
+    original_bytes: u64,
+    thumbnail_bytes: u64,
+    display_bytes: u64,
+    motion_bytes: u64,
+}
+
+/// Buckets keyed by a photo attribute
+#[derive(Default)]
+struct Breakdown(HashMap<String, StatsBucket>);
+
+impl Breakdown {
+    fn add(&mut self, key: &str, photo: &PhotoSizes) {
+        self.0
+            .entry(key.to_string())
+            .or_insert_with(|| StatsBucket {
+                key: key.to_string(),
+                ..Default::default()
+            })
+            .add(photo);
+    }
+
+    /// Buckets sorted by size, largest first
+    fn by_size(self) -> Vec<StatsBucket> {
+        let mut buckets: Vec<_> = self.0.into_values().collect();
+        buckets.sort_by(|a, b| {
+            (b.original_bytes + b.thumbnail_bytes + b.display_bytes + b.motion_bytes)
+                .cmp(&(a.original_bytes + a.thumbnail_bytes + a.display_bytes + a.motion_bytes))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        buckets
+    }
+
+    /// Buckets sorted by key
+    fn by_key(self) -> Vec<StatsBucket> {
+        let mut buckets: Vec<_> = self.0.into_values().collect();
+        buckets.sort_by(|a, b| a.key.cmp(&b.key));
+        buckets
+    }
+}
+
+/// "Make Model", without repeating the make when the model already includes it
+fn camera_label(make: Option<&str>, model: Option<&str>) -> String {
+    let make = make.map(str::trim).filter(|m| !m.is_empty());
+    let model = model.map(str::trim).filter(|m| !m.is_empty());
+    match (make, model) {
+        (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => {
+            model.to_string()
+        }
+        (Some(make), Some(model)) => format!("{} {}", make, model),
+        (Some(only), None) | (None, Some(only)) => only.to_string(),
+        (None, None) => UNKNOWN.to_string(),
+    }
+}
+
+fn extension(filename: &str) -> String {
+    std::path::Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| UNKNOWN.to_string())
+}
+
+pub fn compute_stats(conn: &Connection) -> rusqlite::Result<VaultStats> {
+    let mut stmt = conn.prepare(
+        "SELECT tier, media_type, COALESCE(captured_at, created_at), make, model, filename,
+                COALESCE(size_bytes, 0), COALESCE(thumbnail_size_bytes, 0), deleted_at IS NOT NULL,
+                COALESCE(display_size_bytes, 0), COALESCE(motion_size_bytes, 0)
+         FROM photos",
+    )?;
+
+    let mut stats = VaultStats::default();
+    let mut by_tier = Breakdown::default();
+    let mut by_media_type = Breakdown::default();
+    let mut by_year = Breakdown::default();
+    let mut by_month = Breakdown::default();
+    let mut by_camera = Breakdown::default();
+    let mut by_extension = Breakdown::default();
+
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let photo = PhotoSizes {
+            tier: row.get(0)?,
+            original_bytes: row.get::<_, i64>(6)?.max(0) as u64,
+            thumbnail_bytes: row.get::<_, i64>(7)?.max(0) as u64,
+            display_bytes: row.get::<_, i64>(9)?.max(0) as u64,
+            motion_bytes: row.get::<_, i64>(10)?.max(0) as u64,
+        };
+
+        stats.total.add(&photo);
+        if row.get::<_, bool>(8)? {
+            stats.trash.add(&photo);
+            continue;
+        }
+
+        let date: Option<String> = row.get(2)?;
+        let year = date.as_deref().and_then(|d| d.get(..4)).unwrap_or(UNKNOWN);
+        let month = date.as_deref().and_then(|d| d.get(..7)).unwrap_or(UNKNOWN);
+        let make: Option<String> = row.get(3)?;
+        let model: Option<String> = row.get(4)?;
+        let filename: String = row.get(5)?;
+
+        by_tier.add(&photo.tier, &photo);
+        by_media_type.add(&row.get::<_, String>(1)?, &photo);
+        by_year.add(year, &photo);
+        by_month.add(month, &photo);
+        by_camera.add(&camera_label(make.as_deref(), model.as_deref()), &photo);
+        by_extension.add(&extension(&filename), &photo);
+    }
+
+    stats.total.key = "total".to_string();
+    stats.trash.key = "trash".to_string();
+    stats.by_tier = by_tier.by_size();
+    stats.by_media_type = by_media_type.by_size();
+    stats.by_year = by_year.by_key();
+    stats.by_month = by_month.by_key();
+    stats.by_camera = by_camera.by_size();
+    stats.by_extension = by_extension.by_size();
+    Ok(stats)
+}
+
+/// Storage breakdown and estimated monthly cost of the current vault
+#[tauri::command]
+pub async fn get_vault_stats(state: State<'_, AppState>) -> Result<VaultStats, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    compute_stats(conn).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: i64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_camera_label() {
+        assert_eq!(camera_label(Some("Canon"), Some("Canon EOS R5")), "Canon EOS R5");
+        assert_eq!(camera_label(Some("FUJIFILM"), Some("X100V")), "FUJIFILM X100V");
+        assert_eq!(camera_label(Some(" "), None), UNKNOWN);
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO photos (id, filename, s3_key, tier, media_type, created_at, captured_at, make, model, size_bytes, thumbnail_size_bytes, deleted_at)
+             VALUES ('p1', 'a.JPG', 'k', 'DeepArchive', 'image', '2024-05-01T00:00:00Z', '2023-12-31T23:00:00Z', 'Apple', 'iPhone 15', ?1, ?2, NULL),
+                    ('p2', 'b.mov', 'k', 'GlacierIR', 'video', '2024-05-01T00:00:00Z', NULL, NULL, NULL, ?1, NULL, NULL),
+                    ('p3', 'c.jpg', 'k', 'Standard', 'image', '2024-05-01T00:00:00Z', NULL, NULL, NULL, ?1, ?2, '2024-06-01T00:00:00Z')",
+            [10 * GB, GB],
+        )
+        .unwrap();
+
+        let stats = compute_stats(&conn).unwrap();
+        assert_eq!(stats.total.count, 3);
+        assert_eq!(stats.total.original_bytes, 30 * GB as u64);
+        assert_eq!(stats.total.thumbnail_bytes, 2 * GB as u64);
+        assert_eq!(stats.trash.count, 1);
+
+        let expected_cost = 10.0 * (pricing::COST_GLACIER_DEEP + pricing::COST_S3_INSTANT + pricing::COST_S3_STANDARD)
+            + 2.0 * pricing::COST_S3_INSTANT;
+        assert!((stats.total.monthly_cost_usd - expected_cost).abs() < 1e-9);
+
+        let keys = |buckets: &[StatsBucket]| buckets.iter().map(|b| b.key.clone()).collect::<Vec<_>>();
+        assert_eq!(keys(&stats.by_month), vec!["2023-12", "2024-05"]);
+        assert_eq!(keys(&stats.by_extension), vec!["jpg", "mov"]);
+        assert_eq!(keys(&stats.by_camera), vec!["Apple iPhone 15", UNKNOWN]);
+    }
+}
@@ -54,6 +54,23 @@ pub enum RestoreResult {
     AlreadyInProgress,
 }
 
+/// A single stored version of an object in the versioned bucket
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ObjectVersion {
+    pub version_id: String,
+    pub last_modified: Option<String>,
+    pub size_bytes: u64,
+    pub is_latest: bool,
+}
+
+/// An object returned by a bucket listing
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ObjectSummary {
+    pub key: String,
+    pub size_bytes: u64,
+    pub storage_class: Option<String>,
+}
+
 /// Parse expiry date from x-amz-restore header
 /// Example: ongoing-request="false", expiry-date="Wed, 07 Nov 2012 00:00:00 GMT"
 fn parse_restore_expiry(header: &str) -> Option<String> {
@@ -142,6 +159,20 @@ impl Storage {
         Ok(())
     }
 
+    /// Upload a small file and return the ETag S3 assigned to it
+    pub async fn upload_file_with_etag(&self, key: &str, body: Vec<u8>) -> Result<Option<String>> {
+        let output = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .context("Failed to upload file")?;
+        Ok(output.e_tag().map(|s| s.to_string()))
+    }
+
     /// Upload a file with a specific storage class (for thumbnails/audio to GLACIER_IR)
     pub async fn upload_file_with_storage_class(
         &self,
@@ -399,6 +430,200 @@ impl Storage {
         Ok(data.to_vec())
     }
 
+    /// Download a file, returning None if the key does not exist
+    pub async fn download_file_if_exists(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let result = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await;
+
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                let not_found = e
+                    .as_service_error()
+                    .map(|se| se.is_no_such_key())
+                    .unwrap_or(false);
+                if not_found {
+                    return Ok(None);
+                }
+                return Err(e).context("Failed to download file");
+            }
+        };
+
+        let data = output
+            .body
+            .collect()
+            .await
+            .context("Failed to read body")?
+            .into_bytes();
+        Ok(Some(data.to_vec()))
+    }
+
+    /// Cheap change detection via HeadObject.
+    /// Returns the object's ETag (or LastModified if no ETag), or None if the key does not exist.
+    pub async fn head_object_stamp(&self, key: &str) -> Result<Option<String>> {
+        let result = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await;
+
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                let not_found = e
+                    .as_service_error()
+                    .map(|se| se.is_not_found())
+                    .unwrap_or(false);
+                if not_found {
+                    return Ok(None);
+                }
+                return Err(e).context("Failed to head object");
+            }
+        };
+
+        let stamp = output.e_tag().map(|s| s.to_string()).or_else(|| {
+            output
+                .last_modified()
+                .and_then(|t| t.fmt(aws_smithy_types::date_time::Format::DateTime).ok())
+        });
+        Ok(stamp)
+    }
+
+    /// Size and storage class of a stored object, as reported by HeadObject
+    pub async fn head_object_info(&self, key: &str) -> Result<ObjectSummary> {
+        let output = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to head object {}", key))?;
+
+        Ok(ObjectSummary {
+            key: key.to_string(),
+            size_bytes: output.content_length().unwrap_or(0) as u64,
+            storage_class: output.storage_class().map(|c| c.as_str().to_string()),
+        })
+    }
+
+    /// Download a specific version of a file
+    pub async fn download_file_version(&self, key: &str, version_id: &str) -> Result<Vec<u8>> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .version_id(version_id)
+            .send()
+            .await
+            .context("Failed to download file version")?;
+
+        let data = output
+            .body
+            .collect()
+            .await
+            .context("Failed to read body")?
+            .into_bytes();
+        Ok(data.to_vec())
+    }
+
+    /// List all current objects under a prefix
+    pub async fn list_objects(&self, prefix: &str) -> Result<Vec<ObjectSummary>> {
+        let mut objects = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token.take())
+                .send()
+                .await
+                .context("Failed to list objects")?;
+
+            for object in output.contents() {
+                if let Some(key) = object.key() {
+                    objects.push(ObjectSummary {
+                        key: key.to_string(),
+                        size_bytes: object.size().unwrap_or(0) as u64,
+                        storage_class: object.storage_class().map(|c| c.as_str().to_string()),
+                    });
+                }
+            }
+
+            if !output.is_truncated().unwrap_or(false) {
+                break;
+            }
+            continuation_token = output.next_continuation_token().map(|s| s.to_string());
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(objects)
+    }
+
+    /// List all stored versions of a single key, newest first.
+    /// Delete markers are skipped since they hold no data.
+    pub async fn list_object_versions(&self, key: &str) -> Result<Vec<ObjectVersion>> {
+        let mut versions = Vec::new();
+        let mut key_marker: Option<String> = None;
+        let mut version_id_marker: Option<String> = None;
+
+        loop {
+            let output = self
+                .client
+                .list_object_versions()
+                .bucket(&self.bucket)
+                .prefix(key)
+                .set_key_marker(key_marker.take())
+                .set_version_id_marker(version_id_marker.take())
+                .send()
+                .await
+                .context("Failed to list object versions")?;
+
+            for version in output.versions() {
+                // Prefix matching may include other keys (e.g. "manifest.enc.bak")
+                if version.key() != Some(key) {
+                    continue;
+                }
+                let Some(version_id) = version.version_id() else {
+                    continue;
+                };
+                versions.push(ObjectVersion {
+                    version_id: version_id.to_string(),
+                    last_modified: version.last_modified().and_then(|t| {
+                        t.fmt(aws_smithy_types::date_time::Format::DateTime).ok()
+                    }),
+                    size_bytes: version.size().unwrap_or(0) as u64,
+                    is_latest: version.is_latest().unwrap_or(false),
+                });
+            }
+
+            if !output.is_truncated().unwrap_or(false) {
+                break;
+            }
+            key_marker = output.next_key_marker().map(|s| s.to_string());
+            version_id_marker = output.next_version_id_marker().map(|s| s.to_string());
+            if key_marker.is_none() {
+                break;
+            }
+        }
+
+        versions.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+        Ok(versions)
+    }
+
     pub async fn delete_file(&self, key: &str) -> Result<()> {
         self.client
             .delete_object()
@@ -410,6 +635,96 @@ impl Storage {
         Ok(())
     }
 
+    /// Permanently delete every version and delete marker of a single key
+    pub async fn delete_all_versions(&self, key: &str) -> Result<()> {
+        let mut object_identifiers = Vec::new();
+        let mut key_marker: Option<String> = None;
+        let mut version_id_marker: Option<String> = None;
+
+        loop {
+            let output = self
+                .client
+                .list_object_versions()
+                .bucket(&self.bucket)
+                .prefix(key)
+                .set_key_marker(key_marker.take())
+                .set_version_id_marker(version_id_marker.take())
+                .send()
+                .await
+                .context("Failed to list object versions")?;
+
+            let versions = output
+                .versions()
+                .iter()
+                .map(|v| (v.key(), v.version_id()))
+                .chain(
+                    output
+                        .delete_markers()
+                        .iter()
+                        .map(|m| (m.key(), m.version_id())),
+                );
+
+            for (version_key, version_id) in versions {
+                // Prefix matching may include other keys
+                if version_key != Some(key) {
+                    continue;
+                }
+                if let Some(version_id) = version_id {
+                    object_identifiers.push(
+                        aws_sdk_s3::types::ObjectIdentifier::builder()
+                            .key(key)
+                            .version_id(version_id)
+                            .build()
+                            .unwrap(), // safe unwrap
+                    );
+                }
+            }
+
+            if !output.is_truncated().unwrap_or(false) {
+                break;
+            }
+            key_marker = output.next_key_marker().map(|s| s.to_string());
+            version_id_marker = output.next_version_id_marker().map(|s| s.to_string());
+            if key_marker.is_none() {
+                break;
+            }
+        }
+
+        // Buckets without versioning report no versions; fall back to a plain delete
+        if object_identifiers.is_empty() {
+            return self.delete_file(key).await;
+        }
+
+        for chunk in object_identifiers.chunks(1000) {
+            let delete = aws_sdk_s3::types::Delete::builder()
+                .set_objects(Some(chunk.to_vec()))
+                .quiet(true)
+                .build()
+                .unwrap(); // safe
+
+            let output = self
+                .client
+                .delete_objects()
+                .bucket(&self.bucket)
+                .delete(delete)
+                .send()
+                .await
+                .context("Failed to delete object versions")?;
+
+            // Quiet mode only reports the versions that failed (AccessDenied, object lock, ...)
+            if let Some(error) = output.errors().first() {
+                anyhow::bail!(
+                    "Failed to delete {} of {} version(s) of {}: {}",
+                    output.errors().len(),
+                    chunk.len(),
+                    key,
+                    error.message().or(error.code()).unwrap_or("unknown error")
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Recursively delete all objects (including versions) in the bucket
     pub async fn empty_bucket(&self) -> Result<()> {
         loop {
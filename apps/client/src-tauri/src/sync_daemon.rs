@@ -0,0 +1,299 @@
+//! Background Manifest Sync
+//!
+//! Runs one task per loaded vault that keeps the local DB and `manifest.enc` in sync:
+//! - Polls the manifest with HeadObject and only downloads/merges when its ETag changes
+//! - Debounces local changes so a burst of edits results in a single upload
+//! - Emits typed `sync:*` events so the UI can refresh after remote changes
+
+use crate::devices;
+use crate::manifest::{self, MergeStats};
+use crate::storage::Storage;
+use crate::vault::VaultConfig;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, oneshot, Mutex, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// How often the remote manifest is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Quiet period after the last local change before uploading
+const UPLOAD_DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// Payload of `sync:pulled`, emitted after a remote change was merged
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPulledEvent {
+    pub vault_id: String,
+    pub stats: MergeStats,
+}
+
+/// Payload of `sync:pushed`, emitted after the local manifest was uploaded
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPushedEvent {
+    pub vault_id: String,
+}
+
+/// Payload of `sync:device_revoked`, emitted when another device revoked this one
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncDeviceRevokedEvent {
+    pub vault_id: String,
+}
+
+/// Payload of `sync:error`
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncErrorEvent {
+    pub vault_id: String,
+    pub message: String,
+}
+
+/// Handle to the background sync task of the active vault.
+/// The task is aborted when the handle is dropped.
+pub struct SyncDaemon {
+    vault_id: String,
+    local_change: Arc<Notify>,
+    push_requests: mpsc::Sender<PushRequest>,
+    task: JoinHandle<()>,
+}
+
+/// Reply channel of an immediate upload requested with `push_now`
+type PushRequest = oneshot::Sender<Result<(), String>>;
+
+impl SyncDaemon {
+    /// Spawn the sync task for a vault. `last_stamp` is the ETag of the manifest
+    /// version the initial sync merged or uploaded, if known.
+    pub fn start(
+        app_handle: AppHandle,
+        storage: Storage,
+        db: Arc<Mutex<Option<Connection>>>,
+        config: VaultConfig,
+        last_stamp: Option<String>,
+    ) -> Self {
+        let local_change = Arc::new(Notify::new());
+        let (push_requests, push_rx) = mpsc::channel(4);
+        let vault_id = config.id.clone();
+
+        let worker = SyncWorker {
+            app_handle,
+            storage,
+            db,
+            config,
+            last_stamp,
+        };
+        let task = tokio::spawn(worker.run(Arc::clone(&local_change), push_rx));
+
+        log::info!("[Sync Daemon] Started for vault {}", vault_id);
+
+        Self {
+            vault_id,
+            local_change,
+            push_requests,
+            task,
+        }
+    }
+
+    pub fn vault_id(&self) -> &str {
+        &self.vault_id
+    }
+
+    /// Schedule a (debounced) upload of the local manifest.
+    /// Remote changes are merged first, so this also acts as an immediate remote check.
+    pub fn notify_local_change(&self) {
+        self.local_change.notify_one();
+    }
+
+    /// Upload the local manifest now, merging remote changes first, and wait for the result
+    pub async fn push_now(&self) -> Result<(), String> {
+        let (reply, result) = oneshot::channel();
+        self.push_requests
+            .send(reply)
+            .await
+            .map_err(|_| "Sync daemon stopped".to_string())?;
+        result.await.map_err(|_| "Sync daemon stopped".to_string())?
+    }
+
+    /// Stop the task and wait until it has finished, so it can no longer touch the
+    /// shared DB or storage (an aborted task only stops at its next await)
+    pub async fn stop(mut self) {
+        self.task.abort();
+        (&mut self.task).await.ok();
+    }
+}
+
+/// Schedule a manifest upload on the running daemon after a local change, if any
+pub async fn notify_local_change(app: &AppHandle) {
+    use tauri::Manager;
+    if let Some(sync_state) = app.try_state::<crate::SyncDaemonState>() {
+        if let Some(daemon) = sync_state.daemon.lock().await.as_ref() {
+            daemon.notify_local_change();
+        }
+    }
+}
+
+impl Drop for SyncDaemon {
+    fn drop(&mut self) {
+        self.task.abort();
+        log::info!("[Sync Daemon] Stopped for vault {}", self.vault_id);
+    }
+}
+
+struct SyncWorker {
+    app_handle: AppHandle,
+    storage: Storage,
+    db: Arc<Mutex<Option<Connection>>>,
+    config: VaultConfig,
+    /// ETag of the manifest version last merged or uploaded by this device
+    last_stamp: Option<String>,
+}
+
+impl SyncWorker {
+    async fn run(mut self, local_change: Arc<Notify>, mut push_requests: mpsc::Receiver<PushRequest>) {
+        // The daemon starts after load_vault's initial sync, so skip the immediate tick
+        let mut interval = tokio::time::interval_at(Instant::now() + POLL_INTERVAL, POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        let mut upload_deadline: Option<Instant> = None;
+
+        loop {
+            let deadline = upload_deadline;
+            let debounce = async move {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                _ = interval.tick() => {
+                    self.pull_if_changed().await;
+                }
+                _ = local_change.notified() => {
+                    upload_deadline = Some(Instant::now() + UPLOAD_DEBOUNCE);
+                }
+                Some(reply) = push_requests.recv() => {
+                    let result = self.try_push().await;
+                    if result.is_ok() {
+                        upload_deadline = None;
+                    }
+                    reply.send(result).ok();
+                }
+                _ = debounce => {
+                    // Retry on the next poll interval if the upload didn't go through
+                    upload_deadline = if self.push().await {
+                        None
+                    } else {
+                        Some(Instant::now() + POLL_INTERVAL)
+                    };
+                }
+            }
+        }
+    }
+
+    /// HeadObject the manifest and merge it only if it changed since we last saw it.
+    /// Returns false if the check failed.
+    async fn pull_if_changed(&mut self) -> bool {
+        let stamp = match self.storage.head_object_stamp(manifest::MANIFEST_S3_KEY).await {
+            Ok(stamp) => stamp,
+            Err(e) => {
+                self.emit_error(format!("Failed to check manifest: {}", e));
+                return false;
+            }
+        };
+
+        if stamp.is_none() || stamp == self.last_stamp {
+            return true;
+        }
+
+        log::info!("[Sync Daemon] Remote manifest changed, merging");
+        match crate::sync_manifest_download_internal(&self.storage, &self.db, &self.config).await {
+            Ok(stats) => {
+                self.last_stamp = stamp;
+                self.app_handle
+                    .emit(
+                        "sync:pulled",
+                        SyncPulledEvent {
+                            vault_id: self.config.id.clone(),
+                            stats,
+                        },
+                    )
+                    .ok();
+                self.check_revoked().await;
+                true
+            }
+            Err(e) => {
+                self.emit_error(e);
+                false
+            }
+        }
+    }
+
+    /// Merge any remote changes first so they aren't overwritten, then upload.
+    /// Returns false if the upload didn't happen.
+    async fn push(&mut self) -> bool {
+        self.try_push().await.is_ok()
+    }
+
+    /// `push` reporting why the upload didn't happen. Errors are also emitted.
+    async fn try_push(&mut self) -> Result<(), String> {
+        if !self.pull_if_changed().await {
+            return Err("Failed to merge the remote manifest before uploading".to_string());
+        }
+
+        match crate::sync_manifest_upload_internal(&self.storage, &self.db, &self.config).await {
+            Ok(etag) => {
+                self.last_stamp = etag;
+                self.app_handle
+                    .emit(
+                        "sync:pushed",
+                        SyncPushedEvent {
+                            vault_id: self.config.id.clone(),
+                        },
+                    )
+                    .ok();
+                Ok(())
+            }
+            Err(e) => {
+                self.emit_error(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    async fn check_revoked(&self) {
+        let revoked = {
+            let db_guard = self.db.lock().await;
+            db_guard
+                .as_ref()
+                .map(|conn| devices::is_current_device_revoked(conn).unwrap_or(false))
+                .unwrap_or(false)
+        };
+
+        if revoked {
+            log::warn!("[Sync Daemon] This device was revoked by another device");
+            self.app_handle
+                .emit(
+                    "sync:device_revoked",
+                    SyncDeviceRevokedEvent {
+                        vault_id: self.config.id.clone(),
+                    },
+                )
+                .ok();
+        }
+    }
+
+    fn emit_error(&self, message: String) {
+        log::warn!("[Sync Daemon] {}", message);
+        self.app_handle
+            .emit(
+                "sync:error",
+                SyncErrorEvent {
+                    vault_id: self.config.id.clone(),
+                    message,
+                },
+            )
+            .ok();
+    }
+}
@@ -0,0 +1,153 @@
+//! Full-text search over filenames, camera metadata, captions and memories
+//!
+//! Backed by the `search_index` FTS5 table, which triggers keep in sync with
+//! `photos` and `memories`. Scores are normalised to 0..1 (higher is better)
+//! so results can be merged with semantic search results.
+
+use crate::AppState;
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::State;
+
+/// Column weights for bm25: kind, id, title, camera, body
+const BM25_WEIGHTS: &str = "0.0, 0.0, 10.0, 2.0, 5.0";
+
+#[derive(Debug, Serialize)]
+pub struct TextSearchResult {
+    /// Photo or memory ID
+    pub id: String,
+    /// "photo" or "memory"
+    pub kind: String,
+    pub score: f32,
+}
+
+/// Turn user input into an FTS5 query: every word must match, as a prefix.
+/// Words are quoted so FTS5 syntax characters in the input are taken literally.
+fn build_match_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| word.chars().any(|c| c.is_alphanumeric()))
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Map a bm25 rank (negative, lower is better) to 0..1
+fn normalize_rank(rank: f64) -> f32 {
+    let relevance = (-rank).max(0.0);
+    (relevance / (1.0 + relevance)) as f32
+}
+
+pub fn search(conn: &Connection, input: &str, limit: usize) -> rusqlite::Result<Vec<TextSearchResult>> {
+    let Some(match_query) = build_match_query(input) else {
+        return Ok(Vec::new());
+    };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, kind, bm25(search_index, {}) AS rank
+         FROM search_index
+         WHERE search_index MATCH ?1
+           AND NOT (kind = 'photo' AND id IN (SELECT id FROM photos WHERE deleted_at IS NOT NULL))
+         ORDER BY rank
+         LIMIT ?2",
+        BM25_WEIGHTS
+    ))?;
+
+    let results = stmt
+        .query_map(rusqlite::params![match_query, limit as i64], |row| {
+            Ok(TextSearchResult {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                score: normalize_rank(row.get(2)?),
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn search_text(
+    state: State<'_, AppState>,
+    query: String,
+    limit: usize,
+) -> Result<Vec<TextSearchResult>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+
+    search(conn, &query, limit).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn
+    }
+
+    fn insert_photo(conn: &Connection, id: &str, filename: &str, make: &str, model: &str) {
+        conn.execute(
+            "INSERT INTO photos (id, filename, s3_key, tier, make, model) VALUES (?1, ?2, 'k', 'Standard', ?3, ?4)",
+            [id, filename, make, model],
+        )
+        .unwrap();
+    }
+
+    fn ids(results: &[TextSearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_build_match_query() {
+        assert_eq!(build_match_query("  "), None);
+        assert_eq!(build_match_query("beach \"sun"), Some("\"beach\"* \"sun\"*".to_string()));
+        assert_eq!(build_match_query("- ()"), None);
+    }
+
+    #[test]
+    fn test_prefix_search_over_photos_and_memories() {
+        let conn = test_db();
+        insert_photo(&conn, "p1", "IMG_2041.jpg", "FUJIFILM", "X100V");
+        insert_photo(&conn, "p2", "beach_day.heic", "Apple", "iPhone 15 Pro");
+        conn.execute(
+            "INSERT INTO memories (id, title, text_content, date, created_at, updated_at)
+             VALUES ('m1', 'Summer trip', 'A day at the beach', '2024-07-01', '', '')",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(ids(&search(&conn, "fuji", 10).unwrap()), vec!["p1"]);
+        assert_eq!(ids(&search(&conn, "iphone pro", 10).unwrap()), vec!["p2"]);
+
+        // Filename matches rank above memory body matches
+        let results = search(&conn, "beach", 10).unwrap();
+        assert_eq!(ids(&results), vec!["p2", "m1"]);
+        assert_eq!(results[1].kind, "memory");
+        assert!(results.iter().all(|r| r.score > 0.0 && r.score < 1.0));
+    }
+
+    #[test]
+    fn test_triggers_keep_index_in_sync() {
+        let conn = test_db();
+        insert_photo(&conn, "p1", "IMG_0001.jpg", "Canon", "EOS R5");
+
+        conn.execute("UPDATE photos SET model = 'EOS R6' WHERE id = 'p1'", []).unwrap();
+        assert!(search(&conn, "r5", 10).unwrap().is_empty());
+        assert_eq!(ids(&search(&conn, "r6", 10).unwrap()), vec!["p1"]);
+
+        conn.execute("UPDATE photos SET description = 'Grandma''s garden' WHERE id = 'p1'", []).unwrap();
+        assert_eq!(ids(&search(&conn, "garden", 10).unwrap()), vec!["p1"]);
+
+        conn.execute("DELETE FROM photos WHERE id = 'p1'", []).unwrap();
+        assert!(search(&conn, "canon", 10).unwrap().is_empty());
+    }
+}
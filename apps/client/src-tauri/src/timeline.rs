@@ -0,0 +1,211 @@
+//! Timeline aggregation for the scrubber
+//!
+//! Counts photos per day, week, month or year of their display date
+//! (`COALESCE(captured_at, created_at)`), using the same filters as the photo
+//! listing. Dates are stored in UTC and bucketed in the requested time zone.
+
+use crate::photo_query::{self, PhotoFilter, DISPLAY_DATE_SQL};
+use chrono::{FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineGranularity {
+    Day,
+    /// ISO weeks, starting on Monday
+    Week,
+    #[default]
+    Month,
+    Year,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TimelineQuery {
+    #[serde(flatten)]
+    pub filter: PhotoFilter,
+    pub granularity: TimelineGranularity,
+    /// Fixed UTC offset to bucket in. Unset uses the device's time zone,
+    /// including its daylight saving rules.
+    pub utc_offset_minutes: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelineBucket {
+    /// `YYYY-MM-DD` (day, week), `YYYY-MM` (month) or `YYYY` (year)
+    pub key: String,
+    /// Start of the bucket as RFC3339 in the requested time zone
+    pub start: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Timeline {
+    /// Newest bucket first, matching the default listing order
+    pub buckets: Vec<TimelineBucket>,
+    /// Photos without a parseable date
+    pub undated: u32,
+}
+
+/// Time zone used for bucketing
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    fn from_offset(minutes: Option<i32>) -> Result<Self, String> {
+        match minutes {
+            None => Ok(Zone::Local),
+            Some(minutes) => FixedOffset::east_opt(minutes * 60)
+                .map(Zone::Fixed)
+                .ok_or_else(|| format!("Invalid UTC offset: {} minutes", minutes)),
+        }
+    }
+
+    /// SQLite modifier converting a UTC time into this zone
+    fn sql_modifier(self) -> String {
+        match self {
+            Zone::Local => "'localtime'".to_string(),
+            Zone::Fixed(offset) => format!("'{:+} seconds'", offset.local_minus_utc()),
+        }
+    }
+
+    /// Midnight at the start of `date`, as RFC3339
+    fn start_of(self, date: NaiveDate) -> String {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        let start = match self {
+            Zone::Local => Local.from_local_datetime(&midnight).earliest().map(|t| t.to_rfc3339()),
+            Zone::Fixed(offset) => offset.from_local_datetime(&midnight).single().map(|t| t.to_rfc3339()),
+        };
+        start.unwrap_or_else(|| Utc.from_utc_datetime(&midnight).to_rfc3339())
+    }
+}
+
+fn bucket_key(start: NaiveDate, granularity: TimelineGranularity) -> String {
+    match granularity {
+        TimelineGranularity::Day | TimelineGranularity::Week => start.format("%Y-%m-%d").to_string(),
+        TimelineGranularity::Month => start.format("%Y-%m").to_string(),
+        TimelineGranularity::Year => start.format("%Y").to_string(),
+    }
+}
+
+/// SQLite date modifiers that select the first day of a bucket
+fn bucket_modifiers(granularity: TimelineGranularity) -> &'static str {
+    match granularity {
+        TimelineGranularity::Day => "",
+        // 'weekday 0' moves forward to Sunday (or stays), so six days back is Monday
+        TimelineGranularity::Week => ", 'weekday 0', '-6 days'",
+        TimelineGranularity::Month => ", 'start of month'",
+        TimelineGranularity::Year => ", 'start of year'",
+    }
+}
+
+/// Count one vault's photos per bucket start date. Returns the number of undated photos.
+fn count_vault(
+    conn: &Connection,
+    filter: &PhotoFilter,
+    granularity: TimelineGranularity,
+    zone: Zone,
+    counts: &mut BTreeMap<NaiveDate, u32>,
+) -> rusqlite::Result<u32> {
+    let sql = photo_query::build_filter(filter);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT date({}, {}{}) AS start, COUNT(*) FROM photos {} GROUP BY start",
+        DISPLAY_DATE_SQL,
+        zone.sql_modifier(),
+        bucket_modifiers(granularity),
+        sql.where_sql()
+    ))?;
+
+    let mut undated = 0;
+    let mut rows = stmt.query(sql.param_refs().as_slice())?;
+    while let Some(row) = rows.next()? {
+        let start: Option<String> = row.get(0)?;
+        let count: u32 = row.get(1)?;
+        match start.and_then(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok()) {
+            Some(date) => *counts.entry(date).or_default() += count,
+            None => undated += count,
+        }
+    }
+    Ok(undated)
+}
+
+/// Photo counts per day/week/month/year across the selected vaults
+#[tauri::command]
+pub async fn get_timeline(app: AppHandle, query: TimelineQuery) -> Result<Timeline, String> {
+    let zone = Zone::from_offset(query.utc_offset_minutes)?;
+    let app_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    let mut counts = BTreeMap::new();
+    let mut undated = 0;
+    for vault_id in photo_query::selected_vaults(&app, &query.filter)? {
+        let db_path = app_dir.join("vaults").join(&vault_id).join("manifest.db");
+        if !db_path.exists() {
+            continue;
+        }
+
+        let conn = crate::open_vault_db(&app, &vault_id, &db_path)?;
+        undated += count_vault(&conn, &query.filter, query.granularity, zone, &mut counts)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let buckets = counts
+        .into_iter()
+        .rev()
+        .map(|(start, count)| TimelineBucket {
+            key: bucket_key(start, query.granularity),
+            start: zone.start_of(start),
+            count,
+        })
+        .collect();
+
+    Ok(Timeline { buckets, undated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucketing_in_fixed_offset() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        for (i, captured) in [
+            Some("2024-03-31T23:30:00+00:00"), // 2024-04-01 01:30 at +02:00
+            Some("2024-04-03T10:00:00+00:00"),
+            Some("2024-03-30T10:00:00+00:00"),
+            None,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            conn.execute(
+                "INSERT INTO photos (id, filename, s3_key, tier, created_at, captured_at)
+                 VALUES (?1, 'a.jpg', 'k', 'Standard', NULL, ?2)",
+                rusqlite::params![format!("p{}", i), captured],
+            )
+            .unwrap();
+        }
+
+        let zone = Zone::from_offset(Some(120)).unwrap();
+        let mut counts = BTreeMap::new();
+        let undated = count_vault(&conn, &PhotoFilter::default(), TimelineGranularity::Month, zone, &mut counts).unwrap();
+        assert_eq!(undated, 1);
+        let months: Vec<(String, u32)> = counts
+            .iter()
+            .map(|(d, c)| (bucket_key(*d, TimelineGranularity::Month), *c))
+            .collect();
+        assert_eq!(months, vec![("2024-03".to_string(), 1), ("2024-04".to_string(), 2)]);
+
+        let mut counts = BTreeMap::new();
+        count_vault(&conn, &PhotoFilter::default(), TimelineGranularity::Week, zone, &mut counts).unwrap();
+        // 2024-04-01 is a Monday; 2024-03-30 falls in the week before
+        assert_eq!(counts.keys().map(|d| d.to_string()).collect::<Vec<_>>(), vec!["2024-03-25", "2024-04-01"]);
+        assert_eq!(zone.start_of(NaiveDate::from_ymd_opt(2024, 4, 1).unwrap()), "2024-04-01T00:00:00+02:00");
+    }
+}
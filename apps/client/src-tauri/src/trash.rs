@@ -0,0 +1,510 @@
+//! Trash
+//!
+//! Deleting a photo moves it to the trash (`photos.deleted_at`), which hides it from
+//! listings and search but keeps everything needed to restore it. Once the retention
+//! period ends, or when the user empties the trash, the photo is purged: all S3 object
+//! versions, local cache entries, embeddings, memory links and restore requests are
+//! removed, and a tombstone is synced so other devices drop the photo as well.
+
+use crate::db;
+use crate::embedding::EmbeddingState;
+use crate::manifest;
+use crate::pricing;
+use crate::recovery;
+use crate::storage::Storage;
+use crate::sync_daemon;
+use crate::AppState;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Mutex;
+
+/// Metadata key holding the trash retention in days
+const RETENTION_KEY: &str = "trash_retention_days";
+
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// Shorter retentions would purge soft-deleted photos right away
+const MIN_RETENTION_DAYS: u32 = 1;
+
+/// A photo in the trash
+#[derive(Debug, Serialize)]
+pub struct TrashedPhoto {
+    pub id: String,
+    pub filename: String,
+    pub media_type: String,
+    pub tier: String,
+    pub size_bytes: Option<i64>,
+    pub deleted_at: String,
+    /// When the photo will be purged automatically
+    pub expires_at: Option<String>,
+}
+
+/// What a purge will delete and cost, shown before the user confirms
+#[derive(Debug, Default, Serialize)]
+pub struct PurgePreview {
+    pub photo_count: u32,
+    pub total_bytes: i64,
+    /// Originals still inside their tier's minimum storage duration
+    pub early_deletion_count: u32,
+    /// Pro-rated charge S3 bills for deleting those originals early
+    pub early_deletion_cost_usd: f64,
+}
+
+/// Result of a purge
+#[derive(Debug, Default, Serialize)]
+pub struct PurgeStats {
+    pub photos_purged: u32,
+    /// Photos kept in the trash because their objects could not be deleted
+    pub failed: u32,
+}
+
+/// Everything needed to purge one photo
+struct PurgeTarget {
+    id: String,
+    s3_key: String,
+    thumbnail_key: Option<String>,
+    display_key: Option<String>,
+    motion_key: Option<String>,
+    tier: String,
+    size_bytes: i64,
+    created_at: Option<String>,
+}
+
+pub fn retention_days(conn: &Connection) -> u32 {
+    db::get_metadata(conn, RETENTION_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .filter(|days| *days >= MIN_RETENTION_DAYS)
+        .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+fn parse_time(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Utc))
+}
+
+/// Pro-rated early deletion charge for an original uploaded at `created_at`
+fn early_deletion_cost(
+    tier: &str,
+    size_bytes: i64,
+    created_at: Option<&str>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> f64 {
+    let Some(min_days) = pricing::minimum_storage_days(tier) else {
+        return 0.0;
+    };
+
+    // Without an upload date, assume the worst case (just uploaded)
+    let stored_days = created_at
+        .and_then(parse_time)
+        .map(|t| (now - t).num_seconds() as f64 / 86_400.0)
+        .unwrap_or(0.0)
+        .max(0.0);
+
+    let remaining_days = min_days - stored_days;
+    if remaining_days <= 0.0 {
+        return 0.0;
+    }
+
+    pricing::monthly_storage_cost(tier, size_bytes.max(0) as u64) * remaining_days / 30.0
+}
+
+/// Trashed photos matching `ids` (all trashed photos when None)
+fn load_targets(conn: &Connection, ids: Option<&[String]>) -> rusqlite::Result<Vec<PurgeTarget>> {
+    let mut query = String::from(
+        "SELECT id, s3_key, thumbnail_key, tier, COALESCE(size_bytes, 0), created_at, display_key,
+                motion_key
+         FROM photos WHERE deleted_at IS NOT NULL",
+    );
+    let mut params: Vec<&dyn rusqlite::ToSql> = Vec::new();
+    if let Some(ids) = ids {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        query.push_str(&format!(" AND id IN ({})", vec!["?"; ids.len()].join(", ")));
+        params.extend(ids.iter().map(|id| id as &dyn rusqlite::ToSql));
+    }
+
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt.query_map(params.as_slice(), |row| {
+        Ok(PurgeTarget {
+            id: row.get(0)?,
+            s3_key: row.get(1)?,
+            thumbnail_key: row.get(2)?,
+            tier: row.get(3)?,
+            size_bytes: row.get(4)?,
+            created_at: row.get(5)?,
+            display_key: row.get(6)?,
+            motion_key: row.get(7)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn preview(targets: &[PurgeTarget]) -> PurgePreview {
+    let now = chrono::Utc::now();
+    let mut preview = PurgePreview::default();
+
+    for target in targets {
+        preview.photo_count += 1;
+        preview.total_bytes += target.size_bytes;
+
+        let cost = early_deletion_cost(
+            &target.tier,
+            target.size_bytes,
+            target.created_at.as_deref(),
+            now,
+        );
+        if cost > 0.0 {
+            preview.early_deletion_count += 1;
+            preview.early_deletion_cost_usd += cost;
+        }
+    }
+
+    preview
+}
+
+/// Delete every S3 object of the targets, then remove them locally and record tombstones
+async fn purge(
+    app: &AppHandle,
+    storage: &Storage,
+    db: &Arc<Mutex<Option<Connection>>>,
+    targets: Vec<PurgeTarget>,
+) -> Result<PurgeStats, String> {
+    let mut stats = PurgeStats::default();
+    let mut purged_ids = Vec::new();
+
+    for target in targets {
+        // The original goes last, so a failure before it leaves the photo in the
+        // trash with its original intact
+        let mut keys = vec![recovery::sidecar_key(&target.id)];
+        keys.extend(target.thumbnail_key.clone().filter(|k| !k.is_empty()));
+        keys.extend(target.display_key.clone());
+        keys.extend(target.motion_key.clone());
+        keys.push(target.s3_key.clone());
+
+        let mut deleted = true;
+        for key in &keys {
+            if let Err(e) = storage.delete_all_versions(key).await {
+                log::warn!("[Trash] Failed to delete {} for photo {}: {}", key, target.id, e);
+                deleted = false;
+                break;
+            }
+        }
+
+        if deleted {
+            purged_ids.push(target.id);
+        } else {
+            stats.failed += 1;
+        }
+    }
+
+    {
+        let db_guard = db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let now = chrono::Utc::now().to_rfc3339();
+        for id in &purged_ids {
+            manifest::delete_photo_rows(&tx, id).map_err(|e| e.to_string())?;
+            tx.execute(
+                "INSERT OR IGNORE INTO photo_tombstones (id, purged_at) VALUES (?1, ?2)",
+                rusqlite::params![id, now],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    // Drop local copies
+    if let Some(cache_state) = app.try_state::<crate::CacheState>() {
+        if let Some(cache) = cache_state.thumbnail_cache.lock().await.as_ref() {
+            for id in &purged_ids {
+                cache.remove(id).ok();
+            }
+        }
+    }
+    if let Some(originals_state) = app.try_state::<crate::OriginalsCacheState>() {
+        if let Some(cache) = originals_state.cache.lock().await.as_ref() {
+            for id in &purged_ids {
+                cache.remove(id).ok();
+            }
+        }
+    }
+    if let Some(embedding_state) = app.try_state::<EmbeddingState>() {
+        let mut index = embedding_state.index.lock().await;
+        for id in &purged_ids {
+            index.remove(id);
+        }
+    }
+
+    stats.photos_purged = purged_ids.len() as u32;
+    if stats.photos_purged > 0 {
+        log::info!("[Trash] Purged {} photos", stats.photos_purged);
+        sync_daemon::notify_local_change(app).await;
+    }
+
+    Ok(stats)
+}
+
+/// Purge photos that have been in the trash longer than the retention period
+pub async fn purge_expired(
+    app: &AppHandle,
+    storage: &Storage,
+    db: &Arc<Mutex<Option<Connection>>>,
+) -> Result<PurgeStats, String> {
+    let targets = {
+        let db_guard = db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days(conn) as i64);
+
+        let mut stmt = conn
+            .prepare("SELECT id, deleted_at FROM photos WHERE deleted_at IS NOT NULL")
+            .map_err(|e| e.to_string())?;
+        let expired: Vec<String> = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?
+            .filter_map(|row| row.ok())
+            .filter(|(_, deleted_at)| parse_time(deleted_at).is_some_and(|t| t < cutoff))
+            .map(|(id, _)| id)
+            .collect();
+
+        load_targets(conn, Some(&expired)).map_err(|e| e.to_string())?
+    };
+
+    if targets.is_empty() {
+        return Ok(PurgeStats::default());
+    }
+    purge(app, storage, db, targets).await
+}
+
+/// Move photos to the trash. Returns how many were trashed.
+#[tauri::command]
+pub async fn trash_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    embedding_state: State<'_, EmbeddingState>,
+    ids: Vec<String>,
+) -> Result<u32, String> {
+    let trashed = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let mut trashed = 0;
+        for id in &ids {
+            trashed += conn
+                .execute(
+                    "UPDATE photos SET deleted_at = ?1, trash_updated_at = ?1
+                     WHERE id = ?2 AND deleted_at IS NULL",
+                    rusqlite::params![now, id],
+                )
+                .map_err(|e| e.to_string())? as u32;
+        }
+        trashed
+    };
+
+    // Keep trashed photos out of semantic search
+    {
+        let mut index = embedding_state.index.lock().await;
+        for id in &ids {
+            index.remove(id);
+        }
+    }
+
+    recovery::refresh_sidecars(&app, ids);
+    sync_daemon::notify_local_change(&app).await;
+    Ok(trashed)
+}
+
+/// Move photos out of the trash. Returns how many were restored.
+#[tauri::command]
+pub async fn restore_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    embedding_state: State<'_, EmbeddingState>,
+    ids: Vec<String>,
+) -> Result<u32, String> {
+    let (restored, embeddings) = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let mut restored = 0;
+        let mut embeddings = Vec::new();
+        for id in &ids {
+            let updated = conn
+                .execute(
+                    "UPDATE photos SET deleted_at = NULL, trash_updated_at = ?1
+                     WHERE id = ?2 AND deleted_at IS NOT NULL",
+                    rusqlite::params![now, id],
+                )
+                .map_err(|e| e.to_string())?;
+            if updated == 0 {
+                continue;
+            }
+            restored += 1;
+
+            let embedding: Option<Vec<u8>> = conn
+                .query_row(
+                    "SELECT embedding FROM embeddings WHERE photo_id = ?1",
+                    [id],
+                    |row| row.get(0),
+                )
+                .ok();
+            if let Some(bytes) = embedding {
+                embeddings.push((id.clone(), db::embedding_from_bytes(&bytes)));
+            }
+        }
+        (restored, embeddings)
+    };
+
+    {
+        let mut index = embedding_state.index.lock().await;
+        for (id, embedding) in embeddings {
+            index.insert_vec(id, embedding);
+        }
+    }
+
+    recovery::refresh_sidecars(&app, ids);
+    sync_daemon::notify_local_change(&app).await;
+    Ok(restored)
+}
+
+#[tauri::command]
+pub async fn list_trash(state: State<'_, AppState>) -> Result<Vec<TrashedPhoto>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    let retention = chrono::Duration::days(retention_days(conn) as i64);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, filename, media_type, tier, size_bytes, deleted_at
+             FROM photos WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let photos = stmt
+        .query_map([], |row| {
+            let deleted_at: String = row.get(5)?;
+            Ok(TrashedPhoto {
+                id: row.get(0)?,
+                filename: row.get(1)?,
+                media_type: row.get(2)?,
+                tier: row.get(3)?,
+                size_bytes: row.get(4)?,
+                expires_at: parse_time(&deleted_at).map(|t| (t + retention).to_rfc3339()),
+                deleted_at,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(photos)
+}
+
+#[tauri::command]
+pub async fn get_trash_retention(state: State<'_, AppState>) -> Result<u32, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    Ok(retention_days(conn))
+}
+
+#[tauri::command]
+pub async fn set_trash_retention(state: State<'_, AppState>, days: u32) -> Result<(), String> {
+    if days < MIN_RETENTION_DAYS {
+        return Err(format!("Trash retention must be at least {} day", MIN_RETENTION_DAYS));
+    }
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    db::set_metadata(conn, RETENTION_KEY, &days.to_string()).map_err(|e| e.to_string())
+}
+
+/// Summarize a purge (including Deep Archive early deletion charges) before confirming it.
+/// `ids` of None means the whole trash.
+#[tauri::command]
+pub async fn preview_purge(
+    state: State<'_, AppState>,
+    ids: Option<Vec<String>>,
+) -> Result<PurgePreview, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    let targets = load_targets(conn, ids.as_deref()).map_err(|e| e.to_string())?;
+    Ok(preview(&targets))
+}
+
+/// Permanently delete trashed photos. `ids` of None empties the whole trash.
+#[tauri::command]
+pub async fn purge_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    ids: Option<Vec<String>>,
+) -> Result<PurgeStats, String> {
+    let storage = state
+        .storage
+        .lock()
+        .await
+        .clone()
+        .ok_or("Storage not initialized")?;
+
+    let targets = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        load_targets(conn, ids.as_deref()).map_err(|e| e.to_string())?
+    };
+
+    purge(&app, &storage, &state.db, targets).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: i64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_early_deletion_cost() {
+        let now = chrono::Utc::now();
+        let days_ago = |days: i64| (now - chrono::Duration::days(days)).to_rfc3339();
+
+        // 30 of 180 days stored: 150 days (5 months) remain
+        let cost = early_deletion_cost("DeepArchive", 10 * GB, Some(&days_ago(30)), now);
+        assert!((cost - 10.0 * pricing::COST_GLACIER_DEEP * 5.0).abs() < 1e-9);
+
+        assert_eq!(early_deletion_cost("DeepArchive", GB, Some(&days_ago(200)), now), 0.0);
+        assert_eq!(early_deletion_cost("GlacierIR", GB, Some(&days_ago(90)), now), 0.0);
+        assert_eq!(early_deletion_cost("Standard", GB, Some(&days_ago(1)), now), 0.0);
+    }
+
+    #[test]
+    fn test_load_targets_only_returns_trashed_photos() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO photos (id, filename, s3_key, tier) VALUES ('p1', 'a.jpg', 'k1', 'Standard');
+             INSERT INTO photos (id, filename, s3_key, tier, deleted_at)
+             VALUES ('p2', 'b.jpg', 'k2', 'Standard', '2024-01-01T00:00:00+00:00');",
+        )
+        .unwrap();
+
+        let ids = |targets: Vec<PurgeTarget>| targets.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(ids(load_targets(&conn, None).unwrap()), vec!["p2"]);
+        assert!(load_targets(&conn, Some(&["p1".to_string()])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_retention_days() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        assert_eq!(retention_days(&conn), DEFAULT_RETENTION_DAYS);
+        db::set_metadata(&conn, RETENTION_KEY, "7").unwrap();
+        assert_eq!(retention_days(&conn), 7);
+        // A zero retention stored before it was rejected falls back to the default
+        db::set_metadata(&conn, RETENTION_KEY, "0").unwrap();
+        assert_eq!(retention_days(&conn), DEFAULT_RETENTION_DAYS);
+    }
+}
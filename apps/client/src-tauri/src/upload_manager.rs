@@ -2,15 +2,21 @@ use crate::cache::ThumbnailCache;
 use crate::crypto;
 use crate::exif_extractor;
 use crate::file_filter::{self, MediaType};
+use crate::library_import::{self, ImportedMetadata};
 use crate::media_processor::{self, Transcoder};
+use crate::motion_photo::{self, MotionSource};
+use crate::recovery;
 use crate::storage::{Storage, StorageClass};
-use crate::vault::{StorageTier, VaultConfig};
+use crate::upload_queue::{self, UploadQueueStore};
+use crate::vault::{OriginalPolicy, StorageTier, UploadVerification, VaultConfig};
+use crate::verification::{self, ExpectedObject, Verification};
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter};
@@ -21,6 +27,12 @@ use tokio::time::sleep;
 const FRESH_UPLOAD_FILE_THRESHOLD: usize = 1000;
 const FRESH_UPLOAD_SIZE_THRESHOLD: u64 = 20 * 1024 * 1024 * 1024; // 20GB
 
+/// How often unfinished queue items are written to the queue DB
+const QUEUE_PERSIST_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Memory budget is tracked in MiB-sized semaphore permits
+const MEMORY_PERMIT_BYTES: u64 = 1024 * 1024;
+
 /// Retry configuration
 const MAX_RETRY_ATTEMPTS: u32 = 3;
 const INITIAL_RETRY_DELAY_MS: u64 = 1000;
@@ -34,6 +46,28 @@ struct PreparedUpload {
     height: u32,
     raw_thumbnail: Option<Vec<u8>>,
     exif_metadata: Option<exif_extractor::ExifMetadata>,
+    /// How the original was produced (see `media_processor::ProcessedMedia::policy`)
+    original_policy: OriginalPolicy,
+    /// Optimized copy kept next to a lossless original
+    display_key: Option<String>,
+    enc_display: Option<Vec<u8>>,
+    /// Live Photo / Motion Photo video (see `motion_photo`)
+    motion_key: Option<String>,
+    enc_motion: Option<Vec<u8>>,
+    /// Vault key, reused to encrypt the metadata sidecar once the tier is known
+    vault_key: [u8; 32],
+}
+
+impl PreparedUpload {
+    /// Bytes held until the upload finishes
+    fn held_bytes(&self) -> u64 {
+        [&self.enc_thumbnail, &self.raw_thumbnail, &self.enc_display, &self.enc_motion]
+            .into_iter()
+            .flatten()
+            .map(|bytes| bytes.len() as u64)
+            .sum::<u64>()
+            + self.enc_original.len() as u64
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -44,10 +78,15 @@ pub enum UploadStatus {
     EncryptingThumbnail,
     UploadingOriginal { progress: f64 },
     UploadingThumbnail { progress: f64 },
+    /// Checking the stored objects before the photo is recorded
+    Verifying,
     Completed,
     Failed { error: String },
     Cancelled,
     Paused,
+    /// Same contents as a photo already in the vault, or as another queued item.
+    /// Not uploaded unless retried.
+    Duplicate { of_id: String, of_filename: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,16 +103,42 @@ pub struct UploadItem {
     pub bytes_uploaded: u64,
     #[serde(default)]
     pub retry_count: u32,
+    /// Keyed hash of the source file (see `crypto::content_hash`)
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Source file mtime (ms since epoch) when queued, to detect changes before a restored upload
+    #[serde(default)]
+    pub modified_at: Option<i64>,
+    /// Insertion order, used as the tie-breaker when picking the next item
+    #[serde(default)]
+    pub seq: u64,
+    /// Higher goes first regardless of the queue order (raised by "move to top")
+    #[serde(default)]
+    pub priority: i64,
+    /// Import session the item was added in (see `BatchProgress`)
+    #[serde(default)]
+    pub batch_id: Option<String>,
+    #[serde(default)]
+    pub batch_name: Option<String>,
+    /// Live Photo / Motion Photo video, uploaded as part of this item
+    #[serde(default)]
+    pub motion: Option<MotionSource>,
+    /// Sidecar metadata from a Google Takeout / Apple Photos export
+    #[serde(default)]
+    pub imported: Option<ImportedMetadata>,
     /// Pre-generated frames for video thumbnailing (from Frontend)
     #[serde(skip)]
     pub pre_generated_frames: Option<Vec<Vec<u8>>>,
 }
 
 impl UploadItem {
+    /// `hasher` (hash key and cache) enables content hashing for duplicate detection.
+    /// Hashing reads the whole file, so call this off the async runtime.
     pub fn new(
         path: PathBuf,
         fresh_upload: bool,
         pre_generated_frames: Option<Vec<Vec<u8>>>,
+        hasher: Option<(&[u8; 32], &ContentHashCache)>,
     ) -> Result<Self> {
         let filename = path
             .file_name()
@@ -81,6 +146,11 @@ impl UploadItem {
             .unwrap_or_else(|| "unknown".to_string());
         let size = file_filter::get_file_size(&path)?;
         let media_type = file_filter::detect_media_type(&path)?;
+        let modified_at = std::fs::metadata(&path).ok().and_then(|m| modified_millis(&m));
+        let content_hash = hasher
+            .map(|(key, cache)| cache.take_or_hash(&path, key))
+            .transpose()
+            .context("Failed to hash file")?;
 
         Ok(Self {
             id: uuid::Uuid::new_v4().to_string(),
@@ -93,18 +163,436 @@ impl UploadItem {
             fresh_upload,
             bytes_uploaded: 0,
             retry_count: 0,
+            content_hash,
+            modified_at,
+            seq: 0,
+            priority: 0,
+            batch_id: None,
+            batch_name: None,
+            motion: None,
+            imported: None,
             pre_generated_frames,
         })
     }
 }
 
+/// Whether a batch is too large for Fresh Upload (Standard storage until the lifecycle transition)
+pub fn exceeds_fresh_upload_limits(count: usize, total_size: u64) -> bool {
+    count > FRESH_UPLOAD_FILE_THRESHOLD || total_size > FRESH_UPLOAD_SIZE_THRESHOLD
+}
+
+/// Storage class for an uploaded original (None is Standard)
+/// - Fresh uploads: Standard (lifecycle will transition after 60 days)
+/// - Audio: GLACIER_IR (frequently accessed, no archive benefit)
+/// - Images/Videos non-fresh: vault's configured tier (DEEP_ARCHIVE or GLACIER_IR)
+pub fn original_storage_class(
+    media_type: MediaType,
+    fresh_upload: bool,
+    storage_tier: StorageTier,
+) -> Option<StorageClass> {
+    if fresh_upload {
+        return None;
+    }
+    match media_type {
+        MediaType::Audio => Some(StorageClass::GlacierIr),
+        MediaType::Image | MediaType::Video => Some(match storage_tier {
+            StorageTier::DeepArchive => StorageClass::DeepArchive,
+            StorageTier::GlacierInstantRetrieval => StorageClass::GlacierIr,
+        }),
+    }
+}
+
+/// `photos.tier` value of an original stored with `class`
+pub fn tier_label(class: Option<&StorageClass>) -> &'static str {
+    match class {
+        None => "Standard",
+        Some(StorageClass::DeepArchive) => "DeepArchive",
+        Some(StorageClass::GlacierIr) => "GlacierIR",
+        Some(_) => "Unknown",
+    }
+}
+
+/// Content hashes from the latest folder import preview, so queueing the import
+/// doesn't read every file again. Entries are only used while the file's size and
+/// modification time are unchanged.
+#[derive(Default)]
+pub struct ContentHashCache {
+    entries: std::sync::Mutex<HashMap<PathBuf, CachedHash>>,
+}
+
+struct CachedHash {
+    key: [u8; 32],
+    size: u64,
+    modified_at: Option<i64>,
+    hash: String,
+}
+
+impl ContentHashCache {
+    /// Replace the cache with the hashes of `paths`. Returns them in the same order.
+    pub fn hash_all(&self, paths: &[PathBuf], key: &[u8; 32]) -> Vec<Option<String>> {
+        let mut entries = HashMap::new();
+        let hashes = paths
+            .iter()
+            .map(|path| {
+                let meta = std::fs::metadata(path).ok()?;
+                let hash = crypto::content_hash(path, key).ok()?;
+                entries.insert(
+                    path.clone(),
+                    CachedHash {
+                        key: *key,
+                        size: meta.len(),
+                        modified_at: modified_millis(&meta),
+                        hash: hash.clone(),
+                    },
+                );
+                Some(hash)
+            })
+            .collect();
+        *self.entries.lock().unwrap_or_else(|e| e.into_inner()) = entries;
+        hashes
+    }
+
+    /// Hash of `path`, cached for a later `take_or_hash`
+    pub fn get_or_hash(&self, path: &Path, key: &[u8; 32]) -> Result<String> {
+        let hash = self.take_or_hash(path, key)?;
+        let meta = std::fs::metadata(path)?;
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).insert(
+            path.to_path_buf(),
+            CachedHash {
+                key: *key,
+                size: meta.len(),
+                modified_at: modified_millis(&meta),
+                hash: hash.clone(),
+            },
+        );
+        Ok(hash)
+    }
+
+    /// Hash of `path`, taken from the cache if the file is unchanged
+    pub fn take_or_hash(&self, path: &Path, key: &[u8; 32]) -> Result<String> {
+        let cached = self.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(path);
+        if let Some(cached) = cached.filter(|c| c.key == *key) {
+            let meta = std::fs::metadata(path)?;
+            if cached.size == meta.len() && cached.modified_at == modified_millis(&meta) {
+                return Ok(cached.hash);
+            }
+        }
+        crypto::content_hash(path, key)
+    }
+}
+
+/// Key for the content hashes of a vault's files (see `crypto::content_hash`)
+pub fn content_hash_key(config: &VaultConfig) -> Option<[u8; 32]> {
+    let vault_key: [u8; 32] = BASE64.decode(&config.vault_key).ok()?.try_into().ok()?;
+    Some(crypto::derive_content_hash_key(&vault_key))
+}
+
+/// ID and filename of a photo in the vault (not in the trash) with this content hash
+pub fn find_vault_duplicate(conn: &Connection, content_hash: &str) -> rusqlite::Result<Option<(String, String)>> {
+    use rusqlite::OptionalExtension;
+
+    conn.query_row(
+        "SELECT id, filename FROM photos WHERE content_hash = ?1 AND deleted_at IS NULL LIMIT 1",
+        [content_hash],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+/// File modification time in milliseconds since the Unix epoch
+pub fn modified_millis(meta: &std::fs::Metadata) -> Option<i64> {
+    meta.modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as i64)
+}
+
+/// Limits of the upload pipeline: items are prepared (decoded, transcoded, encrypted)
+/// on the blocking thread pool while others upload
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct UploadConcurrency {
+    /// Items prepared at once
+    pub prepare: usize,
+    /// Items uploaded at once
+    pub upload: usize,
+    /// Cap on bytes held by items between preparing and finishing their upload.
+    /// Estimated from the source file size until an item is prepared, then from
+    /// the encrypted objects it holds.
+    pub memory_budget_bytes: u64,
+}
+
+impl Default for UploadConcurrency {
+    fn default() -> Self {
+        // Logical cores (min 2, max 8 for safety)
+        let cpu_count = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4);
+        Self {
+            prepare: cpu_count.clamp(2, 8),
+            upload: 6,
+            memory_budget_bytes: 1024 * 1024 * 1024, // 1GB
+        }
+    }
+}
+
+impl UploadConcurrency {
+    /// Clamp to sane values
+    pub fn normalized(self) -> Self {
+        Self {
+            prepare: self.prepare.clamp(1, 32),
+            upload: self.upload.clamp(1, 32),
+            memory_budget_bytes: self.memory_budget_bytes.clamp(64 * MEMORY_PERMIT_BYTES, 64 * 1024 * MEMORY_PERMIT_BYTES),
+        }
+    }
+
+    fn memory_permits(&self) -> u32 {
+        (self.memory_budget_bytes / MEMORY_PERMIT_BYTES) as u32
+    }
+
+    /// Permits reserved for an item. Items larger than the whole budget take all of
+    /// it, so they still run, one at a time.
+    fn item_permits(&self, size: u64) -> u32 {
+        (size.div_ceil(MEMORY_PERMIT_BYTES).max(1) as u32).min(self.memory_permits())
+    }
+}
+
+/// Semaphores of a running pipeline. `set_concurrency` resizes them, so new limits
+/// apply without waiting for the queue to drain.
+struct PipelineSlots {
+    prepare: Arc<tokio::sync::Semaphore>,
+    upload: Arc<tokio::sync::Semaphore>,
+    memory: Arc<tokio::sync::Semaphore>,
+    limits: std::sync::Mutex<UploadConcurrency>,
+}
+
+impl PipelineSlots {
+    fn new(concurrency: UploadConcurrency) -> Self {
+        use tokio::sync::Semaphore;
+        Self {
+            prepare: Arc::new(Semaphore::new(concurrency.prepare)),
+            upload: Arc::new(Semaphore::new(concurrency.upload)),
+            memory: Arc::new(Semaphore::new(concurrency.memory_permits() as usize)),
+            limits: std::sync::Mutex::new(concurrency),
+        }
+    }
+
+    fn resize(&self, concurrency: UploadConcurrency) {
+        let mut limits = self.limits.lock().unwrap_or_else(|e| e.into_inner());
+        resize_semaphore(&self.prepare, limits.prepare, concurrency.prepare);
+        resize_semaphore(&self.upload, limits.upload, concurrency.upload);
+        resize_semaphore(
+            &self.memory,
+            limits.memory_permits() as usize,
+            concurrency.memory_permits() as usize,
+        );
+        *limits = concurrency;
+    }
+
+    fn item_permits(&self, size: u64) -> u32 {
+        self.limits.lock().unwrap_or_else(|e| e.into_inner()).item_permits(size)
+    }
+
+    /// Adjust an item's memory reservation to what its prepared objects hold
+    async fn reserve_prepared(
+        &self,
+        mut reserved: tokio::sync::OwnedSemaphorePermit,
+        held_bytes: u64,
+    ) -> Result<tokio::sync::OwnedSemaphorePermit> {
+        let needed = self.item_permits(held_bytes) as usize;
+        let current = reserved.num_permits();
+        if needed < current {
+            drop(reserved.split(current - needed));
+        } else if needed > current {
+            match Arc::clone(&self.memory).try_acquire_many_owned((needed - current) as u32) {
+                Ok(extra) => reserved.merge(extra),
+                // Waiting while holding the estimate could deadlock with other items
+                // doing the same, so give it back and wait for the whole amount
+                Err(_) => {
+                    drop(reserved);
+                    reserved = Arc::clone(&self.memory).acquire_many_owned(needed as u32).await?;
+                }
+            }
+        }
+        Ok(reserved)
+    }
+}
+
+/// Grow a semaphore right away; shrink it as permits are returned
+fn resize_semaphore(semaphore: &Arc<tokio::sync::Semaphore>, from: usize, to: usize) {
+    if to > from {
+        semaphore.add_permits(to - from);
+    } else if to < from {
+        let semaphore = Arc::clone(semaphore);
+        tokio::spawn(async move {
+            if let Ok(permits) = semaphore.acquire_many_owned((from - to) as u32).await {
+                permits.forget();
+            }
+        });
+    }
+}
+
+/// Order in which pending items are uploaded. Prioritized items always go first.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOrder {
+    /// In the order they were added
+    #[default]
+    Queued,
+    SmallestFirst,
+    /// Oldest source file first (by modification time)
+    OldestFirst,
+}
+
+/// Ordering of two queue items: priority, then `order`, then insertion order
+pub fn compare_items(a: &UploadItem, b: &UploadItem, order: QueueOrder) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    b.priority
+        .cmp(&a.priority)
+        .then_with(|| match order {
+            QueueOrder::Queued => Ordering::Equal,
+            QueueOrder::SmallestFirst => a.size.cmp(&b.size),
+            // Files without a modification time go last
+            QueueOrder::OldestFirst => match (a.modified_at, b.modified_at) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+        })
+        .then_with(|| a.seq.cmp(&b.seq))
+}
+
+/// Whether an item is done, one way or another
+fn is_finished(status: &UploadStatus) -> bool {
+    matches!(
+        status,
+        UploadStatus::Completed
+            | UploadStatus::Failed { .. }
+            | UploadStatus::Cancelled
+            | UploadStatus::Duplicate { .. }
+    )
+}
+
+fn is_active(status: &UploadStatus) -> bool {
+    matches!(
+        status,
+        UploadStatus::Processing
+            | UploadStatus::EncryptingOriginal
+            | UploadStatus::EncryptingThumbnail
+            | UploadStatus::UploadingOriginal { .. }
+            | UploadStatus::UploadingThumbnail { .. }
+            | UploadStatus::Verifying
+    )
+}
+
+/// Aggregate progress of the items added in one import session
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchProgress {
+    pub id: String,
+    pub name: String,
+    pub total_count: usize,
+    pub completed_count: usize,
+    pub failed_count: usize,
+    pub pending_count: usize,
+    pub paused_count: usize,
+    /// Items currently being prepared or uploaded
+    pub active_count: usize,
+    /// Bytes still to be uploaded or already uploaded; cancelled, failed and duplicate items
+    /// don't count
+    pub total_bytes: u64,
+    pub uploaded_bytes: u64,
+    /// 0.0 - 1.0
+    pub progress: f64,
+}
+
+impl BatchProgress {
+    fn add(&mut self, item: &UploadItem) {
+        self.total_count += 1;
+        match &item.status {
+            UploadStatus::Completed => self.completed_count += 1,
+            UploadStatus::Failed { .. } => self.failed_count += 1,
+            UploadStatus::Pending => self.pending_count += 1,
+            UploadStatus::Paused => self.paused_count += 1,
+            status if is_active(status) => self.active_count += 1,
+            _ => {}
+        }
+        if !matches!(
+            item.status,
+            UploadStatus::Cancelled | UploadStatus::Failed { .. } | UploadStatus::Duplicate { .. }
+        ) {
+            self.total_bytes += item.size;
+            self.uploaded_bytes += if matches!(item.status, UploadStatus::Completed) {
+                item.size
+            } else {
+                item.bytes_uploaded.min(item.size)
+            };
+        }
+    }
+
+    /// Whether the batch still has items to upload
+    pub fn is_unfinished(&self) -> bool {
+        self.pending_count + self.paused_count + self.active_count > 0
+    }
+}
+
+/// Progress per batch, in the order the batches were added. Items queued before
+/// batches existed share one unnamed batch.
+pub fn batch_progress<'a>(items: impl IntoIterator<Item = &'a UploadItem>) -> Vec<BatchProgress> {
+    // Batch ID -> (first seq, progress)
+    let mut batches: HashMap<String, (u64, BatchProgress)> = HashMap::new();
+    for item in items {
+        let id = item.batch_id.clone().unwrap_or_default();
+        let (first_seq, batch) = batches.entry(id.clone()).or_insert_with(|| {
+            (
+                item.seq,
+                BatchProgress {
+                    id,
+                    name: item.batch_name.clone().unwrap_or_else(|| "Uploads".to_string()),
+                    ..Default::default()
+                },
+            )
+        });
+        *first_seq = (*first_seq).min(item.seq);
+        batch.add(item);
+    }
+
+    let mut batches: Vec<(u64, BatchProgress)> = batches.into_values().collect();
+    batches.sort_by_key(|(seq, _)| *seq);
+    batches
+        .into_iter()
+        .map(|(_, mut batch)| {
+            batch.progress = if batch.total_bytes > 0 {
+                batch.uploaded_bytes as f64 / batch.total_bytes as f64
+            } else if batch.total_count > 0 && !batch.is_unfinished() {
+                1.0
+            } else {
+                0.0
+            };
+            batch
+        })
+        .collect()
+}
+
+/// The batch the tray reports: the first one with uploads left, or the latest one
+fn tray_batch(batches: &[BatchProgress]) -> Option<&BatchProgress> {
+    batches.iter().find(|b| b.is_unfinished()).or_else(|| batches.last())
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct QueueState {
+    /// In upload order
     pub items: Vec<UploadItem>,
     pub total_size: u64,
     pub completed_count: usize,
     pub failed_count: usize,
     pub pending_count: usize,
+    pub duplicate_count: usize,
+    pub concurrency: UploadConcurrency,
+    pub order: QueueOrder,
+    pub batches: Vec<BatchProgress>,
 }
 
 pub struct UploadManager {
@@ -118,6 +606,15 @@ pub struct UploadManager {
     thumbnail_cache: Arc<Mutex<Option<ThumbnailCache>>>,
     cancel_tx: mpsc::Sender<String>,
     is_processing: Arc<RwLock<bool>>,
+    concurrency: Arc<RwLock<UploadConcurrency>>,
+    /// Slots of the running pipeline, if any
+    pipeline: Arc<RwLock<Option<Arc<PipelineSlots>>>>,
+    hash_cache: Arc<ContentHashCache>,
+    order: Arc<RwLock<QueueOrder>>,
+    /// Next `UploadItem::seq`
+    next_seq: AtomicU64,
+    /// Vault whose queue is persisted, once `restore_queue` has run
+    vault_id: Option<String>,
 }
 
 impl UploadManager {
@@ -142,27 +639,108 @@ impl UploadManager {
                 thumbnail_cache,
                 cancel_tx,
                 is_processing: Arc::new(RwLock::new(false)),
+                concurrency: Arc::new(RwLock::new(UploadConcurrency::default())),
+                pipeline: Arc::new(RwLock::new(None)),
+                hash_cache: Arc::new(ContentHashCache::default()),
+                order: Arc::new(RwLock::new(QueueOrder::default())),
+                next_seq: AtomicU64::new(0),
+                vault_id: None,
             },
             cancel_rx,
         )
     }
 
+    pub fn vault_id(&self) -> Option<&str> {
+        self.vault_id.as_deref()
+    }
+
+    /// Restore the vault's saved queue and keep it persisted from now on.
+    /// Returns the number of restored items ready to upload.
+    pub async fn restore_queue(&mut self, mut store: UploadQueueStore) -> Result<usize> {
+        let mut items = store.load()?;
+        for item in &mut items {
+            upload_queue::revalidate(item);
+        }
+        let pending = items
+            .iter()
+            .filter(|i| matches!(i.status, UploadStatus::Pending))
+            .count();
+        if let Some(max_seq) = items.iter().map(|i| i.seq).max() {
+            self.next_seq.fetch_max(max_seq + 1, AtomicOrdering::Relaxed);
+        }
+
+        {
+            let mut queue = self.queue.write().await;
+            let mut paused = self.paused_ids.write().await;
+            for item in items {
+                if matches!(item.status, UploadStatus::Paused) {
+                    paused.insert(item.id.clone());
+                }
+                queue.insert(item.id.clone(), item);
+            }
+        }
+        self.vault_id = Some(store.vault_id().to_string());
+
+        // The task holds a weak reference so it ends when the manager is replaced
+        let queue = Arc::downgrade(&self.queue);
+        tokio::spawn(async move {
+            loop {
+                sleep(QUEUE_PERSIST_INTERVAL).await;
+                let Some(queue) = queue.upgrade() else {
+                    break;
+                };
+                let items: Vec<UploadItem> = queue
+                    .read()
+                    .await
+                    .values()
+                    .filter_map(upload_queue::persisted_form)
+                    .collect();
+                if let Err(e) = store.sync(&items) {
+                    log::warn!("[UploadQueue] Failed to persist queue: {}", e);
+                }
+            }
+        });
+
+        if pending > 0 {
+            log::info!("[UploadQueue] Restored {} pending uploads", pending);
+        }
+        self.emit_queue_changed().await;
+        Ok(pending)
+    }
+
     /// Checks if Fresh Upload should be auto-toggled off based on file count/size
     pub fn should_disable_fresh_upload(&self, files: &[UploadItem]) -> bool {
-        let total_count = files.len();
         let total_size: u64 = files.iter().map(|f| f.size).sum();
-        total_count > FRESH_UPLOAD_FILE_THRESHOLD || total_size > FRESH_UPLOAD_SIZE_THRESHOLD
+        exceeds_fresh_upload_limits(files.len(), total_size)
     }
 
-    /// Add files to the upload queue
+    /// Add files to the upload queue as a new batch, named `batch_name` or after the
+    /// current time. Files with the same contents as a photo in the vault or a queued
+    /// item are dropped if `skip_duplicates`, otherwise queued as `Duplicate`.
+    /// Returns the queued items and the number of skipped duplicates.
     pub async fn add_files(
         &self,
         paths: Vec<PathBuf>,
         fresh_upload: bool,
         thumbnails: Option<HashMap<String, Vec<String>>>,
-    ) -> Result<Vec<UploadItem>> {
-        let mut items = Vec::new();
-        let mut errors = Vec::new();
+        skip_duplicates: bool,
+        batch_name: Option<String>,
+    ) -> Result<(Vec<UploadItem>, usize)> {
+        self.add_files_with_metadata(paths, fresh_upload, thumbnails, skip_duplicates, batch_name, HashMap::new())
+            .await
+    }
+
+    /// `add_files`, with sidecar metadata of a library export for some of the paths
+    pub async fn add_files_with_metadata(
+        &self,
+        paths: Vec<PathBuf>,
+        fresh_upload: bool,
+        thumbnails: Option<HashMap<String, Vec<String>>>,
+        skip_duplicates: bool,
+        batch_name: Option<String>,
+        mut imported: HashMap<PathBuf, ImportedMetadata>,
+    ) -> Result<(Vec<UploadItem>, usize)> {
+        let mut sources = Vec::new();
 
         for path in paths {
             let path_str = path.to_string_lossy().to_string();
@@ -195,13 +773,57 @@ impl UploadManager {
             } else {
                 None
             };
+            sources.push((path, frames));
+        }
 
-            match UploadItem::new(path.clone(), fresh_upload, frames) {
-                Ok(item) => items.push(item),
+        // Hashing reads every file; keep it off the async workers
+        let hash_key = self.content_hash_key().await;
+        let hash_cache = Arc::clone(&self.hash_cache);
+        let created = tokio::task::spawn_blocking(move || {
+            // Live Photo videos are uploaded with their still instead of on their own
+            let paths: Vec<PathBuf> = sources.iter().map(|(p, _)| p.clone()).collect();
+            let mut companions = motion_photo::pair_live_photos(&paths);
+            let paired: HashSet<PathBuf> = companions.values().cloned().collect();
+
+            sources
+                .into_iter()
+                .filter(|(path, _)| !paired.contains(path))
+                .map(|(path, frames)| {
+                    let result = UploadItem::new(path.clone(), fresh_upload, frames, hash_key.as_ref().map(|key| (key, &*hash_cache))).map(|mut item| {
+                        item.motion = match companions.remove(&path) {
+                            Some(video) => Some(MotionSource::Companion { path: video }),
+                            None => motion_photo::embedded_video(&path),
+                        };
+                        item.imported = imported.remove(&path);
+                        item
+                    });
+                    (path, result)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await?;
+
+        let batch_id = uuid::Uuid::new_v4().to_string();
+        let batch_name = batch_name
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or_else(|| format!("Import {}", chrono::Local::now().format("%Y-%m-%d %H:%M")));
+
+        let mut items = Vec::new();
+        let mut errors = Vec::new();
+        for (path, result) in created {
+            match result {
+                Ok(mut item) => {
+                    item.seq = self.next_seq.fetch_add(1, AtomicOrdering::Relaxed);
+                    item.batch_id = Some(batch_id.clone());
+                    item.batch_name = Some(batch_name.clone());
+                    items.push(item);
+                }
                 Err(e) => errors.push((path, e.to_string())), // Log error but continue
             }
         }
 
+        let skipped = self.mark_duplicates(&mut items, skip_duplicates).await;
+
         // Add to queue
         {
             let mut queue = self.queue.write().await;
@@ -226,13 +848,74 @@ impl UploadManager {
                 .ok();
         }
 
-        Ok(items)
+        Ok((items, skipped))
+    }
+
+    /// Key for content hashes of the current vault, if one is loaded
+    async fn content_hash_key(&self) -> Option<[u8; 32]> {
+        content_hash_key(self.config.lock().await.as_ref()?)
+    }
+
+    /// Hashes computed for a folder import preview, reused by `add_files`
+    pub fn hash_cache(&self) -> Arc<ContentHashCache> {
+        Arc::clone(&self.hash_cache)
+    }
+
+    /// Status of a queued item, if it is still in the queue
+    pub async fn item_status(&self, id: &str) -> Option<UploadStatus> {
+        self.queue.read().await.get(id).map(|i| i.status.clone())
+    }
+
+    /// Mark (or drop, if `skip`) new items whose contents are already in the vault,
+    /// already queued, or earlier in `items`. Returns the number dropped.
+    async fn mark_duplicates(&self, items: &mut Vec<UploadItem>, skip: bool) -> usize {
+        // Hash -> (id, filename) of items that will be uploaded
+        let mut queued: HashMap<String, (String, String)> = HashMap::new();
+        for item in self.queue.read().await.values() {
+            let active = !matches!(
+                item.status,
+                UploadStatus::Failed { .. } | UploadStatus::Cancelled | UploadStatus::Duplicate { .. }
+            );
+            if let (true, Some(hash)) = (active, &item.content_hash) {
+                queued.insert(hash.clone(), (item.id.clone(), item.filename.clone()));
+            }
+        }
+
+        let db_guard = self.db.lock().await;
+        let before = items.len();
+        items.retain_mut(|item| {
+            let Some(hash) = item.content_hash.clone() else {
+                return true;
+            };
+            let in_vault = db_guard.as_ref().and_then(|conn| match find_vault_duplicate(conn, &hash) {
+                Ok(found) => found,
+                Err(e) => {
+                    log::warn!("[Upload] Duplicate lookup failed: {}", e);
+                    None
+                }
+            });
+
+            match in_vault.or_else(|| queued.get(&hash).cloned()) {
+                Some(_) if skip => false,
+                Some((of_id, of_filename)) => {
+                    item.status = UploadStatus::Duplicate { of_id, of_filename };
+                    true
+                }
+                None => {
+                    queued.insert(hash, (item.id.clone(), item.filename.clone()));
+                    true
+                }
+            }
+        });
+        before - items.len()
     }
 
     /// Get current queue state
     pub async fn get_state(&self) -> QueueState {
+        let order = *self.order.read().await;
         let queue = self.queue.read().await;
-        let items: Vec<UploadItem> = queue.values().cloned().collect();
+        let mut items: Vec<UploadItem> = queue.values().cloned().collect();
+        items.sort_by(|a, b| compare_items(a, b, order));
         let total_size = items.iter().map(|i| i.size).sum();
         let completed_count = items
             .iter()
@@ -246,14 +929,125 @@ impl UploadManager {
             .iter()
             .filter(|i| matches!(i.status, UploadStatus::Pending))
             .count();
+        let duplicate_count = items
+            .iter()
+            .filter(|i| matches!(i.status, UploadStatus::Duplicate { .. }))
+            .count();
 
         QueueState {
-            items,
             total_size,
             completed_count,
             failed_count,
             pending_count,
+            duplicate_count,
+            concurrency: *self.concurrency.read().await,
+            order,
+            batches: batch_progress(&items),
+            items,
+        }
+    }
+
+    /// Change the pipeline limits. A running pipeline picks up higher limits right
+    /// away; lower ones take effect as running items release their slots.
+    pub async fn set_concurrency(&self, concurrency: UploadConcurrency) {
+        {
+            let concurrency = concurrency.normalized();
+            let mut current = self.concurrency.write().await;
+            if let Some(slots) = self.pipeline.read().await.as_ref() {
+                slots.resize(concurrency);
+            }
+            *current = concurrency;
+        }
+        self.emit_queue_changed().await;
+    }
+
+    /// Change the order pending items are picked in. Applies to the next pick.
+    pub async fn set_order(&self, order: QueueOrder) {
+        *self.order.write().await = order;
+        self.emit_queue_changed().await;
+    }
+
+    /// Upload an item before everything else that is pending
+    pub async fn move_to_top(&self, id: &str) {
+        {
+            let mut queue = self.queue.write().await;
+            let top = queue.values().map(|i| i.priority).max().unwrap_or(0);
+            if let Some(item) = queue.get_mut(id) {
+                item.priority = top + 1;
+            }
+        }
+        self.emit_queue_changed().await;
+    }
+
+    /// IDs of the batch's items whose status matches
+    async fn batch_item_ids(&self, batch_id: &str, filter: impl Fn(&UploadStatus) -> bool) -> Vec<String> {
+        self.queue
+            .read()
+            .await
+            .values()
+            .filter(|i| i.batch_id.as_deref().unwrap_or_default() == batch_id && filter(&i.status))
+            .map(|i| i.id.clone())
+            .collect()
+    }
+
+    /// Pause the batch's pending items. Items already uploading finish.
+    pub async fn pause_batch(&self, batch_id: &str) {
+        let ids = self
+            .batch_item_ids(batch_id, |s| matches!(s, UploadStatus::Pending))
+            .await;
+        self.paused_ids.write().await.extend(ids.iter().cloned());
+        self.set_statuses(&ids, UploadStatus::Paused).await;
+    }
+
+    /// Resume the batch's paused items
+    pub async fn resume_batch(&self, batch_id: &str) {
+        let ids = self
+            .batch_item_ids(batch_id, |s| matches!(s, UploadStatus::Paused))
+            .await;
+        {
+            let mut paused = self.paused_ids.write().await;
+            for id in &ids {
+                paused.remove(id);
+            }
+        }
+        self.set_statuses(&ids, UploadStatus::Pending).await;
+    }
+
+    /// Cancel every unfinished item of the batch
+    pub async fn cancel_batch(&self, batch_id: &str) {
+        let ids = self.batch_item_ids(batch_id, |s| !is_finished(s)).await;
+        self.cancelled_ids.write().await.extend(ids.iter().cloned());
+        for id in &ids {
+            // Don't wait on a full channel for large batches; the cancelled set is authoritative
+            self.cancel_tx.try_send(id.clone()).ok();
+        }
+        self.set_statuses(&ids, UploadStatus::Cancelled).await;
+        self.forget_memberships(&ids).await;
+    }
+
+    /// Drop the imported album memberships of uploads that won't produce a photo
+    async fn forget_memberships(&self, ids: &[String]) {
+        if ids.is_empty() {
+            return;
+        }
+        let db_guard = self.db.lock().await;
+        if let Some(conn) = db_guard.as_ref() {
+            if let Err(e) = crate::library_import::forget_pending(conn, ids) {
+                log::warn!("[Upload] Failed to drop pending album memberships: {}", e);
+            }
+        }
+    }
+
+    async fn set_statuses(&self, ids: &[String], status: UploadStatus) {
+        {
+            let mut queue = self.queue.write().await;
+            for id in ids {
+                if let Some(item) = queue.get_mut(id) {
+                    item.status = status.clone();
+                }
+            }
         }
+        self.emit_queue_changed().await;
     }
 
     /// Pause a specific upload
@@ -279,14 +1073,15 @@ impl UploadManager {
             item.status = UploadStatus::Cancelled;
         }
         drop(queue);
+        self.forget_memberships(&[id.to_string()]).await;
         self.emit_queue_changed().await;
     }
 
-    /// Retry a failed upload
+    /// Retry a failed upload, or upload a duplicate anyway
     pub async fn retry(&self, id: &str) {
         let mut queue = self.queue.write().await;
         if let Some(item) = queue.get_mut(id) {
-            if matches!(item.status, UploadStatus::Failed { .. }) {
+            if matches!(item.status, UploadStatus::Failed { .. } | UploadStatus::Duplicate { .. }) {
                 item.status = UploadStatus::Pending;
                 item.progress = 0.0;
                 item.bytes_uploaded = 0;
@@ -311,6 +1106,7 @@ impl UploadManager {
                         | UploadStatus::EncryptingThumbnail
                         | UploadStatus::UploadingOriginal { .. }
                         | UploadStatus::UploadingThumbnail { .. }
+                        | UploadStatus::Verifying
                 ) {
                     // If active, we should cancel first.
                     // But since this is a tailored "remove" for the UI list, usually user removes PEnding items.
@@ -323,6 +1119,7 @@ impl UploadManager {
         // Also remove from cancelled/paused sets to clean up
         self.paused_ids.write().await.remove(id);
         self.cancelled_ids.write().await.remove(id);
+        self.forget_memberships(&[id.to_string()]).await;
 
         // If it was being processed, the cancellation logic (if invoked) would handle it,
         // but if we just ripped it out of the HashMap, the background task might fail when it tries to update status?
@@ -345,13 +1142,17 @@ impl UploadManager {
     /// Remove completed/failed items from queue
     pub async fn clear_finished(&self) {
         let mut queue = self.queue.write().await;
-        queue.retain(|_, item| {
-            !matches!(
-                item.status,
-                UploadStatus::Completed | UploadStatus::Failed { .. } | UploadStatus::Cancelled
-            )
+        let mut unfinished = Vec::new();
+        queue.retain(|id, item| match item.status {
+            UploadStatus::Completed | UploadStatus::Duplicate { .. } => false,
+            UploadStatus::Failed { .. } | UploadStatus::Cancelled => {
+                unfinished.push(id.clone());
+                false
+            }
+            _ => true,
         });
         drop(queue);
+        self.forget_memberships(&unfinished).await;
         self.emit_queue_changed().await;
     }
 
@@ -365,10 +1166,10 @@ impl UploadManager {
         }
     }
 
-    /// Start processing the upload queue in the background (parallel with concurrency limit)
+    /// Start processing the upload queue in the background as a pipeline: up to
+    /// `prepare` items are processed on the CPU pool while up to `upload` items upload,
+    /// within the memory budget
     pub async fn start_processing(&self) -> Result<()> {
-        use tokio::sync::Semaphore;
-        
         // Check if already processing
         {
             let is_processing = self.is_processing.read().await;
@@ -390,41 +1191,51 @@ impl UploadManager {
         let thumbnail_cache = Arc::clone(&self.thumbnail_cache);
         let app_handle = self.app_handle.clone();
         let is_processing = Arc::clone(&self.is_processing);
+        let queue_order = Arc::clone(&self.order);
+        let pipeline = Arc::clone(&self.pipeline);
+
+        // Created under the concurrency lock so `set_concurrency` can't miss it
+        let (concurrency, slots) = {
+            let concurrency = self.concurrency.read().await;
+            let slots = Arc::new(PipelineSlots::new(*concurrency));
+            *self.pipeline.write().await = Some(Arc::clone(&slots));
+            (*concurrency, slots)
+        };
 
         // Spawn background processing coordinator
         tokio::spawn(async move {
-            // Determine concurrency based on logical cores (min 2, max 8 for safety)
-            let cpu_count = std::thread::available_parallelism()
-                .map(|n| n.get())
-                .unwrap_or(4);
-            let concurrency = cpu_count.clamp(2, 8);
-            
-            log::info!("[UploadManager] Starting background processor with {} concurrent threads", concurrency);
+            log::info!(
+                "[UploadManager] Starting pipeline: {} preparing, {} uploading, {} memory budget",
+                concurrency.prepare,
+                concurrency.upload,
+                format_bytes(concurrency.memory_budget_bytes)
+            );
 
-            // Semaphore to limit concurrent uploads
-            let semaphore = Arc::new(Semaphore::new(concurrency));
             let mut active_tasks: Vec<tokio::task::JoinHandle<()>> = Vec::new();
 
             loop {
-                // Wait for a semaphore permit before finding next item
-                let permit = match semaphore.clone().acquire_owned().await {
+                // Wait for a free prepare slot before finding next item
+                let prepare_permit = match slots.prepare.clone().acquire_owned().await {
                     Ok(p) => p,
                     Err(_) => break, // Semaphore closed
                 };
 
-                // Find the next pending item and mark it as Processing IMMEDIATELY
+                // Find the next pending item in queue order and mark it as Processing IMMEDIATELY
                 let next_item: Option<UploadItem> = {
                     let mut queue_guard = queue.write().await; // Write lock needed to update status
                     let paused = paused_ids.read().await;
                     let cancelled = cancelled_ids.read().await;
 
-                    if let Some((_, item)) = queue_guard
-                        .iter_mut()
-                        .find(|(_, i)| {
+                    let order = *queue_order.read().await;
+
+                    if let Some(item) = queue_guard
+                        .values_mut()
+                        .filter(|i| {
                             matches!(i.status, UploadStatus::Pending)
                                 && !paused.contains(&i.id)
                                 && !cancelled.contains(&i.id)
-                        }) 
+                        })
+                        .min_by(|a, b| compare_items(a, b, order))
                     {
                         // Mark as processing immediately to prevent other threads from picking it up
                         item.status = UploadStatus::Processing;
@@ -436,6 +1247,18 @@ impl UploadManager {
 
                 match next_item {
                     Some(item) => {
+                        // Reserve the item's estimated share of the memory budget; it's
+                        // adjusted once the item is prepared
+                        let memory_permit = match slots
+                            .memory
+                            .clone()
+                            .acquire_many_owned(slots.item_permits(item.size))
+                            .await
+                        {
+                            Ok(p) => p,
+                            Err(_) => break,
+                        };
+
                         // Clone shared state for this task
                         let queue_clone = Arc::clone(&queue);
                         let cancelled_clone = Arc::clone(&cancelled_ids);
@@ -444,8 +1267,9 @@ impl UploadManager {
                         let db_clone = Arc::clone(&db);
                         let cache_clone = Arc::clone(&thumbnail_cache);
                         let app_clone = app_handle.clone();
+                        let slots = Arc::clone(&slots);
 
-                        // Spawn parallel upload task
+                        // Spawn pipeline task
                         let task = tokio::spawn(async move {
                             let result = Self::process_item_with_retry(
                                 &queue_clone,
@@ -456,22 +1280,22 @@ impl UploadManager {
                                 &cache_clone,
                                 &app_clone,
                                 item,
+                                prepare_permit,
+                                memory_permit,
+                                &slots,
                             )
                             .await;
 
                             if let Err(e) = result {
                                 log::info!("Upload failed: {:?}", e);
                             }
-
-                            // Release permit when done
-                            drop(permit);
                         });
 
                         active_tasks.push(task);
                     }
                     None => {
                         // No more pending items, release permit and break
-                        drop(permit);
+                        drop(prepare_permit);
                         break;
                     }
                 }
@@ -486,9 +1310,20 @@ impl UploadManager {
             }
 
             // Mark as not processing
+            *pipeline.write().await = None;
             *is_processing.write().await = false;
             
             log::info!("[Upload] All uploads completed");
+
+            // Extracted library exports are only needed until their uploads finish
+            let vault_id = config.lock().await.as_ref().map(|c| c.id.clone());
+            if let Some(Ok(staging)) = vault_id.map(|id| library_import::staging_dir(&app_handle, &id)) {
+                let in_use = library_import::unfinished_sources(queue.read().await.values());
+                library_import::clean_staging(&staging, &in_use);
+            }
+
+            // New photos are in the local DB now; have the sync daemon upload the manifest
+            crate::sync_daemon::notify_local_change(&app_handle).await;
         });
 
         Ok(())
@@ -504,18 +1339,33 @@ impl UploadManager {
         thumbnail_cache: &Arc<Mutex<Option<ThumbnailCache>>>,
         app_handle: &AppHandle,
         mut item: UploadItem,
+        prepare_permit: tokio::sync::OwnedSemaphorePermit,
+        memory_permit: tokio::sync::OwnedSemaphorePermit,
+        slots: &PipelineSlots,
     ) -> Result<()> {
         let id = item.id.clone();
         
-        // Step 1: Prepare (Heavy CPU Processing + Encryption) - Done ONCE
+        // Step 1: Prepare (Heavy CPU Processing + Encryption) - Done ONCE, on the
+        // blocking pool so decoding and encryption don't stall the async workers.
         // Cancel check happens inside prepare
-        let prepared = match Self::prepare_item(
-            queue,
-            cancelled_ids,
-            config,
-            app_handle,
-            &item
-        ).await {
+        let prepared = {
+            let runtime = tokio::runtime::Handle::current();
+            let (queue, cancelled_ids, config, app_handle, item) = (
+                Arc::clone(queue),
+                Arc::clone(cancelled_ids),
+                Arc::clone(config),
+                app_handle.clone(),
+                item.clone(),
+            );
+            tokio::task::spawn_blocking(move || {
+                runtime.block_on(Self::prepare_item(&queue, &cancelled_ids, &config, &app_handle, &item))
+            })
+            .await
+            .unwrap_or_else(|e| Err(anyhow::anyhow!("Preparation task failed: {}", e)))
+        };
+        drop(prepare_permit);
+
+        let prepared = match prepared {
             Ok(Some(p)) => p,
             Ok(None) => return Ok(()), // Cancelled
             Err(e) => {
@@ -524,12 +1374,19 @@ impl UploadManager {
             }
         };
 
-        // Get storage tier from config for determining target storage class
-        let storage_tier = {
+        // Hold memory for what was actually prepared until the upload finishes
+        let _memory_permit = slots.reserve_prepared(memory_permit, prepared.held_bytes()).await?;
+
+        // Wait for an upload slot; the next item is being prepared meanwhile
+        let _upload_permit = slots.upload.acquire().await?;
+
+        // Get storage tier from config for determining target storage class,
+        // and the checks to run on the stored objects
+        let (storage_tier, checks) = {
             let config_guard = config.lock().await;
             config_guard
                 .as_ref()
-                .map(|c| c.storage_tier)
+                .map(|c| (c.storage_tier, c.verification))
                 .unwrap_or_default()
         };
 
@@ -563,6 +1420,7 @@ impl UploadManager {
                 &item,
                 &prepared,
                 storage_tier,
+                checks,
             ).await {
                 Ok(()) => return Ok(()),
                 Err(e) => {
@@ -601,14 +1459,15 @@ impl UploadManager {
         }
 
         // Get config and key
-        let vault_key = {
+        let (vault_key, archival) = {
             let config_guard = config.lock().await;
             let config = config_guard
                 .as_ref()
                 .ok_or_else(|| anyhow::anyhow!("Vault not loaded"))?;
-            BASE64
+            let vault_key = BASE64
                 .decode(&config.vault_key)
-                .context("Invalid vault key encoding")?
+                .context("Invalid vault key encoding")?;
+            (vault_key, config.archival)
         };
 
         let key_arr: [u8; 32] = vault_key
@@ -685,6 +1544,11 @@ impl UploadManager {
         } else {
             None
         };
+        // Sidecars of a library export fill in what the file itself lacks
+        let exif_metadata = match item.imported.as_ref() {
+            Some(imported) => Some(imported.fill(exif_metadata.unwrap_or_default())),
+            None => exif_metadata,
+        };
 
         // Process based on media type
         let (
@@ -695,12 +1559,14 @@ impl UploadManager {
             width,
             height,
             raw_thumbnail,
+            original_policy,
+            display,
         ) = match item.media_type {
             MediaType::Image => {
                 let transcoder = media_processor::TauriTranscoder {
                     app: app_handle.clone(),
                 };
-                let processed = media_processor::process_image(&transcoder, &item.path)
+                let processed = media_processor::process_image(&transcoder, &item.path, archival)
                     .await
                     .context(format!("Failed to process image: {:?}", item.path))?;
 
@@ -735,7 +1601,15 @@ impl UploadManager {
                 )
                 .await;
                 
-                let enc_original = crypto::encrypt(&processed.original, &key_arr).context("Encryption failed")?;
+                // A Motion Photo kept as the source file would store its video twice; the
+                // video goes to the motion object instead (see `OriginalPolicy::Source`)
+                let mut original = processed.original;
+                if let Some(MotionSource::Embedded { offset, .. }) = &item.motion {
+                    if processed.policy == OriginalPolicy::Source {
+                        original.truncate(*offset as usize);
+                    }
+                }
+                let enc_original = crypto::encrypt(&original, &key_arr).context("Encryption failed")?;
 
                 // Use dynamic extension from processor (supports passthrough formats like .heic)
                 let extension = &processed.original_extension;
@@ -749,6 +1623,8 @@ impl UploadManager {
                     processed.width,
                     processed.height,
                     raw_thumbnail,
+                    processed.policy,
+                    processed.display,
                 )
             }
             MediaType::Video => {
@@ -760,7 +1636,7 @@ impl UploadManager {
                     app: app_handle.clone(),
                 };
                 let managed_frames = item.pre_generated_frames.clone();
-                let processed = media_processor::process_video(&transcoder, &item.path, &output_path, managed_frames)
+                let processed = media_processor::process_video(&transcoder, &item.path, &output_path, managed_frames, archival)
                         .await
                         .context(format!("Failed to process video: {:?}", item.path))?;
 
@@ -784,7 +1660,8 @@ impl UploadManager {
                     None
                 };
 
-                let original_key = format!("originals/videos/{}.mp4", id);
+                // Extension from processor: mp4 when transcoded, the source's otherwise
+                let original_key = format!("originals/videos/{}.{}", id, processed.original_extension);
                 let thumbnail_key = format!("thumbnails/{}.webp", id);
                 let raw_thumb = if !thumbnail_bytes.is_empty() { Some(thumbnail_bytes) } else { None };
 
@@ -796,6 +1673,8 @@ impl UploadManager {
                     processed.width,
                     processed.height,
                     raw_thumb,
+                    processed.policy,
+                    processed.display,
                 )
             }
             MediaType::Audio => {
@@ -805,7 +1684,7 @@ impl UploadManager {
                 let transcoder = media_processor::TauriTranscoder {
                     app: app_handle.clone(),
                 };
-                let processed = media_processor::process_audio(&transcoder, &item.path, &output_path)
+                let processed = media_processor::process_audio(&transcoder, &item.path, &output_path, archival)
                         .await
                         .context(format!("Failed to process audio: {:?}", item.path))?;
 
@@ -826,8 +1705,27 @@ impl UploadManager {
                 let original_key = format!("audio/{}.{}", id, extension);
 
 
-                (original_key, None, enc_original, None, 0, 0, None)
+                (original_key, None, enc_original, None, 0, 0, None, processed.policy, None)
+            }
+        };
+
+        let (display_key, enc_display) = match display {
+            Some(display) => (
+                Some(format!("display/{}.{}", id, display.extension)),
+                Some(crypto::encrypt(&display.bytes, &key_arr).context("Display copy encryption failed")?),
+            ),
+            None => (None, None),
+        };
+
+        let (motion_key, enc_motion) = match item.motion.as_ref() {
+            Some(source) => {
+                let (bytes, extension) = motion_photo::read_motion(&item.path, source)?;
+                (
+                    Some(format!("motion/{}.{}", id, extension)),
+                    Some(crypto::encrypt(&bytes, &key_arr).context("Motion encryption failed")?),
+                )
             }
+            None => (None, None),
         };
 
         Ok(Some(PreparedUpload {
@@ -839,6 +1737,12 @@ impl UploadManager {
             height,
             raw_thumbnail,
             exif_metadata,
+            original_policy,
+            display_key,
+            enc_display,
+            motion_key,
+            enc_motion,
+            vault_key: key_arr,
         }))
     }
 
@@ -851,6 +1755,7 @@ impl UploadManager {
         item: &UploadItem,
         prepared: &PreparedUpload,
         storage_tier: StorageTier,
+        checks: UploadVerification,
     ) -> Result<()> {
         let id = item.id.clone();
 
@@ -863,21 +1768,7 @@ impl UploadManager {
                 .clone()
         };
 
-        // Determine storage class for the original file
-        // - Fresh uploads: Standard (lifecycle will transition after 60 days)
-        // - Audio: GLACIER_IR (frequently accessed, no archive benefit)
-        // - Images/Videos non-fresh: vault's configured tier (DEEP_ARCHIVE or GLACIER_IR)
-        let original_storage_class = if item.fresh_upload {
-            None // Standard (default)
-        } else {
-            match item.media_type {
-                MediaType::Audio => Some(StorageClass::GlacierIr),
-                MediaType::Image | MediaType::Video => Some(match storage_tier {
-                    StorageTier::DeepArchive => StorageClass::DeepArchive,
-                    StorageTier::GlacierInstantRetrieval => StorageClass::GlacierIr,
-                }),
-            }
-        };
+        let original_storage_class = original_storage_class(item.media_type, item.fresh_upload, storage_tier);
 
         // Upload original
         let media_type_label = match item.media_type {
@@ -988,6 +1879,36 @@ impl UploadManager {
             }
         }
 
+        // Upload the display copy of a lossless original (GLACIER_IR, viewable without a restore)
+        if let (Some(display_key), Some(enc_display)) = (prepared.display_key.as_ref(), prepared.enc_display.as_ref()) {
+            if let Err(e) = storage
+                .upload_file_with_storage_class(display_key, enc_display.clone(), StorageClass::GlacierIr)
+                .await
+            {
+                storage.delete_file(&prepared.original_key).await.ok();
+                if let Some(thumb_key) = prepared.thumbnail_key.as_ref() {
+                    storage.delete_file(thumb_key).await.ok();
+                }
+                return Err(e.context("Failed to upload display copy"));
+            }
+        }
+        let display_size = prepared.enc_display.as_ref().map(|d| d.len() as i64);
+
+        // Upload the Live Photo / Motion Photo video (GLACIER_IR, plays alongside the still)
+        if let (Some(motion_key), Some(enc_motion)) = (prepared.motion_key.as_ref(), prepared.enc_motion.as_ref()) {
+            if let Err(e) = storage
+                .upload_file_with_storage_class(motion_key, enc_motion.clone(), StorageClass::GlacierIr)
+                .await
+            {
+                storage.delete_file(&prepared.original_key).await.ok();
+                for key in [prepared.thumbnail_key.as_ref(), prepared.display_key.as_ref()].into_iter().flatten() {
+                    storage.delete_file(key).await.ok();
+                }
+                return Err(e.context("Failed to upload motion video"));
+            }
+        }
+        let motion_size = prepared.enc_motion.as_ref().map(|m| m.len() as i64);
+
         // Cache thumbnail locally
         if let Some(raw_thumb) = prepared.raw_thumbnail.as_ref() {
             let cache_guard = thumbnail_cache.lock().await;
@@ -1008,50 +1929,164 @@ impl UploadManager {
             MediaType::Audio => "audio",
         };
 
+        // Track the actual storage tier used
+        let tier = tier_label(original_storage_class.as_ref());
+        if tier == "Unknown" {
+            log::warn!("[Upload {}] Unexpected storage class: {:?}", id, original_storage_class);
+        }
+
+        let created_at = chrono::Utc::now().to_rfc3339();
+        let captured_at = prepared.exif_metadata
+            .as_ref()
+            .and_then(|m| m.captured_at.as_ref())
+            .map(|d| d.to_rfc3339());
+        let latitude = prepared.exif_metadata.as_ref().and_then(|m| m.latitude);
+        let longitude = prepared.exif_metadata.as_ref().and_then(|m| m.longitude);
+        let make = prepared.exif_metadata.as_ref().and_then(|m| m.make.clone());
+        let model = prepared.exif_metadata.as_ref().and_then(|m| m.model.clone());
+        let lens_model = prepared.exif_metadata.as_ref().and_then(|m| m.lens_model.clone());
+        let iso = prepared.exif_metadata.as_ref().and_then(|m| m.iso);
+        let f_number = prepared.exif_metadata.as_ref().and_then(|m| m.f_number).map(|f| f as f64);
+        let exposure_time = prepared.exif_metadata.as_ref().and_then(|m| m.exposure_time.clone());
+        // XMP ratings and keywords (e.g. from Lightroom) seed the curation fields
+        let rating = prepared.exif_metadata.as_ref().and_then(|m| m.rating).unwrap_or(0);
+        let tags = prepared
+            .exif_metadata
+            .as_ref()
+            .map(|m| m.keywords.clone())
+            .unwrap_or_default();
+        let description = prepared.exif_metadata.as_ref().and_then(|m| m.description.clone());
+        let favorite = item.imported.as_ref().is_some_and(|i| i.favorite);
+        let curation_updated_at = (rating > 0 || favorite || !tags.is_empty()).then(|| created_at.clone());
+
+        // Check the stored objects before anything records the photo
+        Self::update_status_static(queue, app_handle, &id, UploadStatus::Verifying).await;
+        let glacier_ir = StorageClass::GlacierIr;
+        let original_object = ExpectedObject {
+            key: &prepared.original_key,
+            size: original_size,
+            class: original_storage_class.as_ref(),
+        };
+        let other_objects: Vec<ExpectedObject> = [
+            (prepared.thumbnail_key.as_ref(), prepared.enc_thumbnail.as_ref()),
+            (prepared.display_key.as_ref(), prepared.enc_display.as_ref()),
+            (prepared.motion_key.as_ref(), prepared.enc_motion.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(key, data)| {
+            Some(ExpectedObject {
+                key: key?.as_str(),
+                size: data?.len() as u64,
+                class: Some(&glacier_ir),
+            })
+        })
+        .collect();
+        // Originals kept as the source file must decrypt back to it, unless the
+        // embedded video of a Motion Photo was cut off
+        let embedded_motion = matches!(item.motion, Some(MotionSource::Embedded { .. }));
+        let source = (prepared.original_policy == OriginalPolicy::Source && !embedded_motion)
+            .then_some(item.path.as_path());
+        let verified = match verification::verify_upload(
+            &storage,
+            checks,
+            &original_object,
+            &other_objects,
+            &prepared.enc_original,
+            &prepared.vault_key,
+            source,
+        )
+        .await
+        {
+            Ok(level) => level,
+            Err(e) => {
+                Self::delete_uploaded(&storage, prepared, &id).await;
+                return Err(e.context("Upload verification failed"));
+            }
+        };
+        let verified_at = (verified != Verification::Unverified).then(|| chrono::Utc::now().to_rfc3339());
+        log::info!("[Upload {}] Stored objects verified: {}", id, verified.as_str());
+
+        // Upload encrypted metadata sidecar so the photo can be recovered without the manifest
+        let sidecar = crate::manifest::PhotoRecord {
+            id: id.clone(),
+            filename: item.filename.clone(),
+            width: Some(prepared.width),
+            height: Some(prepared.height),
+            created_at: Some(created_at.clone()),
+            captured_at: captured_at.clone(),
+            size_bytes: Some(original_size as i64),
+            thumbnail_size_bytes: compressed_thumbnail_size.map(|t| t as i64),
+            s3_key: prepared.original_key.clone(),
+            thumbnail_key: prepared.thumbnail_key.clone(),
+            tier: tier.to_string(),
+            media_type: media_type_label.to_string(),
+            latitude,
+            longitude,
+            make: make.clone(),
+            model: model.clone(),
+            lens_model: lens_model.clone(),
+            iso,
+            f_number,
+            exposure_time: exposure_time.clone(),
+            favorite,
+            rating,
+            tags: tags.clone(),
+            curation_updated_at: curation_updated_at.clone(),
+            content_hash: item.content_hash.clone(),
+            original_policy: Some(prepared.original_policy.as_str().to_string()),
+            display_key: prepared.display_key.clone(),
+            display_size_bytes: display_size,
+            motion_key: prepared.motion_key.clone(),
+            motion_size_bytes: motion_size,
+            description: description.clone(),
+            verification: Some(verified.as_str().to_string()),
+            verified_at: verified_at.clone(),
+            ..Default::default()
+        };
+        let enc_sidecar = recovery::encrypt_sidecar(&sidecar, &prepared.vault_key)?;
+        let sidecar_key = recovery::sidecar_key(&id);
+        let sidecar_size = enc_sidecar.len() as u64;
+        if let Err(e) = storage.upload_file(&sidecar_key, enc_sidecar).await {
+            Self::delete_uploaded(&storage, prepared, &id).await;
+            return Err(e.context("Failed to upload metadata sidecar"));
+        }
+        if checks.head_check {
+            let sidecar_object = ExpectedObject {
+                key: &sidecar_key,
+                size: sidecar_size,
+                class: None,
+            };
+            if let Err(e) = verification::verify_object(&storage, &sidecar_object).await {
+                Self::delete_uploaded(&storage, prepared, &id).await;
+                return Err(e.context("Upload verification failed"));
+            }
+        }
+
         // Add entry to local database
         log::info!("[Upload {}] Adding {} to database...", id, media_type_str);
         {
             let db_guard = db.lock().await;
             if let Some(conn) = db_guard.as_ref() {
-                // Track the actual storage tier used
-                let tier = match &original_storage_class {
-                    None => "Standard",
-                    Some(StorageClass::DeepArchive) => "DeepArchive",
-                    Some(StorageClass::GlacierIr) => "GlacierIR",
-                    Some(other) => {
-                        log::warn!("[Upload {}] Unexpected storage class: {:?}", id, other);
-                        "Unknown"
-                    }
-                };
-
-                let captured_at = prepared.exif_metadata
-                    .as_ref()
-                    .and_then(|m| m.captured_at.as_ref())
-                    .map(|d| d.to_rfc3339());
-                let latitude = prepared.exif_metadata.as_ref().and_then(|m| m.latitude);
-                let longitude = prepared.exif_metadata.as_ref().and_then(|m| m.longitude);
-                let make = prepared.exif_metadata.as_ref().and_then(|m| m.make.clone());
-                let model = prepared.exif_metadata.as_ref().and_then(|m| m.model.clone());
-                let lens_model = prepared.exif_metadata.as_ref().and_then(|m| m.lens_model.clone());
-                let iso = prepared.exif_metadata.as_ref().and_then(|m| m.iso);
-                let f_number = prepared.exif_metadata.as_ref().and_then(|m| m.f_number).map(|f| f as f64);
-                let exposure_time = prepared.exif_metadata.as_ref().and_then(|m| m.exposure_time.clone());
-
                 conn.execute(
                     "INSERT INTO photos (
-                        id, filename, width, height, created_at, captured_at, size_bytes, 
+                        id, filename, width, height, created_at, captured_at, size_bytes, thumbnail_size_bytes,
                         s3_key, thumbnail_key, tier, media_type, latitude, longitude,
-                        make, model, lens_model, iso, f_number, exposure_time
+                        make, model, lens_model, iso, f_number, exposure_time,
+                        rating, curation_updated_at, content_hash,
+                        original_policy, display_key, display_size_bytes, motion_key, motion_size_bytes,
+                        description, favorite, verification, verified_at
                     )
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23,
+                             ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32)",
                     rusqlite::params![
                         id,
                         item.filename,
                         prepared.width,
                         prepared.height,
-                        chrono::Utc::now().to_rfc3339(),
+                        created_at,
                         captured_at,
                         original_size,
+                        compressed_thumbnail_size.map(|t| t as i64),
                         prepared.original_key,
                         prepared.thumbnail_key.as_deref().unwrap_or(""),
                         tier,
@@ -1063,9 +2098,27 @@ impl UploadManager {
                         lens_model,
                         iso,
                         f_number,
-                        exposure_time
+                        exposure_time,
+                        rating,
+                        curation_updated_at,
+                        item.content_hash,
+                        prepared.original_policy.as_str(),
+                        prepared.display_key,
+                        display_size,
+                        prepared.motion_key,
+                        motion_size,
+                        description,
+                        favorite,
+                        verified.as_str(),
+                        verified_at
                     ],
                 ).context("Failed to insert into database")?;
+                crate::curation::set_photo_tags(conn, &id, &tags)
+                    .context("Failed to save photo tags")?;
+                // Albums and memories of a library import
+                if let Err(e) = crate::library_import::attach_pending(conn, &id) {
+                    log::warn!("[Upload {}] Failed to add photo to its imported albums: {}", id, e);
+                }
                 log::info!(
                     "[Upload {}] {} added to database successfully",
                     id, media_type_label
@@ -1106,6 +2159,22 @@ impl UploadManager {
         Ok(())
     }
 
+    /// Remove every object an upload stored, so a retry starts clean
+    async fn delete_uploaded(storage: &Storage, prepared: &PreparedUpload, id: &str) {
+        storage.delete_file(&prepared.original_key).await.ok();
+        for key in [
+            prepared.thumbnail_key.as_ref(),
+            prepared.display_key.as_ref(),
+            prepared.motion_key.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            storage.delete_file(key).await.ok();
+        }
+        storage.delete_file(&recovery::sidecar_key(id)).await.ok();
+    }
+
     async fn handle_failure_static(
         queue: &Arc<RwLock<HashMap<String, UploadItem>>>,
         app_handle: &AppHandle,
@@ -1213,70 +2282,38 @@ impl UploadManager {
 
     /// Update system tray with upload progress (called from emit_queue_changed and processing loop)
     async fn update_tray_progress(app_handle: &AppHandle, state: &QueueState) {
-        use tauri::Manager;
-        
-        // Calculate overall progress
-        let total_size: u64 = state.items.iter().map(|i| i.size).sum();
-        let bytes_uploaded: u64 = state.items.iter().map(|i| i.bytes_uploaded).sum();
-        let progress = if total_size > 0 {
-            bytes_uploaded as f64 / total_size as f64
-        } else {
-            0.0
-        };
-        
         // Check if uploads are active (processing)
-        let is_processing = state.items.iter().any(|i| {
-            matches!(
-                i.status,
-                UploadStatus::Processing
-                    | UploadStatus::EncryptingOriginal
-                    | UploadStatus::EncryptingThumbnail
-                    | UploadStatus::UploadingOriginal { .. }
-                    | UploadStatus::UploadingThumbnail { .. }
-            )
-        }) || state.pending_count > 0;
+        let is_processing = state.items.iter().any(|i| is_active(&i.status)) || state.pending_count > 0;
         
         // Check if there are completed items (for persistent tray icon)
         let has_completed_items = state.completed_count > 0;
         
-        Self::update_tray_internal(app_handle, is_processing, has_completed_items, progress, state.completed_count, state.items.len()).await;
+        // Report the batch being uploaded rather than the whole queue
+        let (progress, completed, total) = tray_batch(&state.batches)
+            .map(|b| (b.progress, b.completed_count, b.total_count))
+            .unwrap_or((0.0, 0, 0));
+        
+        Self::update_tray_internal(app_handle, is_processing, has_completed_items, progress, completed, total).await;
     }
     
     /// Static helper to sync tray state directly from queue lock
     async fn sync_tray_static(queue: &Arc<RwLock<HashMap<String, UploadItem>>>, app_handle: &AppHandle) {
-        let (is_processing, has_completed_items, progress, completed_count, total_count) = {
+        let (is_processing, has_completed_items, batch) = {
             let queue = queue.read().await;
-            let items: Vec<&UploadItem> = queue.values().collect();
-            
-            let total_size: u64 = items.iter().map(|i| i.size).sum();
-            let bytes_uploaded: u64 = items.iter().map(|i| i.bytes_uploaded).sum();
-            
-            let progress = if total_size > 0 {
-                bytes_uploaded as f64 / total_size as f64
-            } else {
-                0.0
-            };
             
-            let completed_count = items.iter().filter(|i| matches!(i.status, UploadStatus::Completed)).count();
-            let pending_count = items.iter().filter(|i| matches!(i.status, UploadStatus::Pending)).count();
-             
-             let is_processing = items.iter().any(|i| {
-                matches!(
-                    i.status,
-                    UploadStatus::Processing
-                        | UploadStatus::EncryptingOriginal
-                        | UploadStatus::EncryptingThumbnail
-                        | UploadStatus::UploadingOriginal { .. }
-                        | UploadStatus::UploadingThumbnail { .. }
-                )
-            }) || pending_count > 0;
-            
-            let has_completed_items = completed_count > 0;
+            let is_processing = queue
+                .values()
+                .any(|i| is_active(&i.status) || matches!(i.status, UploadStatus::Pending));
+            let has_completed_items = queue.values().any(|i| matches!(i.status, UploadStatus::Completed));
+            let batches = batch_progress(queue.values());
             
-            (is_processing, has_completed_items, progress, completed_count, items.len())
+            (is_processing, has_completed_items, tray_batch(&batches).cloned())
         };
         
-        Self::update_tray_internal(app_handle, is_processing, has_completed_items, progress, completed_count, total_count).await;
+        let (progress, completed, total) = batch
+            .map(|b| (b.progress, b.completed_count, b.total_count))
+            .unwrap_or((0.0, 0, 0));
+        Self::update_tray_internal(app_handle, is_processing, has_completed_items, progress, completed, total).await;
     }
     
     async fn update_tray_internal(
@@ -1323,3 +2360,135 @@ fn format_bytes(bytes: u64) -> String {
         format!("{} B", bytes)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_reservation() {
+        let concurrency = UploadConcurrency {
+            prepare: 0,
+            upload: 100,
+            memory_budget_bytes: 256 * MEMORY_PERMIT_BYTES,
+        }
+        .normalized();
+        assert_eq!((concurrency.prepare, concurrency.upload), (1, 32));
+
+        assert_eq!(concurrency.item_permits(0), 1);
+        assert_eq!(concurrency.item_permits(MEMORY_PERMIT_BYTES + 1), 2);
+        // Larger than the budget: takes all of it instead of waiting forever
+        assert_eq!(concurrency.item_permits(10 * 1024 * MEMORY_PERMIT_BYTES), 256);
+    }
+
+    #[test]
+    fn test_content_hash_cache() {
+        let path = std::env::temp_dir().join(format!("boreal-hash-{}.jpg", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"first").unwrap();
+        let key = [3u8; 32];
+
+        let cache = ContentHashCache::default();
+        let hashes = cache.hash_all(std::slice::from_ref(&path), &key);
+        let first = crypto::content_hash(&path, &key).unwrap();
+        assert_eq!(hashes, vec![Some(first.clone())]);
+        assert_eq!(cache.take_or_hash(&path, &key).unwrap(), first);
+
+        // A file changed since the preview is hashed again
+        cache.hash_all(std::slice::from_ref(&path), &key);
+        std::fs::write(&path, b"changed").unwrap();
+        assert_ne!(cache.take_or_hash(&path, &key).unwrap(), first);
+
+        // Hashes taken when importing stay cached for `add_files`
+        let changed = cache.get_or_hash(&path, &key).unwrap();
+        assert!(cache.entries.lock().unwrap().contains_key(&path));
+        assert_eq!(cache.take_or_hash(&path, &key).unwrap(), changed);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    fn queued(seq: u64, size: u64, modified_at: Option<i64>, batch: &str) -> UploadItem {
+        UploadItem {
+            id: format!("i{}", seq),
+            path: PathBuf::from(format!("/tmp/{}.jpg", seq)),
+            filename: format!("{}.jpg", seq),
+            size,
+            status: UploadStatus::Pending,
+            progress: 0.0,
+            media_type: MediaType::Image,
+            fresh_upload: false,
+            bytes_uploaded: 0,
+            retry_count: 0,
+            content_hash: None,
+            modified_at,
+            seq,
+            priority: 0,
+            batch_id: Some(batch.to_string()),
+            batch_name: Some(batch.to_uppercase()),
+            motion: None,
+            imported: None,
+            pre_generated_frames: None,
+        }
+    }
+
+    #[test]
+    fn test_queue_order() {
+        let mut items = vec![
+            queued(0, 300, Some(20), "a"),
+            queued(1, 100, None, "a"),
+            queued(2, 200, Some(10), "a"),
+        ];
+        let ids = |items: &[UploadItem]| items.iter().map(|i| i.id.clone()).collect::<Vec<_>>();
+
+        items.sort_by(|a, b| compare_items(a, b, QueueOrder::SmallestFirst));
+        assert_eq!(ids(&items), vec!["i1", "i2", "i0"]);
+        items.sort_by(|a, b| compare_items(a, b, QueueOrder::OldestFirst));
+        assert_eq!(ids(&items), vec!["i2", "i0", "i1"]);
+
+        // Moved to top: first in any order
+        items[2].priority = 1;
+        items.sort_by(|a, b| compare_items(a, b, QueueOrder::Queued));
+        assert_eq!(ids(&items), vec!["i1", "i0", "i2"]);
+    }
+
+    #[test]
+    fn test_batch_progress() {
+        let mut items = vec![
+            queued(2, 100, None, "b"),
+            queued(0, 100, None, "a"),
+            queued(1, 300, None, "a"),
+            queued(3, 500, None, "a"),
+        ];
+        items[1].status = UploadStatus::Completed;
+        items[2].status = UploadStatus::UploadingOriginal { progress: 0.5 };
+        items[2].bytes_uploaded = 100;
+        items[3].status = UploadStatus::Cancelled;
+
+        let batches = batch_progress(&items);
+        assert_eq!(batches.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), vec!["A", "B"]);
+        let a = &batches[0];
+        assert_eq!((a.total_count, a.completed_count, a.active_count), (3, 1, 1));
+        assert_eq!((a.total_bytes, a.uploaded_bytes), (400, 200));
+        assert!((a.progress - 0.5).abs() < 1e-9);
+        assert_eq!(tray_batch(&batches).map(|b| b.id.as_str()), Some("a"));
+    }
+
+    #[test]
+    fn test_find_vault_duplicate() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO photos (id, filename, s3_key, tier, content_hash, deleted_at)
+             VALUES ('p1', 'a.jpg', 'k', 'Standard', 'h1', NULL),
+                    ('p2', 'b.jpg', 'k', 'Standard', 'h2', '2024-01-01T00:00:00+00:00')",
+            [],
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_vault_duplicate(&conn, "h1").unwrap(),
+            Some(("p1".to_string(), "a.jpg".to_string()))
+        );
+        // Trashed photos don't block a re-upload
+        assert_eq!(find_vault_duplicate(&conn, "h2").unwrap(), None);
+    }
+}
@@ -0,0 +1,313 @@
+//! Persistent upload queue
+//!
+//! `UploadManager` keeps its queue in memory. This store mirrors the unfinished
+//! items to an app-level SQLite DB (`upload_queue.db`, shared by all vaults and
+//! keyed by vault ID) so a large import survives quitting the app. Completed and
+//! cancelled items are dropped; interrupted ones are restored as pending.
+//!
+//! The DB isn't encrypted, so sidecar metadata of library imports (locations,
+//! captions) is stored sealed with a key derived from the vault key.
+
+use crate::crypto;
+use crate::library_import::ImportedMetadata;
+use crate::upload_manager::{UploadItem, UploadStatus};
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File name of the queue DB inside the app data dir
+pub const QUEUE_DB_FILE: &str = "upload_queue.db";
+
+/// Derive the key sealing a vault's imported metadata in the queue DB from the vault key
+pub fn derive_queue_key(vault_key: &[u8; 32]) -> [u8; 32] {
+    use hkdf::Hkdf;
+    use sha2::Sha256;
+
+    let hk = Hkdf::<Sha256>::new(None, vault_key);
+    let mut okm = [0u8; 32];
+    hk.expand(b"boreal-upload-queue-v1", &mut okm)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    okm
+}
+
+/// An item as stored: the item without its imported metadata, and that metadata
+/// (both serialized, unsealed)
+type StoredRow = (String, Option<String>);
+
+pub struct UploadQueueStore {
+    conn: Connection,
+    vault_id: String,
+    key: [u8; 32],
+    /// Serialized form of every item last written, to skip unchanged rows
+    written: HashMap<String, StoredRow>,
+}
+
+impl UploadQueueStore {
+    pub fn open(path: &Path, vault_id: &str, key: [u8; 32]) -> Result<Self> {
+        let conn = Connection::open(path).context("Failed to open upload queue DB")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS upload_queue (
+                id TEXT PRIMARY KEY,
+                vault_id TEXT NOT NULL,
+                item TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_upload_queue_vault ON upload_queue (vault_id);",
+        )?;
+        let has_imported = conn
+            .prepare("SELECT imported FROM upload_queue LIMIT 0")
+            .is_ok();
+        if !has_imported {
+            conn.execute("ALTER TABLE upload_queue ADD COLUMN imported TEXT", [])?;
+        }
+        Ok(Self {
+            conn,
+            vault_id: vault_id.to_string(),
+            key,
+            written: HashMap::new(),
+        })
+    }
+
+    pub fn vault_id(&self) -> &str {
+        &self.vault_id
+    }
+
+    /// Items saved for this vault. Unreadable rows are skipped.
+    pub fn load(&mut self) -> Result<Vec<UploadItem>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, item, imported FROM upload_queue WHERE vault_id = ?1 ORDER BY updated_at",
+        )?;
+        let rows = stmt
+            .query_map([&self.vault_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut items = Vec::new();
+        for (id, json, sealed) in rows {
+            let parsed = sealed.map(|sealed| self.unseal(&sealed)).transpose().and_then(|imported| {
+                let metadata = imported.as_deref().map(serde_json::from_str::<ImportedMetadata>).transpose()?;
+                Ok((serde_json::from_str::<UploadItem>(&json)?, metadata, imported))
+            });
+            match parsed {
+                Ok((mut item, metadata, imported)) => {
+                    // Rows written before sealing carry it in the item; the next sync rewrites them
+                    item.imported = metadata.or(item.imported.take());
+                    self.written.insert(id, (json, imported));
+                    items.push(item);
+                }
+                Err(e) => log::warn!("[UploadQueue] Skipping unreadable item {}: {}", id, e),
+            }
+        }
+        Ok(items)
+    }
+
+    fn seal(&self, json: &str) -> Result<String> {
+        Ok(BASE64.encode(crypto::encrypt(json.as_bytes(), &self.key)?))
+    }
+
+    fn unseal(&self, sealed: &str) -> Result<String> {
+        let plain = crypto::decrypt(&BASE64.decode(sealed)?, &self.key)?;
+        Ok(String::from_utf8(plain)?)
+    }
+
+    /// Bring the stored queue in line with `items`, writing only what changed
+    pub fn sync(&mut self, items: &[UploadItem]) -> Result<()> {
+        let mut current = HashMap::new();
+        for item in items {
+            if let Some(mut persisted) = persisted_form(item) {
+                let imported = persisted.imported.take().map(|i| serde_json::to_string(&i)).transpose()?;
+                current.insert(item.id.clone(), (serde_json::to_string(&persisted)?, imported));
+            }
+        }
+
+        let changed: Vec<(&String, &StoredRow)> = current
+            .iter()
+            .filter(|(id, json)| self.written.get(*id) != Some(*json))
+            .collect();
+        let removed: Vec<&String> = self.written.keys().filter(|id| !current.contains_key(*id)).collect();
+        if changed.is_empty() && removed.is_empty() {
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, (json, imported)) in &changed {
+            let sealed = imported.as_deref().map(|i| self.seal(i)).transpose()?;
+            // Keep the original updated_at for existing rows so restore order stays stable
+            tx.execute(
+                "INSERT INTO upload_queue (id, vault_id, item, imported, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(id) DO UPDATE SET item = excluded.item, imported = excluded.imported",
+                rusqlite::params![id, self.vault_id, json, sealed, now],
+            )?;
+        }
+        for id in &removed {
+            tx.execute("DELETE FROM upload_queue WHERE id = ?1", [id])?;
+        }
+        tx.commit()?;
+
+        self.written = current;
+        Ok(())
+    }
+}
+
+/// Drop everything saved for a vault (when the vault is removed)
+pub fn forget_vault(path: &Path, vault_id: &str) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let conn = Connection::open(path).context("Failed to open upload queue DB")?;
+    conn.execute("DELETE FROM upload_queue WHERE vault_id = ?1", [vault_id])?;
+    Ok(())
+}
+
+/// How an item is saved, or None if it doesn't need restoring.
+/// Work in progress is saved as pending: preparation restarts from scratch.
+pub fn persisted_form(item: &UploadItem) -> Option<UploadItem> {
+    let status = match &item.status {
+        UploadStatus::Completed | UploadStatus::Cancelled => return None,
+        UploadStatus::Paused => UploadStatus::Paused,
+        UploadStatus::Failed { .. } | UploadStatus::Duplicate { .. } => item.status.clone(),
+        _ => UploadStatus::Pending,
+    };
+
+    // Frontend-generated thumbnail frames are not saved; they are regenerated
+    // on the backend where possible
+    Some(UploadItem {
+        id: item.id.clone(),
+        path: item.path.clone(),
+        filename: item.filename.clone(),
+        size: item.size,
+        status,
+        progress: 0.0,
+        media_type: item.media_type,
+        fresh_upload: item.fresh_upload,
+        bytes_uploaded: 0,
+        retry_count: item.retry_count,
+        content_hash: item.content_hash.clone(),
+        modified_at: item.modified_at,
+        seq: item.seq,
+        priority: item.priority,
+        batch_id: item.batch_id.clone(),
+        batch_name: item.batch_name.clone(),
+        motion: item.motion.clone(),
+        imported: item.imported.clone(),
+        pre_generated_frames: None,
+    })
+}
+
+/// Fail a restored item whose source file is gone or was modified after it was queued
+pub fn revalidate(item: &mut UploadItem) {
+    if matches!(item.status, UploadStatus::Failed { .. }) {
+        return;
+    }
+
+    let error = match std::fs::metadata(&item.path) {
+        Err(_) => Some("Source file no longer exists"),
+        Ok(meta) if meta.len() != item.size => Some("Source file changed since it was queued"),
+        Ok(meta) if item.modified_at.is_some() && crate::upload_manager::modified_millis(&meta) != item.modified_at => {
+            Some("Source file changed since it was queued")
+        }
+        Ok(_) => None,
+    };
+
+    if let Some(error) = error {
+        item.status = UploadStatus::Failed {
+            error: error.to_string(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_filter::MediaType;
+
+    fn item(id: &str, status: UploadStatus) -> UploadItem {
+        UploadItem {
+            id: id.to_string(),
+            path: std::env::temp_dir().join(format!("boreal-missing-{}.jpg", id)),
+            filename: format!("{}.jpg", id),
+            size: 10,
+            status,
+            progress: 0.5,
+            media_type: MediaType::Image,
+            fresh_upload: false,
+            bytes_uploaded: 5,
+            retry_count: 0,
+            content_hash: None,
+            modified_at: None,
+            seq: 0,
+            priority: 0,
+            batch_id: None,
+            batch_name: None,
+            motion: None,
+            imported: None,
+            pre_generated_frames: None,
+        }
+    }
+
+    #[test]
+    fn test_sync_and_load() {
+        let path = std::env::temp_dir().join(format!("boreal-queue-{}.db", uuid::Uuid::new_v4()));
+        {
+            let mut store = UploadQueueStore::open(&path, "v1", [1u8; 32]).unwrap();
+            store
+                .sync(&[
+                    item("a", UploadStatus::UploadingOriginal { progress: 0.5 }),
+                    item("b", UploadStatus::Paused),
+                    item("c", UploadStatus::Completed),
+                ])
+                .unwrap();
+            store.sync(&[item("a", UploadStatus::Completed), item("b", UploadStatus::Paused)]).unwrap();
+        }
+
+        assert!(UploadQueueStore::open(&path, "v2", [2u8; 32]).unwrap().load().unwrap().is_empty());
+
+        let items = UploadQueueStore::open(&path, "v1", [1u8; 32]).unwrap().load().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "b");
+        assert_eq!(items[0].status, UploadStatus::Paused);
+        assert_eq!(items[0].bytes_uploaded, 0);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_imported_metadata_is_sealed() {
+        let path = std::env::temp_dir().join(format!("boreal-queue-{}.db", uuid::Uuid::new_v4()));
+        let imported = ImportedMetadata {
+            latitude: Some(64.1466),
+            longitude: Some(-21.9426),
+            description: Some("Midnight sun".to_string()),
+            ..Default::default()
+        };
+        let mut queued = item("a", UploadStatus::Pending);
+        queued.imported = Some(imported.clone());
+        UploadQueueStore::open(&path, "v1", [1u8; 32]).unwrap().sync(&[queued]).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let (item_json, sealed): (String, String) = conn
+            .query_row("SELECT item, imported FROM upload_queue", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        for stored in [&item_json, &sealed] {
+            assert!(!stored.contains("Midnight") && !stored.contains("64.1466"));
+        }
+
+        let items = UploadQueueStore::open(&path, "v1", [1u8; 32]).unwrap().load().unwrap();
+        assert_eq!(items[0].imported, Some(imported));
+        // Without the vault's key the item can't be restored
+        assert!(UploadQueueStore::open(&path, "v1", [3u8; 32]).unwrap().load().unwrap().is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_revalidate_missing_file() {
+        let mut missing = item("m", UploadStatus::Pending);
+        revalidate(&mut missing);
+        assert!(matches!(missing.status, UploadStatus::Failed { .. }));
+    }
+}
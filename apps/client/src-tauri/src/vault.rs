@@ -8,6 +8,70 @@ pub enum StorageTier {
     GlacierInstantRetrieval,
 }
 
+/// How uploaded originals are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum OriginalPolicy {
+    /// Re-encode to WebP Q90 / H.265 / Opus when it saves space. Lossy, and drops
+    /// EXIF from images.
+    #[default]
+    Optimized,
+    /// Store the source file bit-for-bit. Google Motion Photos are stored without
+    /// their embedded video, which is kept as the motion object; appending it to
+    /// the original gives back the source file.
+    Source,
+    /// Images as lossless WebP when smaller than the source, with its EXIF, XMP and
+    /// ICC profile carried over; everything else as `Source`. Images whose metadata
+    /// can't be read or has no WebP equivalent (IPTC) are stored as `Source` too.
+    LosslessWebp,
+    /// Images as lossless JPEG XL when smaller than the source (needs FFmpeg with
+    /// libjxl). FFmpeg may drop metadata, so only images without EXIF, XMP or ICC
+    /// are re-encoded; everything else as `Source`
+    LosslessJxl,
+}
+
+impl OriginalPolicy {
+    /// Value stored in `photos.original_policy`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OriginalPolicy::Optimized => "Optimized",
+            OriginalPolicy::Source => "Source",
+            OriginalPolicy::LosslessWebp => "LosslessWebp",
+            OriginalPolicy::LosslessJxl => "LosslessJxl",
+        }
+    }
+}
+
+/// Per-vault archival settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ArchivalPolicy {
+    #[serde(default)]
+    pub original: OriginalPolicy,
+    /// With a lossless policy, also keep the optimized encoding as a display copy in
+    /// Glacier IR, so the photo can be viewed without restoring the original
+    #[serde(default)]
+    pub display_copy: bool,
+}
+
+/// Checks run on the stored objects of an upload before its photo is recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UploadVerification {
+    /// HeadObject every uploaded object and compare its size and storage class
+    pub head_check: bool,
+    /// Download and decrypt originals up to this many bytes again (0 = never).
+    /// Skipped for Deep Archive, which can't be read back without a restore.
+    pub round_trip_max_bytes: u64,
+}
+
+impl Default for UploadVerification {
+    fn default() -> Self {
+        Self {
+            head_check: true,
+            round_trip_max_bytes: 0,
+        }
+    }
+}
+
 /// Vault credentials and encryption key stored in local JSON.
 /// Note: `name` and `visits` are stored in SQLite, not here.
 #[derive(Deserialize, Serialize, Clone, Default)]
@@ -23,6 +87,10 @@ pub struct VaultConfig {
     /// Storage tier for archived originals (DEEP_ARCHIVE or GLACIER_IR)
     #[serde(default)]
     pub storage_tier: StorageTier,
+    #[serde(default)]
+    pub archival: ArchivalPolicy,
+    #[serde(default)]
+    pub verification: UploadVerification,
     // Legacy fields - kept for migration but ignored after first load
     #[serde(default, skip_serializing)]
     pub name: Option<String>,
@@ -59,10 +127,33 @@ impl VaultConfig {
             bucket,
             vault_key,
             storage_tier,
+            archival: ArchivalPolicy::default(),
+            verification: UploadVerification::default(),
             name: None,
             visits: None,
         }
     }
+
+    /// Key for the at-rest encryption of this vault's local manifest.db
+    pub fn db_key(&self) -> Result<[u8; 32], String> {
+        Ok(crate::db::derive_db_key(&self.key_bytes()?))
+    }
+
+    /// Key for this vault's sensitive fields in the shared upload queue DB
+    pub fn upload_queue_key(&self) -> Result<[u8; 32], String> {
+        Ok(crate::upload_queue::derive_queue_key(&self.key_bytes()?))
+    }
+
+    fn key_bytes(&self) -> Result<[u8; 32], String> {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+        let vault_key = BASE64
+            .decode(&self.vault_key)
+            .map_err(|e| format!("Invalid vault key: {}", e))?;
+        vault_key
+            .try_into()
+            .map_err(|_| "Invalid key length".to_string())
+    }
 }
 
 pub mod store {
@@ -0,0 +1,322 @@
+//! Upload verification
+//!
+//! Before an upload is recorded in `photos`, the objects it stored are checked with
+//! HeadObject (size and storage class) and, for small originals, downloaded and
+//! decrypted again (see `vault::UploadVerification`). The level reached is kept per
+//! photo in `photos.verification`; NULL means the photo was uploaded before uploads
+//! were verified and can be checked later with `verify_photos`.
+
+use crate::crypto;
+use crate::storage::{ObjectSummary, Storage, StorageClass};
+use crate::vault::UploadVerification;
+use crate::AppState;
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+/// How thoroughly the stored objects of a photo were checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Unverified,
+    /// Every object exists with the uploaded size and storage class
+    Head,
+    /// The original was also downloaded and decrypted again
+    RoundTrip,
+}
+
+impl Verification {
+    /// Value stored in `photos.verification`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verification::Unverified => "Unverified",
+            Verification::Head => "Head",
+            Verification::RoundTrip => "RoundTrip",
+        }
+    }
+}
+
+/// Whether a `photos.verification` value confirms the stored objects were checked
+pub fn is_verified(value: Option<&str>) -> bool {
+    matches!(value, Some("Head" | "RoundTrip"))
+}
+
+/// An uploaded object as it should be stored
+pub struct ExpectedObject<'a> {
+    pub key: &'a str,
+    pub size: u64,
+    /// None for STANDARD
+    pub class: Option<&'a StorageClass>,
+}
+
+/// Storage class name as reported by HeadObject, which omits it for STANDARD
+fn class_name(class: Option<&str>) -> &str {
+    class.unwrap_or("STANDARD")
+}
+
+/// Compare HeadObject output with what should be stored. `None` skips that check.
+fn check_object(head: &ObjectSummary, size: Option<u64>, class: Option<&str>) -> Result<()> {
+    if let Some(size) = size {
+        if head.size_bytes != size {
+            bail!("{} is stored with {} bytes instead of {}", head.key, head.size_bytes, size);
+        }
+    }
+    let stored = class_name(head.storage_class.as_deref());
+    if let Some(class) = class {
+        if stored != class {
+            bail!("{} is stored as {} instead of {}", head.key, stored, class);
+        }
+    }
+    Ok(())
+}
+
+/// Check the objects of an upload. `original` is also read back when it's at most
+/// `round_trip_max_bytes` and can be downloaded without a restore; with `source`
+/// (originals stored as the source file), it must decrypt to that file.
+pub async fn verify_upload(
+    storage: &Storage,
+    settings: UploadVerification,
+    original: &ExpectedObject<'_>,
+    others: &[ExpectedObject<'_>],
+    uploaded: &[u8],
+    vault_key: &[u8; 32],
+    source: Option<&Path>,
+) -> Result<Verification> {
+    let mut level = Verification::Unverified;
+
+    if settings.head_check {
+        for object in std::iter::once(original).chain(others) {
+            verify_object(storage, object).await?;
+        }
+        level = Verification::Head;
+    }
+
+    let archived = matches!(original.class, Some(StorageClass::DeepArchive | StorageClass::Glacier));
+    if settings.round_trip_max_bytes > 0 && uploaded.len() as u64 <= settings.round_trip_max_bytes && !archived {
+        let stored = storage.download_file(original.key).await?;
+        if stored != uploaded {
+            bail!("{} downloads with different content than was uploaded", original.key);
+        }
+        let plaintext = crypto::decrypt(&stored, vault_key)
+            .with_context(|| format!("{} does not decrypt with the vault key", original.key))?;
+        if let Some(source) = source {
+            if plaintext != std::fs::read(source).context("Failed to read source file")? {
+                bail!("{} does not decrypt to the source file", original.key);
+            }
+        }
+        level = Verification::RoundTrip;
+    }
+
+    Ok(level)
+}
+
+/// HeadObject check of a single uploaded object
+pub async fn verify_object(storage: &Storage, object: &ExpectedObject<'_>) -> Result<()> {
+    let head = storage.head_object_info(object.key).await?;
+    check_object(&head, Some(object.size), Some(class_name(object.class.map(|c| c.as_str()))))
+}
+
+/// Result of checking existing photos
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+    pub verified_count: u32,
+    pub failed: Vec<VerifyFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyFailure {
+    pub id: String,
+    pub filename: String,
+    pub error: String,
+}
+
+/// A stored object of a photo: key and recorded size
+type StoredObject = (String, Option<i64>);
+
+struct VerifyTarget {
+    id: String,
+    filename: String,
+    s3_key: String,
+    tier: String,
+    size_bytes: Option<i64>,
+    /// Thumbnail, display copy and motion video, which are always in Glacier IR
+    others: Vec<StoredObject>,
+}
+
+/// Photos in the vault that haven't been verified (or only `ids` of them)
+fn load_unverified(conn: &Connection, ids: Option<&[String]>) -> rusqlite::Result<Vec<VerifyTarget>> {
+    // Passed as one JSON array to stay clear of SQLite's parameter limit
+    let ids_json = ids.map(|ids| serde_json::to_string(ids).unwrap_or_default());
+    let mut stmt = conn.prepare(
+        "SELECT id, filename, s3_key, tier, size_bytes, thumbnail_key, thumbnail_size_bytes,
+                display_key, display_size_bytes, motion_key, motion_size_bytes
+         FROM photos
+         WHERE deleted_at IS NULL AND (verification IS NULL OR verification = 'Unverified')
+           AND (?1 IS NULL OR id IN (SELECT value FROM json_each(?1)))",
+    )?;
+    let rows = stmt.query_map([ids_json], |row| {
+        let mut others = Vec::new();
+        for (key, size) in [(5, 6), (7, 8), (9, 10)] {
+            // Missing thumbnails are stored as '' rather than NULL
+            if let Some(key) = row.get::<_, Option<String>>(key)?.filter(|k| !k.is_empty()) {
+                others.push((key, row.get(size)?));
+            }
+        }
+        Ok(VerifyTarget {
+            id: row.get(0)?,
+            filename: row.get(1)?,
+            s3_key: row.get(2)?,
+            tier: row.get(3)?,
+            size_bytes: row.get(4)?,
+            others,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Storage class an existing original must have. Standard originals may have been
+/// moved by the lifecycle rule since, so their class isn't checked.
+fn tier_class(tier: &str) -> Option<&'static str> {
+    match tier {
+        "DeepArchive" => Some("DEEP_ARCHIVE"),
+        "GlacierIR" => Some("GLACIER_IR"),
+        _ => None,
+    }
+}
+
+/// HeadObject every object of a photo
+async fn check_target(storage: &Storage, target: &VerifyTarget) -> Result<()> {
+    let head = storage.head_object_info(&target.s3_key).await?;
+    check_object(&head, target.size_bytes.map(|s| s as u64), tier_class(&target.tier))?;
+    for (key, size) in &target.others {
+        let head = storage.head_object_info(key).await?;
+        check_object(&head, size.map(|s| s as u64), Some(StorageClass::GlacierIr.as_str()))?;
+    }
+    Ok(())
+}
+
+/// HeadObject the objects of unverified photos (all, or `ids`) and record the
+/// ones that are stored as expected
+#[tauri::command]
+pub async fn verify_photos(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    ids: Option<Vec<String>>,
+) -> Result<VerifyReport, String> {
+    let storage = state
+        .storage
+        .lock()
+        .await
+        .as_ref()
+        .ok_or("Storage not initialized")?
+        .clone();
+
+    let targets = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        load_unverified(conn, ids.as_deref()).map_err(|e| e.to_string())?
+    };
+
+    let mut report = VerifyReport::default();
+    let mut verified = Vec::new();
+    for target in targets {
+        match check_target(&storage, &target).await {
+            Ok(()) => verified.push(target.id),
+            Err(e) => report.failed.push(VerifyFailure {
+                id: target.id,
+                filename: target.filename,
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    let now = chrono::Utc::now().to_rfc3339();
+    for id in &verified {
+        report.verified_count += conn
+            .execute(
+                "UPDATE photos SET verification = ?1, verified_at = ?2 WHERE id = ?3",
+                rusqlite::params![Verification::Head.as_str(), now, id],
+            )
+            .map_err(|e| e.to_string())? as u32;
+    }
+
+    drop(db_guard);
+
+    if !verified.is_empty() {
+        crate::recovery::refresh_sidecars(&app, verified);
+        crate::sync_daemon::notify_local_change(&app).await;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(size_bytes: u64, storage_class: Option<&str>) -> ObjectSummary {
+        ObjectSummary {
+            key: "originals/p1.webp".to_string(),
+            size_bytes,
+            storage_class: storage_class.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_check_object() {
+        // HeadObject leaves out the class of STANDARD objects
+        assert!(check_object(&head(10, None), Some(10), Some("STANDARD")).is_ok());
+        assert!(check_object(&head(10, Some("GLACIER_IR")), Some(10), Some("GLACIER_IR")).is_ok());
+        assert!(check_object(&head(9, Some("GLACIER_IR")), Some(10), Some("GLACIER_IR")).is_err());
+        assert!(check_object(&head(10, None), Some(10), Some("DEEP_ARCHIVE")).is_err());
+
+        // Existing Standard originals may have been transitioned by the lifecycle rule
+        assert!(check_object(&head(10, Some("DEEP_ARCHIVE")), Some(10), tier_class("Standard")).is_ok());
+        assert!(check_object(&head(10, Some("DEEP_ARCHIVE")), None, None).is_ok());
+    }
+
+    #[test]
+    fn test_load_unverified() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO photos (id, filename, s3_key, tier) VALUES ('p1', 'a.jpg', 'k1', 'Standard');
+             INSERT INTO photos (id, filename, s3_key, tier, verification) VALUES ('p2', 'b.jpg', 'k2', 'Standard', 'Head');
+             INSERT INTO photos (id, filename, s3_key, tier, verification) VALUES ('p3', 'c.jpg', 'k3', 'Standard', 'Unverified');
+             INSERT INTO photos (id, filename, s3_key, tier, deleted_at)
+             VALUES ('p4', 'd.jpg', 'k4', 'Standard', '2024-01-01T00:00:00+00:00');
+             UPDATE photos SET thumbnail_key = 't3', thumbnail_size_bytes = 40, motion_key = 'm3' WHERE id = 'p3';",
+        )
+        .unwrap();
+
+        let ids = |targets: Vec<VerifyTarget>| targets.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(ids(load_unverified(&conn, None).unwrap()), vec!["p1", "p3"]);
+        assert!(load_unverified(&conn, Some(&["p2".to_string()])).unwrap().is_empty());
+        assert!(load_unverified(&conn, Some(&[])).unwrap().is_empty());
+
+        let targets = load_unverified(&conn, Some(&["p3".to_string(), "p4".to_string()])).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(
+            targets[0].others,
+            vec![("t3".to_string(), Some(40)), ("m3".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn test_load_unverified_audio() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO photos (id, filename, s3_key, thumbnail_key, tier, media_type, size_bytes)
+             VALUES ('a1', 'song.mp3', 'k1', '', 'Standard', 'audio', 100);",
+        )
+        .unwrap();
+
+        let targets = load_unverified(&conn, None).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].s3_key, "k1");
+        assert!(targets[0].others.is_empty());
+    }
+}
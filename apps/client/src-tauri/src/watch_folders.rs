@@ -0,0 +1,1034 @@
+//! Watch folders
+//!
+//! Directories (e.g. a camera-sync or phone backup folder) that are scanned for
+//! new media and fed into the `UploadManager`. Watch folders are stored in the
+//! vault DB but not synced, since paths are specific to this device.
+//! - Watches each folder for file system events and only queues a changed file once
+//!   it had no events for a few seconds and its size and modification time stayed
+//!   the same, so files still being copied are left alone. A slow full rescan
+//!   catches anything the watcher missed (network shares, overflowed event queues).
+//! - Include/exclude glob patterns are matched against the path relative to the folder
+//! - Every picked up file is recorded, so nothing is queued twice across restarts
+//! - Optionally deletes or moves the source (and a paired Live Photo video) once
+//!   the stored objects were verified after upload and the source still matches
+
+use crate::file_filter;
+use crate::motion_photo::MotionSource;
+use crate::upload_manager::{self, UploadStatus};
+use crate::vault::VaultConfig;
+use crate::verification;
+use crate::AppState;
+use glob::Pattern;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::{mpsc, Mutex, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// How often watch folders are fully rescanned while file system events are available
+const RESCAN_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Full rescan interval when the platform watcher couldn't be started
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A changed file is queued once it had no events for this long
+const SETTLE_DELAY: Duration = Duration::from_secs(5);
+
+/// How often changed files are checked for being settled
+const CHANGE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often finished uploads are resolved
+const UPLOAD_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// What happens to a source file once its upload is verified
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceAction {
+    #[default]
+    Keep,
+    Delete,
+    /// Move into `move_to`, keeping the path relative to the watch folder
+    Move,
+}
+
+impl SourceAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            SourceAction::Keep => "keep",
+            SourceAction::Delete => "delete",
+            SourceAction::Move => "move",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "delete" => SourceAction::Delete,
+            "move" => SourceAction::Move,
+            _ => SourceAction::Keep,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchFolder {
+    pub id: String,
+    pub path: String,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub after_upload: SourceAction,
+    pub move_to: Option<String>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WatchFolderPayload {
+    pub path: String,
+    /// Only files matching one of these are picked up (all files if empty)
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub after_upload: SourceAction,
+    pub move_to: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl WatchFolderPayload {
+    fn validate(&self) -> Result<(), String> {
+        let path = Path::new(&self.path);
+        if !path.is_dir() {
+            return Err(format!("Not a folder: {}", self.path));
+        }
+        for pattern in self.include.iter().chain(&self.exclude) {
+            Pattern::new(pattern).map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+        }
+        if self.after_upload == SourceAction::Move {
+            let move_to = self
+                .move_to
+                .as_deref()
+                .ok_or("A destination folder is required to move uploaded files")?;
+            // Moving into the watched folder would pick the files up again. Compared
+            // resolved, so symlinks, `..` and different casing can't hide it.
+            let watched = path.canonicalize().map_err(|e| format!("Can't resolve {}: {}", self.path, e))?;
+            if resolve_path(Path::new(move_to)).starts_with(&watched) {
+                return Err("The destination folder can't be inside the watched folder".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Canonicalize a path that may not exist yet: its longest existing ancestor is
+/// resolved and the rest appended, applying `..` lexically
+fn resolve_path(path: &Path) -> PathBuf {
+    let mut existing = path.to_path_buf();
+    let mut rest = Vec::new();
+    let base = loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            break resolved;
+        }
+        match (existing.components().next_back(), existing.parent()) {
+            (Some(last), Some(parent)) => {
+                rest.push(last.as_os_str().to_os_string());
+                existing = parent.to_path_buf();
+            }
+            // Nothing of it exists; compare it as given
+            _ => return path.to_path_buf(),
+        }
+    };
+
+    let mut resolved = base;
+    for component in rest.iter().rev().flat_map(|name| Path::new(name).components()) {
+        match component {
+            std::path::Component::ParentDir => {
+                resolved.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Size and modification time of a file, to tell when it stops changing
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    size: u64,
+    modified_at: Option<i64>,
+}
+
+impl FileStamp {
+    fn of(meta: &std::fs::Metadata) -> Self {
+        FileStamp {
+            size: meta.len(),
+            modified_at: upload_manager::modified_millis(meta),
+        }
+    }
+}
+
+/// Compiled patterns of a watch folder
+struct FolderFilter {
+    root: PathBuf,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl FolderFilter {
+    fn new(folder: &WatchFolder) -> Self {
+        FolderFilter {
+            root: PathBuf::from(&folder.path),
+            include: file_filter::compile_globs(&folder.include),
+            exclude: file_filter::compile_globs(&folder.exclude),
+        }
+    }
+
+    /// Whether a path inside the folder is a supported media file passing its patterns
+    fn accepts(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root)
+            .map(|relative| file_filter::matches_globs(relative, &self.include, &self.exclude))
+            .unwrap_or(false)
+            && file_filter::is_supported_media(path)
+    }
+}
+
+/// Supported media files in a folder that pass its patterns
+fn scan_folder(folder: &WatchFolder) -> Vec<(PathBuf, FileStamp)> {
+    let filter = FolderFilter::new(folder);
+
+    walkdir::WalkDir::new(&filter.root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| filter.accepts(e.path()))
+        .filter_map(|e| {
+            let stamp = FileStamp::of(&e.metadata().ok()?);
+            Some((e.into_path(), stamp))
+        })
+        .collect()
+}
+
+fn load_folders(conn: &Connection) -> rusqlite::Result<Vec<WatchFolder>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, path, include, exclude, after_upload, move_to, enabled, created_at
+         FROM watch_folders ORDER BY created_at",
+    )?;
+    let folders = stmt.query_map([], |row| {
+        Ok(WatchFolder {
+            id: row.get(0)?,
+            path: row.get(1)?,
+            include: serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or_default(),
+            exclude: serde_json::from_str(&row.get::<_, String>(3)?).unwrap_or_default(),
+            after_upload: SourceAction::parse(&row.get::<_, String>(4)?),
+            move_to: row.get(5)?,
+            enabled: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    })?;
+    folders.collect()
+}
+
+fn load_folder(conn: &Connection, id: &str) -> Result<WatchFolder, String> {
+    load_folders(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|f| f.id == id)
+        .ok_or_else(|| format!("Watch folder not found: {}", id))
+}
+
+/// Stamps of the files already picked up from a folder
+fn load_ingested(conn: &Connection, folder_id: &str) -> rusqlite::Result<HashMap<PathBuf, FileStamp>> {
+    let mut stmt = conn.prepare("SELECT path, size_bytes, modified_at FROM watch_ingested WHERE folder_id = ?1")?;
+    let rows = stmt.query_map([folder_id], |row| {
+        Ok((
+            PathBuf::from(row.get::<_, String>(0)?),
+            FileStamp {
+                size: row.get::<_, i64>(1)?.max(0) as u64,
+                modified_at: row.get(2)?,
+            },
+        ))
+    })?;
+    rows.collect()
+}
+
+fn record_ingested(
+    conn: &Connection,
+    folder_id: &str,
+    path: &Path,
+    stamp: FileStamp,
+    upload_id: Option<&str>,
+    state: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO watch_ingested (folder_id, path, size_bytes, modified_at, upload_id, state, ingested_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(folder_id, path) DO UPDATE SET
+            size_bytes = excluded.size_bytes, modified_at = excluded.modified_at,
+            upload_id = excluded.upload_id, state = excluded.state, ingested_at = excluded.ingested_at",
+        rusqlite::params![
+            folder_id,
+            path.to_string_lossy(),
+            stamp.size as i64,
+            stamp.modified_at,
+            upload_id,
+            state,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// A picked up file whose upload hasn't finished yet
+struct QueuedFile {
+    folder_id: String,
+    path: PathBuf,
+    upload_id: String,
+}
+
+fn load_queued(conn: &Connection) -> rusqlite::Result<Vec<QueuedFile>> {
+    let mut stmt = conn.prepare(
+        "SELECT folder_id, path, upload_id FROM watch_ingested
+         WHERE state = 'queued' AND upload_id IS NOT NULL",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(QueuedFile {
+            folder_id: row.get(0)?,
+            path: PathBuf::from(row.get::<_, String>(1)?),
+            upload_id: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Live Photo videos picked up with a queued still (uploaded as its motion component)
+fn load_paired(conn: &Connection, folder_id: &str, upload_id: &str) -> rusqlite::Result<Vec<PathBuf>> {
+    let mut stmt = conn.prepare(
+        "SELECT path FROM watch_ingested WHERE folder_id = ?1 AND upload_id = ?2 AND state = 'paired'",
+    )?;
+    let rows = stmt.query_map([folder_id, upload_id], |row| Ok(PathBuf::from(row.get::<_, String>(0)?)))?;
+    rows.collect()
+}
+
+fn set_state(conn: &Connection, folder_id: &str, path: &Path, state: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE watch_ingested SET state = ?3 WHERE folder_id = ?1 AND path = ?2",
+        rusqlite::params![folder_id, path.to_string_lossy(), state],
+    )?;
+    Ok(())
+}
+
+/// What the vault recorded for the photo of a picked up file
+struct StoredPhoto {
+    content_hash: Option<String>,
+    verification: Option<String>,
+    motion_key: Option<String>,
+}
+
+/// Whether the stored objects of the photo were checked after upload and its content
+/// hash still matches the source, i.e. the source can safely be removed
+fn verify_upload(stored: &StoredPhoto, source: &Path, hash_key: &[u8; 32]) -> bool {
+    if !verification::is_verified(stored.verification.as_deref()) {
+        return false;
+    }
+    match stored.content_hash.as_deref() {
+        Some(stored_hash) => crate::crypto::content_hash(source, hash_key)
+            .map(|hash| hash == stored_hash)
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Move `source` to the same relative path under `move_to`
+fn move_source(source: &Path, folder: &WatchFolder, move_to: &Path) -> anyhow::Result<()> {
+    let relative = source.strip_prefix(&folder.path)?;
+    let target = move_to.join(relative);
+    if target.exists() {
+        anyhow::bail!("{:?} already exists", target);
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // rename fails across file systems (e.g. SD card to internal disk)
+    if std::fs::rename(source, &target).is_err() {
+        std::fs::copy(source, &target)?;
+        std::fs::remove_file(source)?;
+    }
+    Ok(())
+}
+
+/// Payload of `watch:queued`, emitted when files from a watch folder were queued
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchQueuedEvent {
+    pub folder_id: String,
+    pub count: usize,
+}
+
+/// Handle to the watch folder watcher of the active vault.
+/// The task is aborted when the handle is dropped.
+pub struct FolderWatcher {
+    rescan: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+impl FolderWatcher {
+    pub fn start(app_handle: AppHandle, db: Arc<Mutex<Option<Connection>>>, config: VaultConfig) -> Self {
+        let rescan = Arc::new(Notify::new());
+        let worker = WatchWorker {
+            app_handle,
+            db,
+            config,
+            folders: Vec::new(),
+            watcher: None,
+            watched: HashSet::new(),
+            pending: HashMap::new(),
+        };
+        let task = tokio::spawn(worker.run(Arc::clone(&rescan)));
+        Self { rescan, task }
+    }
+
+    /// Reload the folders and rescan them now, e.g. after they were edited
+    pub fn rescan(&self) {
+        self.rescan.notify_one();
+    }
+
+    /// Stop the task and wait until it has finished (see `SyncDaemon::stop`)
+    pub async fn stop(mut self) {
+        self.task.abort();
+        (&mut self.task).await.ok();
+    }
+}
+
+impl Drop for FolderWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn notify_rescan(app: &AppHandle) {
+    if let Some(state) = app.try_state::<crate::FolderWatcherState>() {
+        if let Some(watcher) = state.watcher.lock().await.as_ref() {
+            watcher.rescan();
+        }
+    }
+}
+
+/// A changed file, queued once it stops changing
+struct PendingFile {
+    folder_id: String,
+    stamp: FileStamp,
+    changed_at: Instant,
+}
+
+struct WatchWorker {
+    app_handle: AppHandle,
+    db: Arc<Mutex<Option<Connection>>>,
+    config: VaultConfig,
+    /// Enabled folders with their patterns, reloaded on every full rescan
+    folders: Vec<(WatchFolder, FolderFilter)>,
+    watcher: Option<RecommendedWatcher>,
+    watched: HashSet<PathBuf>,
+    pending: HashMap<PathBuf, PendingFile>,
+}
+
+impl WatchWorker {
+    async fn run(mut self, rescan: Arc<Notify>) {
+        let (event_tx, mut events) = mpsc::unbounded_channel();
+        self.watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                event_tx.send(event).ok();
+            }
+        }) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                log::warn!("[Watch] File system events unavailable, polling instead: {}", e);
+                None
+            }
+        };
+
+        let rescan_every = if self.watcher.is_some() { RESCAN_INTERVAL } else { POLL_INTERVAL };
+        let mut rescan_interval = tokio::time::interval(rescan_every);
+        let mut change_interval = tokio::time::interval(CHANGE_CHECK_INTERVAL);
+        let mut upload_interval = tokio::time::interval(UPLOAD_CHECK_INTERVAL);
+        for interval in [&mut rescan_interval, &mut change_interval, &mut upload_interval] {
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        }
+
+        loop {
+            tokio::select! {
+                _ = rescan_interval.tick() => {
+                    if let Err(e) = self.rescan().await {
+                        log::warn!("[Watch] Scan failed: {}", e);
+                    }
+                }
+                _ = rescan.notified() => {
+                    if let Err(e) = self.rescan().await {
+                        log::warn!("[Watch] Scan failed: {}", e);
+                    }
+                }
+                Some(event) = events.recv() => self.record_event(event),
+                _ = change_interval.tick() => {
+                    if let Err(e) = self.queue_settled().await {
+                        log::warn!("[Watch] Failed to queue files: {}", e);
+                    }
+                }
+                _ = upload_interval.tick() => {
+                    if let Err(e) = self.settle_uploads().await {
+                        log::warn!("[Watch] Failed to check uploads: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reload the folders, update the watched paths and walk every folder for files
+    /// that were never picked up (or changed since)
+    async fn rescan(&mut self) -> anyhow::Result<()> {
+        let folders = {
+            let db_guard = self.db.lock().await;
+            let Some(conn) = db_guard.as_ref() else {
+                return Ok(());
+            };
+            load_folders(conn)?
+        };
+        self.folders = folders
+            .into_iter()
+            .filter(|f| f.enabled)
+            .map(|f| {
+                let filter = FolderFilter::new(&f);
+                (f, filter)
+            })
+            .collect();
+        self.update_watches();
+
+        let folder_ids: HashSet<&str> = self.folders.iter().map(|(f, _)| f.id.as_str()).collect();
+        self.pending.retain(|_, p| folder_ids.contains(p.folder_id.as_str()));
+
+        for (folder, _) in &self.folders {
+            let scan_target = folder.clone();
+            let files = tokio::task::spawn_blocking(move || scan_folder(&scan_target)).await?;
+
+            let ingested = {
+                let db_guard = self.db.lock().await;
+                let Some(conn) = db_guard.as_ref() else {
+                    return Ok(());
+                };
+                load_ingested(conn, &folder.id)?
+            };
+
+            for (path, stamp) in files {
+                if ingested.get(&path) != Some(&stamp) && !self.pending.contains_key(&path) {
+                    self.pending.insert(
+                        path,
+                        PendingFile {
+                            folder_id: folder.id.clone(),
+                            stamp,
+                            changed_at: Instant::now(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Watch the enabled folders and stop watching removed or disabled ones
+    fn update_watches(&mut self) {
+        let Some(watcher) = self.watcher.as_mut() else {
+            return;
+        };
+        let wanted: HashSet<PathBuf> = self.folders.iter().map(|(_, filter)| filter.root.clone()).collect();
+
+        for path in self.watched.difference(&wanted) {
+            watcher.unwatch(path).ok();
+        }
+        self.watched.retain(|path| wanted.contains(path));
+        for path in wanted {
+            if self.watched.contains(&path) {
+                continue;
+            }
+            // Unwatched folders are still picked up by the periodic rescan
+            match watcher.watch(&path, RecursiveMode::Recursive) {
+                Ok(()) => {
+                    self.watched.insert(path);
+                }
+                Err(e) => log::warn!("[Watch] Failed to watch {:?}: {}", path, e),
+            }
+        }
+    }
+
+    /// Note files created or modified in a watched folder
+    fn record_event(&mut self, event: notify::Event) {
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        for path in event.paths {
+            let Some((folder, _)) = self.folders.iter().find(|(_, filter)| filter.accepts(&path)) else {
+                continue;
+            };
+            let Ok(meta) = std::fs::metadata(&path) else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            self.pending.insert(
+                path,
+                PendingFile {
+                    folder_id: folder.id.clone(),
+                    stamp: FileStamp::of(&meta),
+                    changed_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    /// Queue pending files that had no events for `SETTLE_DELAY` and haven't changed since
+    async fn queue_settled(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let due: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| now.duration_since(p.changed_at) >= SETTLE_DELAY)
+            .map(|(path, _)| path.clone())
+            .collect();
+        if due.is_empty() {
+            return Ok(());
+        }
+
+        let mut settled: HashMap<String, Vec<(PathBuf, FileStamp)>> = HashMap::new();
+        for path in due {
+            let Ok(meta) = std::fs::metadata(&path) else {
+                self.pending.remove(&path);
+                continue;
+            };
+            let stamp = FileStamp::of(&meta);
+            let Some(pending) = self.pending.get_mut(&path) else {
+                continue;
+            };
+            if pending.stamp != stamp {
+                // Still being written without events (e.g. on a network share)
+                pending.stamp = stamp;
+                pending.changed_at = now;
+                continue;
+            }
+            let folder_id = pending.folder_id.clone();
+            self.pending.remove(&path);
+            settled.entry(folder_id).or_default().push((path, stamp));
+        }
+
+        for (folder_id, files) in settled {
+            let Some((folder, _)) = self.folders.iter().find(|(f, _)| f.id == folder_id) else {
+                continue;
+            };
+            let ingested = {
+                let db_guard = self.db.lock().await;
+                let Some(conn) = db_guard.as_ref() else {
+                    return Ok(());
+                };
+                load_ingested(conn, &folder.id)?
+            };
+            let files: Vec<(PathBuf, FileStamp)> = files
+                .into_iter()
+                .filter(|(path, stamp)| ingested.get(path) != Some(stamp))
+                .collect();
+            if files.is_empty() {
+                continue;
+            }
+
+            let folder = folder.clone();
+            if !self.enqueue(&folder, files.clone()).await? {
+                // The upload manager isn't running yet; try again later
+                for (path, stamp) in files {
+                    self.pending.insert(
+                        path,
+                        PendingFile {
+                            folder_id: folder.id.clone(),
+                            stamp,
+                            changed_at: now,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue stable files and record them as picked up. Returns false if the upload
+    /// manager of this vault isn't running.
+    async fn enqueue(&self, folder: &WatchFolder, files: Vec<(PathBuf, FileStamp)>) -> anyhow::Result<bool> {
+        let upload_state = self.app_handle.state::<crate::UploadManagerState>();
+        let manager_guard = upload_state.manager.lock().await;
+        // Wait until the upload manager of this vault is running
+        let Some(manager) = manager_guard
+            .as_ref()
+            .filter(|m| m.vault_id() == Some(self.config.id.as_str()))
+        else {
+            return Ok(false);
+        };
+
+        let paths: Vec<PathBuf> = files.iter().map(|(p, _)| p.clone()).collect();
+        let batch_name = Path::new(&folder.path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| folder.path.clone());
+        let (items, _) = manager
+            .add_files(paths, false, None, false, Some(batch_name))
+            .await?;
+        let queued: HashMap<&PathBuf, &upload_manager::UploadItem> = items.iter().map(|i| (&i.path, i)).collect();
+        // Live Photo videos are uploaded as part of their still
+        let paired: HashMap<&PathBuf, &upload_manager::UploadItem> = items
+            .iter()
+            .filter_map(|i| match &i.motion {
+                Some(MotionSource::Companion { path }) => Some((path, i)),
+                _ => None,
+            })
+            .collect();
+
+        // Automatic imports don't list duplicates; they're already in the vault or queue
+        for item in items.iter().filter(|i| matches!(i.status, UploadStatus::Duplicate { .. })) {
+            manager.remove_item(&item.id).await;
+        }
+        manager.start_processing().await?;
+        drop(manager_guard);
+
+        let db_guard = self.db.lock().await;
+        let Some(conn) = db_guard.as_ref() else {
+            return Ok(true);
+        };
+        let mut count = 0;
+        for (path, stamp) in &files {
+            let (upload_id, state) = match queued.get(path) {
+                Some(item) if matches!(item.status, UploadStatus::Duplicate { .. }) => (None, "duplicate"),
+                Some(item) => {
+                    count += 1;
+                    (Some(item.id.as_str()), "queued")
+                }
+                None => match paired.get(path) {
+                    Some(item) => (Some(item.id.as_str()), "paired"),
+                    // Rejected by the upload manager (reported via `upload:error`)
+                    None => (None, "failed"),
+                },
+            };
+            record_ingested(conn, &folder.id, path, *stamp, upload_id, state)?;
+        }
+
+        if count > 0 {
+            log::info!("[Watch] Queued {} new files from {}", count, folder.path);
+            self.app_handle
+                .emit(
+                    "watch:queued",
+                    WatchQueuedEvent {
+                        folder_id: folder.id.clone(),
+                        count,
+                    },
+                )
+                .ok();
+        }
+        Ok(true)
+    }
+
+    /// Resolve finished uploads of picked up files, then apply the folder's
+    /// after-upload action to verified ones
+    async fn settle_uploads(&self) -> anyhow::Result<()> {
+        let (queued, folders) = {
+            let db_guard = self.db.lock().await;
+            let Some(conn) = db_guard.as_ref() else {
+                return Ok(());
+            };
+            (load_queued(conn)?, load_folders(conn)?)
+        };
+        if queued.is_empty() {
+            return Ok(());
+        }
+
+        let statuses: Vec<Option<UploadStatus>> = {
+            let upload_state = self.app_handle.state::<crate::UploadManagerState>();
+            let manager_guard = upload_state.manager.lock().await;
+            let Some(manager) = manager_guard
+                .as_ref()
+                .filter(|m| m.vault_id() == Some(self.config.id.as_str()))
+            else {
+                return Ok(());
+            };
+            let mut statuses = Vec::new();
+            for file in &queued {
+                statuses.push(manager.item_status(&file.upload_id).await);
+            }
+            statuses
+        };
+
+        let hash_key = upload_manager::content_hash_key(&self.config);
+        for (file, status) in queued.iter().zip(statuses) {
+            let finished = match status {
+                Some(UploadStatus::Completed) | None => true,
+                Some(UploadStatus::Failed { .. } | UploadStatus::Cancelled) => {
+                    self.set_states(file, "failed").await?;
+                    continue;
+                }
+                Some(_) => false,
+            };
+            if !finished {
+                continue;
+            }
+
+            // Items cleared from the queue count as uploaded if the photo exists
+            let (stored, paired) = {
+                let db_guard = self.db.lock().await;
+                let Some(conn) = db_guard.as_ref() else {
+                    return Ok(());
+                };
+                use rusqlite::OptionalExtension;
+                let stored = conn
+                    .query_row(
+                        "SELECT content_hash, verification, motion_key FROM photos WHERE id = ?1",
+                        [&file.upload_id],
+                        |row| {
+                            Ok(StoredPhoto {
+                                content_hash: row.get(0)?,
+                                verification: row.get(1)?,
+                                motion_key: row.get(2)?,
+                            })
+                        },
+                    )
+                    .optional()?;
+                (stored, load_paired(conn, &file.folder_id, &file.upload_id)?)
+            };
+            let Some(stored) = stored else {
+                self.set_states(file, "failed").await?;
+                continue;
+            };
+
+            if let Some(folder) = folders.iter().find(|f| f.id == file.folder_id) {
+                if folder.after_upload != SourceAction::Keep && file.path.exists() {
+                    let (folder, path) = (folder.clone(), file.path.clone());
+                    let result = tokio::task::spawn_blocking(move || {
+                        let verified = hash_key
+                            .map(|key| verify_upload(&stored, &path, &key))
+                            .unwrap_or(false);
+                        if !verified {
+                            anyhow::bail!("upload could not be verified, keeping source");
+                        }
+                        // A Live Photo video goes with its still once it's stored as its motion component
+                        let mut sources = vec![path];
+                        if stored.motion_key.is_some() {
+                            sources.extend(paired.into_iter().filter(|p| p.exists()));
+                        }
+                        for source in &sources {
+                            match (folder.after_upload, folder.move_to.as_deref()) {
+                                (SourceAction::Delete, _) => std::fs::remove_file(source)?,
+                                (SourceAction::Move, Some(move_to)) => {
+                                    move_source(source, &folder, Path::new(move_to))?
+                                }
+                                _ => {}
+                            }
+                        }
+                        Ok(())
+                    })
+                    .await?;
+                    if let Err(e) = result {
+                        log::warn!("[Watch] {:?}: {}", file.path, e);
+                    }
+                }
+            }
+
+            self.set_states(file, "uploaded").await?;
+        }
+        Ok(())
+    }
+
+    /// Set the state of a picked up file and of the Live Photo videos paired with it
+    async fn set_states(&self, file: &QueuedFile, state: &str) -> anyhow::Result<()> {
+        let db_guard = self.db.lock().await;
+        if let Some(conn) = db_guard.as_ref() {
+            for path in load_paired(conn, &file.folder_id, &file.upload_id)? {
+                set_state(conn, &file.folder_id, &path, state)?;
+            }
+            set_state(conn, &file.folder_id, &file.path, state)?;
+        }
+        Ok(())
+    }
+}
+
+// ============ Commands ============
+
+#[tauri::command]
+pub async fn get_watch_folders(state: State<'_, AppState>) -> Result<Vec<WatchFolder>, String> {
+    let db_guard = state.db.lock().await;
+    let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+    load_folders(conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_watch_folder(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    payload: WatchFolderPayload,
+) -> Result<WatchFolder, String> {
+    payload.validate()?;
+
+    let folder = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        let id = uuid::Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO watch_folders (id, path, include, exclude, after_upload, move_to, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                id,
+                payload.path,
+                serde_json::to_string(&payload.include).map_err(|e| e.to_string())?,
+                serde_json::to_string(&payload.exclude).map_err(|e| e.to_string())?,
+                payload.after_upload.as_str(),
+                payload.move_to,
+                payload.enabled,
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        load_folder(conn, &id)?
+    };
+
+    notify_rescan(&app).await;
+    Ok(folder)
+}
+
+#[tauri::command]
+pub async fn update_watch_folder(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    payload: WatchFolderPayload,
+) -> Result<WatchFolder, String> {
+    payload.validate()?;
+
+    let folder = {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        let updated = conn
+            .execute(
+                "UPDATE watch_folders SET path = ?2, include = ?3, exclude = ?4, after_upload = ?5,
+                                          move_to = ?6, enabled = ?7
+                 WHERE id = ?1",
+                rusqlite::params![
+                    id,
+                    payload.path,
+                    serde_json::to_string(&payload.include).map_err(|e| e.to_string())?,
+                    serde_json::to_string(&payload.exclude).map_err(|e| e.to_string())?,
+                    payload.after_upload.as_str(),
+                    payload.move_to,
+                    payload.enabled,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(format!("Watch folder not found: {}", id));
+        }
+        load_folder(conn, &id)?
+    };
+
+    notify_rescan(&app).await;
+    Ok(folder)
+}
+
+/// Stop watching a folder. Its files are left untouched.
+#[tauri::command]
+pub async fn remove_watch_folder(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<(), String> {
+    {
+        let db_guard = state.db.lock().await;
+        let conn = db_guard.as_ref().ok_or("DB not initialized")?;
+        conn.execute("DELETE FROM watch_ingested WHERE folder_id = ?1", [&id])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM watch_folders WHERE id = ?1", [&id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    notify_rescan(&app).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_ingested() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::migrate(&conn).unwrap();
+        let path = Path::new("/cards/a.jpg");
+        let stamp = FileStamp {
+            size: 10,
+            modified_at: Some(1),
+        };
+
+        record_ingested(&conn, "f1", path, stamp, Some("u1"), "queued").unwrap();
+        assert_eq!(load_queued(&conn).unwrap().len(), 1);
+
+        let video = Path::new("/cards/a.mov");
+        record_ingested(&conn, "f1", video, stamp, Some("u1"), "paired").unwrap();
+        assert_eq!(load_queued(&conn).unwrap().len(), 1);
+        assert_eq!(load_paired(&conn, "f1", "u1").unwrap(), vec![video.to_path_buf()]);
+
+        set_state(&conn, "f1", path, "uploaded").unwrap();
+        assert!(load_queued(&conn).unwrap().is_empty());
+        assert_eq!(load_ingested(&conn, "f1").unwrap().get(path), Some(&stamp));
+    }
+
+    #[test]
+    fn test_move_to_inside_watched_folder() {
+        let root = std::env::temp_dir().join(format!("boreal-watch-{}", uuid::Uuid::new_v4()));
+        let watched = root.join("Cards");
+        std::fs::create_dir_all(watched.join("DCIM")).unwrap();
+        let payload = |move_to: PathBuf| WatchFolderPayload {
+            path: watched.to_string_lossy().to_string(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            after_upload: SourceAction::Move,
+            move_to: Some(move_to.to_string_lossy().to_string()),
+            enabled: true,
+        };
+
+        assert!(payload(root.join("Uploaded")).validate().is_ok());
+        assert!(payload(watched.join("Uploaded")).validate().is_err());
+        assert!(payload(watched.join("DCIM/../Uploaded/new")).validate().is_err());
+        assert!(payload(root.join("Other/../Cards/Uploaded")).validate().is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&watched, root.join("link")).unwrap();
+            assert!(payload(root.join("link/Uploaded")).validate().is_err());
+        }
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_folder_filter() {
+        let folder = WatchFolder {
+            id: "f1".to_string(),
+            path: "/cards".to_string(),
+            include: vec!["DCIM/**".to_string()],
+            exclude: vec!["**/.thumbnails/**".to_string()],
+            after_upload: SourceAction::Keep,
+            move_to: None,
+            enabled: true,
+            created_at: String::new(),
+        };
+        let filter = FolderFilter::new(&folder);
+
+        assert!(filter.accepts(Path::new("/cards/DCIM/100CANON/a.JPG")));
+        assert!(!filter.accepts(Path::new("/cards/DCIM/.thumbnails/a.jpg")));
+        assert!(!filter.accepts(Path::new("/cards/MISC/a.jpg")));
+        assert!(!filter.accepts(Path::new("/other/DCIM/a.jpg")));
+    }
+}
@@ -315,6 +315,15 @@ export async function syncManifestUpload(): Promise<void> {
   }
 }
 
+/** Result of `embed_all_photos` across all vaults */
+export interface EmbedAllSummary {
+  embedded: number;
+  skipped: number;
+  not_cached: number;
+  /** [vault id, error] of vaults that couldn't be embedded */
+  failed_vaults: [string, string][];
+}
+
 /**
  * Download and merge the manifest from S3 into local DB.
  * This is called automatically on vault load, but can be triggered manually.
@@ -325,9 +334,12 @@ export async function syncManifestDownload(): Promise<void> {
     await invoke('sync_manifest_download');
 
     // After syncing manifest, embed any cached photos that haven't been embedded yet
-    invoke('embed_all_photos').then((count) => {
-      if (typeof count === 'number' && count > 0) {
-        console.log(`[AI] Embedded ${count} new photos after manifest sync`);
+    invoke<EmbedAllSummary>('embed_all_photos').then((summary) => {
+      if (summary.embedded > 0) {
+        console.log(`[AI] Embedded ${summary.embedded} new photos after manifest sync`);
+      }
+      for (const [vaultId, error] of summary.failed_vaults) {
+        console.debug(`[AI] Embedding skipped for vault ${vaultId}:`, error);
       }
     }).catch((e) => {
       // AI embedding is optional, don't fail the sync
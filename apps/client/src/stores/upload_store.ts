@@ -15,6 +15,7 @@ export type UploadStatus =
   | 'EncryptingThumbnail'
   | { UploadingOriginal: { progress: number } }
   | { UploadingThumbnail: { progress: number } }
+  | 'Verifying'
   | 'Completed'
   | { Failed: { error: string } }
   | 'Cancelled'
@@ -116,6 +117,8 @@ function getStatusLabel(status: UploadStatus): string {
         return 'Encrypting...';
       case 'EncryptingThumbnail':
         return 'Creating thumbnail...';
+      case 'Verifying':
+        return 'Verifying...';
       case 'Completed':
         return 'Done';
       case 'Cancelled':